@@ -3,7 +3,9 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use cw_template::msg::{ExecuteMsg, ExecuteResponse, InstantiateMsg, QueryMsg, QueryResponse};
+use cw_template::msg::{
+    ExecuteMsg, ExecuteResponse, InstantiateMsg, MigrateMsg, QueryMsg, QueryResponse,
+};
 // use cw_template::state::State;
 
 fn main() {
@@ -16,6 +18,7 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryResponse), &out_dir);
     export_schema(&schema_for!(ExecuteResponse), &out_dir);
 }
@@ -1,127 +1,388 @@
-use cosmwasm_std::{Decimal, StdResult};
+use std::convert::TryFrom;
 
-use cosmwasm_std::DepsMut;
+use cosmwasm_std::{Decimal, Deps, Int64, Uint128, Uint256, Uint64};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::Config;
+use crate::error::ContractError;
+use crate::msg::{OraiswapContract, PriceGuard, PriceSourceConfig};
+use crate::state::{Config, PriceCache};
 
+/// uorai in one ORAI.
+const UORAI_PER_ORAI: u128 = 1_000_000;
+/// Base units in one USDT on Oraiswap.
+const UUSDT_PER_USDT: u128 = 1_000_000;
+
+/// A feed of the ORAI price in USD.
+pub trait PriceSource {
+    /// Returns the USD price of one ORAI.
+    fn orai_price(&self, deps: Deps) -> Result<Decimal, ContractError>;
+}
+
+/// Converts between uorai and whole USD at the price reported by the configured
+/// [`PriceSource`]s.
 pub struct BandProtocol {
-    orai_per_usd: u128,
+    usd_per_orai: Decimal,
 }
 
 impl BandProtocol {
-    pub const DECIMALS: u8 = 18;
-    pub const ONE_USD: u128 = 1_000_000_000_000_000_000;
-
-    pub fn new(deps: &DepsMut) -> StdResult<Self> {
-        // let querier: SeiQuerier<'_> = SeiQuerier::new(&deps.querier);
-        // let res = querier
-        //     .query_exchange_rates()
-        //     .unwrap_or(ExchangeRatesResponse {
-        //         denom_oracle_exchange_rate_pairs: vec![],
-        //     });
-
-        // let mut orai_per_usd = Self::ONE_USD / 2;
-        // for exratepair in res.denom_oracle_exchange_rate_pairs {
-        //     if exratepair.denom.clone() == "usei" {
-        //         let rate = exratepair.oracle_exchange_rate.exchange_rate;
-        //         orai_per_usd = (Decimal::raw(1000000u128) / rate).to_uint_floor().u128();
-        //     }
-        // }
+    pub fn new(deps: Deps) -> Result<Self, ContractError> {
+        let config = Config::load(deps.storage)?;
+        Self::from_config(deps, &config)
+    }
+
+    /// Like `new`, but when a [`PriceGuard`] is configured the live quote has to stay close
+    /// to a fresh cached price and TWAP.
+    pub fn guarded(deps: Deps, now: u64) -> Result<Self, ContractError> {
         let config = Config::load(deps.storage)?;
-        let orai_contract = config.oraiswap_contract.orai_contract;
-        let native_token = NativeToken::new("orai".to_string());
-        let offer_asset_info = OfferAssetInfo::new(native_token);
-        let usdt_contract_address = config.oraiswap_contract.usdt_contract;
+        let band_protocol = Self::from_config(deps, &config)?;
+        if let Some(guard) = &config.price_guard {
+            let cache = PriceCache::load(deps.storage)?;
+            guard.check(&cache, band_protocol.usd_per_orai, now)?;
+        }
+
+        Ok(band_protocol)
+    }
+
+    /// Tries `price_source` and then each fallback in turn. With `max_price_divergence_bps`
+    /// set, every source is queried and those that answer have to agree with the first one.
+    fn from_config(deps: Deps, config: &Config) -> Result<Self, ContractError> {
+        let sources = std::iter::once(&config.price_source).chain(&config.price_fallbacks);
+
+        let mut quotes = vec![];
+        for source in sources {
+            let source = source.source(&config.oraiswap_contract);
+            let band_protocol = match Self::from_source(deps, source.as_ref()) {
+                Ok(band_protocol) => band_protocol,
+                Err(_) => continue,
+            };
+            if config.max_price_divergence_bps.is_none() {
+                return Ok(band_protocol);
+            }
+            quotes.push(band_protocol.usd_per_orai);
+        }
+
+        let (&price, others) = quotes
+            .split_first()
+            .ok_or(ContractError::PriceUnavailable {})?;
+        let max_divergence_bps = config.max_price_divergence_bps.unwrap_or_default();
+        for &other in others {
+            if deviates(price, other, max_divergence_bps)? {
+                return Err(ContractError::PriceSourcesDisagree { price, other });
+            }
+        }
+
+        Ok(BandProtocol {
+            usd_per_orai: price,
+        })
+    }
+
+    pub fn from_source(deps: Deps, source: &dyn PriceSource) -> Result<Self, ContractError> {
+        let usd_per_orai = source.orai_price(deps)?;
+        if usd_per_orai.is_zero() {
+            return Err(ContractError::InvalidPrice {});
+        }
+
+        Ok(BandProtocol { usd_per_orai })
+    }
+
+    pub fn usd_amount(&self, uorai: u128) -> Result<u128, ContractError> {
+        Uint128::new(uorai)
+            .checked_multiply_ratio(self.usd_per_orai.atomics(), Self::uorai_scale())
+            .map(|usd| usd.u128())
+            .map_err(|_| ContractError::Overflow {})
+    }
+
+    pub fn orai_amount(&self, usd: u128) -> Result<u128, ContractError> {
+        Uint128::new(usd)
+            .checked_multiply_ratio(Self::uorai_scale(), self.usd_per_orai.atomics())
+            .map(|uorai| uorai.u128())
+            .map_err(|_| ContractError::Overflow {})
+    }
+
+    /// Like `orai_amount`, but rounded up so that `usd_amount` of the result reaches `usd`.
+    pub fn orai_amount_ceil(&self, usd: u128) -> Result<u128, ContractError> {
+        let orai = self.orai_amount(usd)?;
+        if self.usd_amount(orai)? >= usd {
+            Ok(orai)
+        } else {
+            orai.checked_add(1).ok_or(ContractError::Overflow {})
+        }
+    }
+
+    pub fn price(&self) -> Decimal {
+        self.usd_per_orai
+    }
+
+    // atomics of a `Decimal` USD price times uorai per ORAI
+    fn uorai_scale() -> Uint128 {
+        Uint128::new(UORAI_PER_ORAI) * Decimal::one().atomics()
+    }
+}
+
+impl PriceGuard {
+    pub fn check(&self, cache: &PriceCache, price: Decimal, now: u64) -> Result<(), ContractError> {
+        if cache.is_empty() || now.saturating_sub(cache.updated_at) > self.max_age {
+            return Err(ContractError::StalePrice {
+                max_age: self.max_age,
+            });
+        }
+
+        for reference in [cache.price, cache.twap] {
+            if deviates(price, reference, self.max_deviation_bps)? {
+                return Err(ContractError::PriceDeviation { price, reference });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clamps a refreshed quote to `max_deviation_bps` around the TWAP. A quote pushed by a trade
+    /// then can't become the reference a deposit in the same block is checked against, while a
+    /// real move still reaches the cache as the TWAP follows it.
+    pub fn clamp_to_twap(&self, price: Decimal, twap: Decimal) -> Result<Decimal, ContractError> {
+        let max_deviation = twap
+            .checked_mul(Decimal::from_ratio(self.max_deviation_bps, 10_000u128))
+            .map_err(|_| ContractError::Overflow {})?;
+        let high = twap
+            .checked_add(max_deviation)
+            .map_err(|_| ContractError::Overflow {})?;
+
+        Ok(price.clamp(twap.saturating_sub(max_deviation), high))
+    }
+}
+
+/// Whether `price` is more than `max_deviation_bps` away from `reference`.
+fn deviates(
+    price: Decimal,
+    reference: Decimal,
+    max_deviation_bps: u16,
+) -> Result<bool, ContractError> {
+    let max_deviation = reference
+        .checked_mul(Decimal::from_ratio(max_deviation_bps, 10_000u128))
+        .map_err(|_| ContractError::Overflow {})?;
+    Ok(price.abs_diff(reference) > max_deviation)
+}
+
+impl OraiswapContract {
+    /// The configured route, or a single ORAI -> `usdt_contract` hop when none is set.
+    pub fn route(&self) -> Vec<Operation> {
+        if !self.route.is_empty() {
+            return self.route.clone();
+        }
+
+        vec![Operation {
+            orai_swap: OraiSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "orai".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: self.usdt_contract.clone(),
+                },
+            },
+        }]
+    }
+
+    pub fn offer_amount(&self) -> u128 {
+        self.offer_amount
+            .map(|amount| amount.u128())
+            .unwrap_or(UORAI_PER_ORAI)
+    }
+
+    /// Checks that the route starts from ORAI and that every hop offers what the previous
+    /// one asked for.
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.offer_amount() == 0 {
+            return Err(ContractError::InvalidPriceRoute {});
+        }
+
+        let route = self.route();
+        let orai = AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        };
+        if route[0].orai_swap.offer_asset_info != orai {
+            return Err(ContractError::InvalidPriceRoute {});
+        }
+        for hops in route.windows(2) {
+            if hops[0].orai_swap.ask_asset_info != hops[1].orai_swap.offer_asset_info {
+                return Err(ContractError::InvalidPriceRoute {});
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PriceSourceConfig {
+    pub fn source(&self, oraiswap_contract: &OraiswapContract) -> Box<dyn PriceSource> {
+        match self {
+            PriceSourceConfig::OraiswapRouter {} => Box::new(OraiswapRouter {
+                router: oraiswap_contract.orai_contract.clone(),
+                operations: oraiswap_contract.route(),
+                offer_amount: oraiswap_contract.offer_amount(),
+            }),
+            PriceSourceConfig::BandStdReference { contract } => Box::new(BandStdReference {
+                contract: contract.clone(),
+            }),
+            PriceSourceConfig::PushOracle { contract, price_id } => Box::new(PushOracle {
+                contract: contract.clone(),
+                price_id: price_id.clone(),
+            }),
+            PriceSourceConfig::FixedPrice { price } => Box::new(FixedPrice { price: *price }),
+        }
+    }
+}
+
+/// Simulates selling `offer_amount` uorai along `operations` on the Oraiswap router. The last
+/// hop has to end in a USD stablecoin with 6 decimals.
+pub struct OraiswapRouter {
+    pub router: String,
+    pub operations: Vec<Operation>,
+    pub offer_amount: u128,
+}
+
+impl PriceSource for OraiswapRouter {
+    fn orai_price(&self, deps: Deps) -> Result<Decimal, ContractError> {
         let msg = SwapContractMessage {
             simulate_swap_operations: SwapCtrMessageContent {
-                offer_amount: 1000000,
-                operations: vec![Operation {
-                    orai_swap: OraiSwap {
-                        offer_asset_info: offer_asset_info,
-                        ask_asset_info: AskAssetInfo {
-                            token: UsdtContractAddr {
-                                contract_addr: usdt_contract_address,
-                            },
-                        },
-                    },
-                }],
+                offer_amount: self.offer_amount,
+                operations: self.operations.clone(),
             },
         };
-        let response: ChangeRateResponse = deps.querier.query_wasm_smart(orai_contract, &msg)?;
-        let rate = response.data.amount;
-        // let rate = 8123456;
-        let orai_per_usd = (Decimal::raw(1000000u128) / Decimal::raw(rate))
-            .to_uint_floor()
-            .u128();
-        Ok(BandProtocol { orai_per_usd })
+        let response: ChangeRateResponse = deps.querier.query_wasm_smart(&self.router, &msg)?;
+
+        let rate = Decimal::checked_from_ratio(response.data.amount, self.offer_amount)
+            .map_err(|_| ContractError::InvalidPrice {})?;
+        rate.checked_mul(Decimal::from_ratio(UORAI_PER_ORAI, UUSDT_PER_USDT))
+            .map_err(|_| ContractError::InvalidPrice {})
     }
+}
 
-    pub fn usd_amount(&self, usei: u128) -> u128 {
-        usei.checked_mul(self.orai_per_usd)
-            .and_then(|v| v.checked_div(BandProtocol::ONE_USD))
-            .unwrap()
+/// Reads the ORAI/USD rate from a Band Protocol std reference contract.
+pub struct BandStdReference {
+    pub contract: String,
+}
+
+impl PriceSource for BandStdReference {
+    fn orai_price(&self, deps: Deps) -> Result<Decimal, ContractError> {
+        let msg = StdReferenceQueryMsg::GetReferenceData {
+            base_symbol: "ORAI".to_string(),
+            quote_symbol: "USD".to_string(),
+        };
+        let response: ReferenceData = deps.querier.query_wasm_smart(&self.contract, &msg)?;
+
+        // rates are fixed point numbers with 18 decimals
+        let rate = Uint128::try_from(response.rate).map_err(|_| ContractError::InvalidPrice {})?;
+        Ok(Decimal::new(rate))
     }
+}
+
+/// Reads the ORAI/USD feed `price_id` from a Pyth-style push oracle.
+pub struct PushOracle {
+    pub contract: String,
+    pub price_id: String,
+}
+
+impl PriceSource for PushOracle {
+    fn orai_price(&self, deps: Deps) -> Result<Decimal, ContractError> {
+        let msg = PushOracleQueryMsg::PriceFeed {
+            id: self.price_id.clone(),
+        };
+        let response: PriceFeedResponse = deps.querier.query_wasm_smart(&self.contract, &msg)?;
 
-    pub fn orai_amount(&self, usd: u128) -> u128 {
-        usd.checked_mul(BandProtocol::ONE_USD)
-            .and_then(|v: u128| v.checked_div(self.orai_per_usd))
-            .unwrap()
+        response.price_feed.price.to_decimal()
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-// Define the struct for the innermost part "native_token"
-pub struct NativeToken {
-    denom: String,
+/// A price set by the admin.
+pub struct FixedPrice {
+    pub price: Decimal,
 }
 
-impl NativeToken {
-    pub fn new(native_token_denom: String) -> Self {
-        NativeToken {
-            denom: native_token_denom,
-        }
+impl PriceSource for FixedPrice {
+    fn orai_price(&self, _deps: Deps) -> Result<Decimal, ContractError> {
+        Ok(self.price)
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-// Define the struct associated with "offer_asset_info"
-pub struct OfferAssetInfo {
-    native_token: NativeToken,
+pub enum StdReferenceQueryMsg {
+    GetReferenceData {
+        base_symbol: String,
+        quote_symbol: String,
+    },
 }
 
-impl OfferAssetInfo {
-    pub fn new(native_token: NativeToken) -> Self {
-        OfferAssetInfo {
-            native_token: native_token,
-        }
-    }
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ReferenceData {
+    pub rate: Uint256,
+    pub last_updated_base: u64,
+    pub last_updated_quote: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct UsdtContractAddr {
-    contract_addr: String,
+pub enum PushOracleQueryMsg {
+    PriceFeed { id: String },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct AskAssetInfo {
-    pub token: UsdtContractAddr,
+pub struct PriceFeedResponse {
+    pub price_feed: PriceFeed,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PriceFeed {
+    pub id: String,
+    pub price: OraclePrice,
+}
+
+/// `price * 10^expo`, published at `publish_time`.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OraclePrice {
+    pub price: Int64,
+    pub conf: Uint64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    pub fn to_decimal(&self) -> Result<Decimal, ContractError> {
+        let price = self.price.i64();
+        if price <= 0 {
+            return Err(ContractError::InvalidPrice {});
+        }
+
+        let price = price as u128;
+        if self.expo < 0 {
+            return Decimal::from_atomics(price, self.expo.unsigned_abs())
+                .map_err(|_| ContractError::InvalidPrice {});
+        }
+        10u128
+            .checked_pow(self.expo as u32)
+            .and_then(|scale| scale.checked_mul(price))
+            .and_then(|price| Decimal::from_atomics(price, 0).ok())
+            .ok_or(ContractError::InvalidPrice {})
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Token { contract_addr: String },
+    NativeToken { denom: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 // Define the struct represented by the "orai_swap" key
 pub struct OraiSwap {
-    offer_asset_info: OfferAssetInfo,
-    ask_asset_info: AskAssetInfo,
+    pub offer_asset_info: AssetInfo,
+    pub ask_asset_info: AssetInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Operation {
     pub orai_swap: OraiSwap,
@@ -150,11 +411,247 @@ pub struct SwapContractMessage {
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Amount {
-    amount: u128
+    amount: u128,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ChangeRateResponse {
-    pub data: Amount   
-}
\ No newline at end of file
+    pub data: Amount,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{from_json, to_json_binary, ContractResult, SystemResult, WasmQuery};
+
+    #[test]
+    fn price_sources() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|request| {
+            let WasmQuery::Smart { contract_addr, msg } = request else {
+                panic!("Unexpected query");
+            };
+            let response = match contract_addr.as_str() {
+                "router" => {
+                    let msg: SwapContractMessage = from_json(msg).unwrap();
+                    let offer_amount = msg.simulate_swap_operations.offer_amount;
+                    to_json_binary(&ChangeRateResponse {
+                        data: Amount {
+                            amount: offer_amount * 8_123_456 / 1_000_000,
+                        },
+                    })
+                }
+                "band" => to_json_binary(&ReferenceData {
+                    rate: Uint256::from(8_123_456_000_000_000_000u128),
+                    last_updated_base: 100,
+                    last_updated_quote: 100,
+                }),
+                "pyth" => to_json_binary(&PriceFeedResponse {
+                    price_feed: PriceFeed {
+                        id: "orai".to_string(),
+                        price: OraclePrice {
+                            price: Int64::new(812_345_600),
+                            conf: Uint64::new(1_000),
+                            expo: -8,
+                            publish_time: 100,
+                        },
+                    },
+                }),
+                _ => panic!("Unexpected contract"),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        });
+
+        let oraiswap_contract = OraiswapContract {
+            orai_contract: "router".to_string(),
+            usdt_contract: "usdt".to_string(),
+            route: vec![],
+            offer_amount: None,
+        };
+        let expected = Decimal::from_ratio(8_123_456u128, 1_000_000u128);
+        let sources: Vec<Box<dyn PriceSource>> = vec![
+            Box::new(OraiswapRouter {
+                router: "router".to_string(),
+                operations: oraiswap_contract.route(),
+                offer_amount: 1_000_000,
+            }),
+            Box::new(OraiswapRouter {
+                router: "router".to_string(),
+                operations: oraiswap_contract.route(),
+                offer_amount: 50_000_000,
+            }),
+            Box::new(BandStdReference {
+                contract: "band".to_string(),
+            }),
+            Box::new(PushOracle {
+                contract: "pyth".to_string(),
+                price_id: "orai".to_string(),
+            }),
+            Box::new(FixedPrice { price: expected }),
+        ];
+        for source in sources {
+            assert_eq!(source.orai_price(deps.as_ref()), Ok(expected));
+        }
+
+        let zero = FixedPrice {
+            price: Decimal::zero(),
+        };
+        assert_eq!(
+            BandProtocol::from_source(deps.as_ref(), &zero).err(),
+            Some(ContractError::InvalidPrice {})
+        );
+
+        let price = |price: i64, expo: i32| {
+            OraclePrice {
+                price: Int64::new(price),
+                conf: Uint64::zero(),
+                expo,
+                publish_time: 0,
+            }
+            .to_decimal()
+        };
+        assert_eq!(price(25, -1), Ok(Decimal::percent(250)));
+        assert_eq!(price(3, 2), Ok(Decimal::from_ratio(300u128, 1u128)));
+        assert_eq!(price(-5, -1), Err(ContractError::InvalidPrice {}));
+    }
+
+    #[test]
+    fn price_routes() {
+        let native = |denom: &str| AssetInfo::NativeToken {
+            denom: denom.to_string(),
+        };
+        let token = |contract_addr: &str| AssetInfo::Token {
+            contract_addr: contract_addr.to_string(),
+        };
+        let hop = |offer_asset_info, ask_asset_info| Operation {
+            orai_swap: OraiSwap {
+                offer_asset_info,
+                ask_asset_info,
+            },
+        };
+        let mut oraiswap_contract = OraiswapContract {
+            orai_contract: "router".to_string(),
+            usdt_contract: "usdt".to_string(),
+            route: vec![],
+            offer_amount: None,
+        };
+        assert_eq!(
+            oraiswap_contract.route(),
+            vec![hop(native("orai"), token("usdt"))]
+        );
+        assert_eq!(oraiswap_contract.offer_amount(), 1_000_000);
+        assert_eq!(oraiswap_contract.validate(), Ok(()));
+
+        oraiswap_contract.route = vec![
+            hop(native("orai"), native("ibc/atom")),
+            hop(native("ibc/atom"), token("usdc")),
+        ];
+        assert_eq!(oraiswap_contract.validate(), Ok(()));
+
+        let invalid_routes = vec![
+            vec![hop(native("ibc/atom"), token("usdc"))],
+            vec![
+                hop(native("orai"), native("ibc/atom")),
+                hop(native("ibc/osmo"), token("usdc")),
+            ],
+        ];
+        for route in invalid_routes {
+            oraiswap_contract.route = route;
+            assert_eq!(
+                oraiswap_contract.validate(),
+                Err(ContractError::InvalidPriceRoute {})
+            );
+        }
+
+        oraiswap_contract.route = vec![];
+        oraiswap_contract.offer_amount = Some(Uint128::zero());
+        assert_eq!(
+            oraiswap_contract.validate(),
+            Err(ContractError::InvalidPriceRoute {})
+        );
+    }
+
+    #[test]
+    fn fallback_chain() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Err("router unavailable".to_string()))
+        });
+
+        let fixed = |percent: u64| PriceSourceConfig::FixedPrice {
+            price: Decimal::percent(percent),
+        };
+        let mut config = Config {
+            admin: "admin".to_string(),
+            status: 0,
+            nft_contract: "nft".to_string(),
+            lock_periods: vec![],
+            min_tier: 1,
+            validators: vec![],
+            usd_deposits: vec![100],
+            oraiswap_contract: OraiswapContract {
+                orai_contract: "router".to_string(),
+                usdt_contract: "usdt".to_string(),
+                route: vec![],
+                offer_amount: None,
+            },
+            price_source: PriceSourceConfig::OraiswapRouter {},
+            price_guard: None,
+            price_fallbacks: vec![],
+            max_price_divergence_bps: None,
+            tier_policy: Default::default(),
+            tier_locks: Default::default(),
+            protocol_fee: None,
+            unbonding: Default::default(),
+        };
+        let price = |config: &Config| {
+            BandProtocol::from_config(deps.as_ref(), config).map(|band| band.price())
+        };
+
+        assert_eq!(price(&config), Err(ContractError::PriceUnavailable {}));
+
+        config.price_fallbacks = vec![fixed(0), fixed(250), fixed(300)];
+        assert_eq!(price(&config), Ok(Decimal::percent(250)));
+
+        config.max_price_divergence_bps = Some(2_000);
+        assert_eq!(price(&config), Ok(Decimal::percent(250)));
+
+        config.max_price_divergence_bps = Some(1_000);
+        assert_eq!(
+            price(&config),
+            Err(ContractError::PriceSourcesDisagree {
+                price: Decimal::percent(250),
+                other: Decimal::percent(300),
+            })
+        );
+
+        config.price_fallbacks = vec![fixed(0)];
+        assert_eq!(price(&config), Err(ContractError::PriceUnavailable {}));
+    }
+
+    #[test]
+    fn conversions() {
+        let deps = mock_dependencies();
+        let source = FixedPrice {
+            price: Decimal::percent(250),
+        };
+        let band_protocol = BandProtocol::from_source(deps.as_ref(), &source).unwrap();
+
+        assert_eq!(band_protocol.usd_amount(1_000_000), Ok(2));
+        assert_eq!(band_protocol.usd_amount(1_200_000), Ok(3));
+        assert_eq!(band_protocol.usd_amount(1_199_999), Ok(2));
+        assert_eq!(band_protocol.orai_amount(5), Ok(2_000_000));
+        assert_eq!(band_protocol.orai_amount(1), Ok(400_000));
+        assert_eq!(band_protocol.orai_amount_ceil(1), Ok(400_000));
+
+        let source = FixedPrice {
+            price: Decimal::from_ratio(3u128, 1u128),
+        };
+        let band_protocol = BandProtocol::from_source(deps.as_ref(), &source).unwrap();
+        assert_eq!(band_protocol.orai_amount(1), Ok(333_333));
+        assert_eq!(band_protocol.orai_amount_ceil(1), Ok(333_334));
+        assert_eq!(band_protocol.usd_amount(333_334), Ok(1));
+    }
+}
@@ -1,34 +1,44 @@
 use std::convert::TryInto;
 
-#[cfg(not(feature = "library"))]
-use crate::band::BandProtocol;
+use crate::band::{BandProtocol, Operation};
 use crate::state;
+#[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::DistributionMsg;
-use cosmwasm_std::StakingMsg;
 use cosmwasm_std::{
-    coin, coins, to_json_binary, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdResult, SubMsg, Uint128, WasmMsg,
+    coins, to_json_binary, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::Bound;
 
+use crate::delegations;
 use crate::error::ContractError;
+use crate::events;
 use crate::msg::{
-    ContractStatus, ExecuteMsg, ExecuteResponse, InstantiateMsg, PaymentMethod, QueryMsg,
-    QueryResponse, ResponseStatus, SerializedWithdrawals, Whitelist,
+    ArchivedPurchasesResponse, BuyRejection, ContractStatus, DelegationsResponse, EarlyExitPolicy,
+    ExecuteMsg, ExecuteResponse, IdoAmountResponse, IdoListOwnedByResponse,
+    IdoParticipantsResponse, InWhitelistResponse, InstantiateMsg, MigrateMsg, ParticipantAnswer,
+    PaymentMethod, PenaltyRecipient, PendingRewardsResponse, PortfolioPosition, PriceGuard,
+    PriceSourceConfig, ProtocolFee, PurchasesResponse, QueryMsg, QueryResponse, ResponseStatus,
+    SerializedWithdrawals, SimulateBuyResponse, SimulateDepositResponse, StakingStatsResponse,
+    TierInfoResponse, TierLockResponse, TierLocks, TierPolicy, Unbonding, UserPortfolioResponse,
+    ValidatorDelegation, ValidatorWithWeight, Whitelist, WithdrawalsResponse,
 };
+use crate::rewards::{self, RewardPool, StakerRewards};
+use crate::slashing;
+use crate::unbonding::{self, UnbondingEpoch};
 use crate::utils::{self, assert_admin, assert_contract_active, assert_ido_admin};
 use crate::{
     state::{
-        Config, Ido, Purchase, UserWithdrawal, ACTIVE_IDOS, ARCHIVED_PURCHASES, CONFIG_KEY,
-        IDO_TO_INFO, OWNER_TO_IDOS, PURCHASES, TIER_USER_INFOS, USERINFO, WHITELIST,
-        WITHDRAWALS_LIST,
+        CircuitBreaker, Config, Ido, PriceCache, Purchase, StakingStats, TierUserInfo,
+        UserWithdrawal, ACTIVE_IDOS, ARCHIVED_PURCHASES, CONFIG_KEY, IDO_PARTICIPANTS, IDO_TO_INFO,
+        OWNER_TO_IDOS, PURCHASES, TIER_USER_INFOS, USERINFO, WHITELIST, WITHDRAWALS_LIST,
     },
-    tier::get_tier,
+    tier::{get_tier, get_tier_from_nft_tokens, marked_user_info, stake_tier},
 };
-use cosmwasm_std::StdError;
 
 pub const ORAI: &str = "orai";
+/// Default unbonding time, see `Unbonding::unbonding_time`.
 pub const UNBOUND_LATENCY: u64 = 21 * 24 * 60 * 60;
 pub const ZERO_CODE: i32 = 0;
 
@@ -48,9 +58,7 @@ pub fn instantiate(
     let deposits = msg.deposits.iter().map(|v| v.u128()).collect::<Vec<_>>();
 
     if deposits.is_empty() {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Deposits array is empty",
-        )));
+        return Err(ContractError::EmptyDeposits {});
     }
     // -------------
 
@@ -63,17 +71,32 @@ pub fn instantiate(
         validators: msg.validators, // Tier Contract
         usd_deposits: deposits,     // Tier Contract
         oraiswap_contract: msg.oraiswap_contract,
+        price_source: msg.price_source.unwrap_or_default(),
+        price_guard: msg.price_guard,
+        price_fallbacks: vec![],
+        max_price_divergence_bps: None,
+        tier_policy: msg.tier_policy.unwrap_or_default(),
+        tier_locks: msg.tier_locks.unwrap_or_default(),
+        protocol_fee: msg.protocol_fee,
+        unbonding: msg.unbonding.unwrap_or_default(),
     };
 
     let min_tier = config.min_tier();
     config.min_tier = min_tier;
 
     if lock_periods_len != min_tier as usize {
-        return Err(ContractError::Std(StdError::generic_err(&format!(
-            "Lock periods array must have {} items",
-            min_tier
-        ))));
+        return Err(ContractError::InvalidLockPeriods { expected: min_tier });
+    }
+    config.oraiswap_contract.validate()?;
+    validate_validators(&config.validators)?;
+    if let Some(price_guard) = &config.price_guard {
+        validate_price_guard(price_guard)?;
+    }
+    validate_tier_locks(deps.as_ref(), &config, &config.tier_locks)?;
+    if let Some(protocol_fee) = &config.protocol_fee {
+        validate_protocol_fee(deps.as_ref(), protocol_fee)?;
     }
+    validate_unbonding(deps.as_ref(), &config.unbonding)?;
 
     CONFIG_KEY.save(deps.storage, &config)?;
 
@@ -87,7 +110,7 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    let response = match msg {
+    match msg {
         ExecuteMsg::ChangeAdmin { admin, .. } => change_admin(deps, env, info, admin),
         ExecuteMsg::ChangeStatus { status, .. } => change_status(deps, env, info, status),
         ExecuteMsg::StartIdo {
@@ -145,36 +168,89 @@ pub fn execute(
 
         // Tier Contract
         ExecuteMsg::Deposit { .. } => try_deposit(deps, env, info),
-        ExecuteMsg::WithdrawFromTier { .. } => withdraw_from_tier(deps, env, info),
+        ExecuteMsg::WithdrawFromTier { amount, .. } => withdraw_from_tier(deps, env, info, amount),
         ExecuteMsg::Claim {
             recipient,
             start,
             limit,
             ..
         } => try_claim(deps, env, info, recipient, start, limit),
-        ExecuteMsg::WithdrawRewards { recipient, .. } => {
-            try_withdraw_rewards(deps, env, info, recipient)
-        }
+        ExecuteMsg::WithdrawRewards { .. } => try_withdraw_rewards(deps, env, info),
         ExecuteMsg::Redelegate {
             validator_address,
             recipient,
             ..
         } => try_redelegate(deps, env, info, validator_address, recipient),
-    };
-
-    return response;
+        ExecuteMsg::UpdatePriceSource { price_source, .. } => {
+            update_price_source(deps, env, info, price_source)
+        }
+        ExecuteMsg::UpdatePriceGuard { price_guard, .. } => {
+            update_price_guard(deps, env, info, price_guard)
+        }
+        ExecuteMsg::RefreshPrice { .. } => refresh_price(deps, env, info),
+        ExecuteMsg::UpdatePriceRoute {
+            route,
+            offer_amount,
+            ..
+        } => update_price_route(deps, env, info, route, offer_amount),
+        ExecuteMsg::UpdatePriceFallbacks {
+            fallbacks,
+            max_divergence_bps,
+            ..
+        } => update_price_fallbacks(deps, env, info, fallbacks, max_divergence_bps),
+        ExecuteMsg::ResetCircuitBreaker { .. } => reset_circuit_breaker(deps, env, info),
+        ExecuteMsg::UpdateTierPolicy { tier_policy, .. } => {
+            update_tier_policy(deps, env, info, tier_policy)
+        }
+        ExecuteMsg::SyncTier { address, .. } => sync_tier(deps, env, info, address),
+        ExecuteMsg::UpdateTierLocks { tier_locks, .. } => {
+            update_tier_locks(deps, env, info, tier_locks)
+        }
+        ExecuteMsg::ClaimStakingRewards { .. } => claim_staking_rewards(deps, env, info),
+        ExecuteMsg::UpdateProtocolFee { protocol_fee, .. } => {
+            update_protocol_fee(deps, env, info, protocol_fee)
+        }
+        ExecuteMsg::SetAutoCompound { enabled, .. } => set_auto_compound(deps, env, info, enabled),
+        ExecuteMsg::Compound { addresses, .. } => compound(deps, env, info, addresses),
+        ExecuteMsg::AddValidator { address, .. } => add_validator(deps, env, info, address),
+        ExecuteMsg::RemoveValidator { address, .. } => remove_validator(deps, env, info, address),
+        ExecuteMsg::SetWeights { validators, .. } => set_weights(deps, env, info, validators),
+        ExecuteMsg::Rebalance { .. } => rebalance(deps, env, info),
+        ExecuteMsg::ProcessUnbondingEpoch { .. } => process_unbonding_epoch(deps, env, info),
+        ExecuteMsg::UpdateUnbonding { unbonding, .. } => {
+            update_unbonding(deps, env, info, unbonding)
+        }
+        ExecuteMsg::ReconcileDelegations { .. } => reconcile_delegations(deps, env, info),
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
         QueryMsg::TierUserInfo { address } => to_json_binary(&query_tier_user_info(deps, address)?),
+        QueryMsg::TierInfo {
+            address,
+            nft_token_ids,
+        } => to_json_binary(&query_tier_info(deps, env, address, nft_token_ids)?),
+        QueryMsg::SimulateDeposit {
+            address,
+            target_tier,
+        } => to_json_binary(&query_simulate_deposit(deps, env, address, target_tier)?),
         QueryMsg::Withdrawals {
             address,
             start,
             limit,
-        } => to_json_binary(&query_withdrawals(deps, address, start, limit)?),
+        } => to_json_binary(&query_withdrawals(deps, env, address, start, limit)?),
+        QueryMsg::StakingStats {} => to_json_binary(&query_staking_stats(deps)?),
+        QueryMsg::PriceCache {} => to_json_binary(&query_price_cache(deps)?),
+        QueryMsg::CircuitBreaker {} => to_json_binary(&query_circuit_breaker(deps)?),
+        QueryMsg::TierLock { address } => to_json_binary(&query_tier_lock(deps, env, address)?),
+        QueryMsg::PendingRewards { address } => {
+            to_json_binary(&query_pending_rewards(deps, env, address)?)
+        }
+        QueryMsg::Delegations {} => to_json_binary(&query_delegations(deps, env)?),
+        QueryMsg::UnbondingEpoch { id } => to_json_binary(&query_unbonding_epoch(deps, env, id)?),
         QueryMsg::IdoAmount {} => to_json_binary(&query_ido_amount(deps)?),
         QueryMsg::IdoInfo { ido_id } => to_json_binary(&query_ido_info(deps, ido_id)?),
         QueryMsg::InWhitelist { address, ido_id } => {
@@ -203,7 +279,97 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::UserInfo { address, ido_id } => {
             to_json_binary(&query_user_info(deps, ido_id, address)?)
         }
+        QueryMsg::IdoParticipants {
+            ido_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query_ido_participants(deps, ido_id, start_after, limit)?),
+        QueryMsg::UserPortfolio { address } => {
+            to_json_binary(&query_user_portfolio(deps, env, address)?)
+        }
+        QueryMsg::SimulateBuy {
+            ido_id,
+            address,
+            amount,
+            viewing_key,
+        } => to_json_binary(&query_simulate_buy(
+            deps,
+            env,
+            ido_id,
+            address,
+            amount.u128(),
+            viewing_key,
+        )?),
+    };
+
+    Ok(response?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stats = rebuild_staking_stats(deps.as_ref(), &env)?;
+    stats.save(deps.storage)?;
+    backfill_ido_participants(deps.storage)?;
+
+    Ok(Response::new().add_event(events::staking_stats_rebuilt(&stats)))
+}
+
+/// Sums the staking aggregates over every tier deposit and withdrawal.
+fn rebuild_staking_stats(deps: Deps, env: &Env) -> Result<StakingStats, ContractError> {
+    let index = slashing::index(deps.storage)?;
+    let mut stats = StakingStats::default();
+    for item in TIER_USER_INFOS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, user_info) = item?;
+        let orai_deposit = slashing::scale(user_info.orai_deposit, user_info.slashing_index, index);
+        stats.add_user(user_info.tier);
+        stats.total_orai_deposit = stats
+            .total_orai_deposit
+            .checked_add(orai_deposit)
+            .ok_or(ContractError::Overflow {})?;
+        stats.total_usd_deposit = stats
+            .total_usd_deposit
+            .checked_add(user_info.usd_deposit)
+            .ok_or(ContractError::Overflow {})?;
+    }
+
+    let config = Config::load(deps.storage)?;
+    let unbonding = unbonding::current(&deps.querier, &config.unbonding)?;
+    let now = env.block.time.seconds();
+    for item in WITHDRAWALS_LIST.range(deps.storage, None, None, Order::Ascending) {
+        let (_, withdrawals) = item?;
+        for withdrawal in withdrawals.iter() {
+            let claimable = unbonding::claimable(deps.storage, &unbonding, withdrawal, now)?;
+            stats.total_pending_withdrawals = stats
+                .total_pending_withdrawals
+                .checked_add(claimable.amount)
+                .ok_or(ContractError::Overflow {})?;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Indexes the buyers from before `IDO_PARTICIPANTS`. The tier they bought with was not kept,
+/// so they are listed with the tier stored for their deposit.
+fn backfill_ido_participants(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let config = Config::load(storage)?;
+    let mut buyers = vec![];
+    for item in IDO_TO_INFO.range(storage, None, None, Order::Ascending) {
+        let (key, user_ido_info) = item?;
+        if user_ido_info.total_payment != 0 {
+            buyers.push(key);
+        }
+    }
+    for (address, ido_id) in buyers {
+        if IDO_PARTICIPANTS.has(storage, (ido_id, address.clone())) {
+            continue;
+        }
+        let tier = TierUserInfo::may_load(storage, &address)?
+            .map_or_else(|| config.min_tier(), |tier_user_info| tier_user_info.tier);
+        IDO_PARTICIPANTS.save(storage, (ido_id, address), &tier)?;
     }
+
+    Ok(())
 }
 
 fn change_admin(
@@ -216,11 +382,14 @@ fn change_admin(
 
     let mut config = Config::load(deps.storage)?;
     let new_admin = admin.to_string();
+    let event = events::admin_changed(&config.admin, &new_admin);
     config.admin = new_admin;
 
     config.save(deps.storage)?;
 
-    Ok(Response::new().add_attribute("action", "changed admin"))
+    Ok(Response::new()
+        .add_attribute("action", "changed admin")
+        .add_event(event))
 }
 
 fn change_status(
@@ -231,37 +400,258 @@ fn change_status(
 ) -> Result<Response, ContractError> {
     assert_admin(&deps, &info.sender.to_string())?;
 
+    let event = events::status_changed(&status);
     let mut config = Config::load(deps.storage)?;
     config.status = status as u8;
     config.save(deps.storage)?;
 
-    Ok(Response::new().add_attribute("action", "changed status"))
+    Ok(Response::new()
+        .add_attribute("action", "changed status")
+        .add_event(event))
+}
+
+fn update_price_source(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    price_source: PriceSourceConfig,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let event = events::price_source_updated(&price_source);
+    let mut config = Config::load(deps.storage)?;
+
+    // refuse a source that cannot quote a price right away
+    let source = price_source.source(&config.oraiswap_contract);
+    BandProtocol::from_source(deps.as_ref(), source.as_ref())?;
+
+    config.price_source = price_source;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdatePriceSource {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn validate_price_guard(price_guard: &PriceGuard) -> Result<(), ContractError> {
+    if price_guard.twap_window == 0 {
+        return Err(ContractError::InvalidPriceGuard {});
+    }
+
+    Ok(())
+}
+
+fn update_price_guard(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    price_guard: Option<PriceGuard>,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+    if let Some(price_guard) = &price_guard {
+        validate_price_guard(price_guard)?;
+    }
+
+    let event = events::price_guard_updated(price_guard.as_ref());
+    let mut config = Config::load(deps.storage)?;
+    config.price_guard = price_guard;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdatePriceGuard {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn update_price_route(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    route: Vec<Operation>,
+    offer_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let mut config = Config::load(deps.storage)?;
+    config.oraiswap_contract.route = route;
+    config.oraiswap_contract.offer_amount = offer_amount;
+    config.oraiswap_contract.validate()?;
+
+    // refuse a route the router cannot simulate
+    let router = PriceSourceConfig::OraiswapRouter {}.source(&config.oraiswap_contract);
+    BandProtocol::from_source(deps.as_ref(), router.as_ref())?;
+
+    let event = events::price_route_updated(
+        config.oraiswap_contract.route().len(),
+        config.oraiswap_contract.offer_amount(),
+    );
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdatePriceRoute {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn update_price_fallbacks(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fallbacks: Vec<PriceSourceConfig>,
+    max_divergence_bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let mut config = Config::load(deps.storage)?;
+
+    // refuse fallbacks that cannot quote a price right away
+    for fallback in &fallbacks {
+        let source = fallback.source(&config.oraiswap_contract);
+        BandProtocol::from_source(deps.as_ref(), source.as_ref())?;
+    }
+
+    let event = events::price_fallbacks_updated(&fallbacks, max_divergence_bps);
+    config.price_fallbacks = fallbacks;
+    config.max_price_divergence_bps = max_divergence_bps;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdatePriceFallbacks {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn reset_circuit_breaker(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    CircuitBreaker::default().save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::ResetCircuitBreaker {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .set_data(answer)
+        .add_event(events::circuit_breaker_reset()))
+}
+
+fn update_tier_policy(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    tier_policy: TierPolicy,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let event = events::tier_policy_updated(&tier_policy);
+    let mut config = Config::load(deps.storage)?;
+    config.tier_policy = tier_policy;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdateTierPolicy {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+/// Stores the mark-to-market tier of `address`. Anyone may call it.
+fn sync_tier(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = Config::load(deps.storage)?;
+    config.assert_contract_active()?;
+
+    if matches!(config.tier_policy, TierPolicy::LockedAtDeposit {}) {
+        return Err(ContractError::TierPolicyLocked {});
+    }
+
+    let address = deps.api.addr_validate(&address)?.to_string();
+    let stored_user_info =
+        TierUserInfo::may_load(deps.storage, &address)?.ok_or(ContractError::NoTierDeposit {})?;
+
+    let now = env.block.time.seconds();
+    let band_protocol = BandProtocol::guarded(deps.as_ref(), now)?;
+    let user_info = marked_user_info(&config, &band_protocol, &stored_user_info, now)?;
+
+    let previous_tier = stored_user_info.tier;
+    let tier = user_info.tier;
+    if tier != previous_tier {
+        let mut stats = StakingStats::load(deps.storage)?;
+        stats.remove_user(previous_tier);
+        stats.add_user(tier);
+        stats.total_usd_deposit = stats
+            .total_usd_deposit
+            .checked_sub(stored_user_info.usd_deposit)
+            .and_then(|v| v.checked_add(user_info.usd_deposit))
+            .ok_or(ContractError::Overflow {})?;
+        stats.save(deps.storage)?;
+    }
+    TIER_USER_INFOS.save(deps.storage, address.clone(), &user_info)?;
+
+    let answer = to_json_binary(&ExecuteResponse::SyncTier {
+        tier,
+        status: ResponseStatus::Success,
+    })?;
+    let event = events::tier_synced(&address, previous_tier, tier, user_info.usd_deposit);
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+/// Caches the live ORAI price for the deposit price guard. Anyone may call it.
+fn refresh_price(deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
+    let config = Config::load(deps.storage)?;
+    config.assert_contract_active()?;
+
+    let band_protocol = BandProtocol::new(deps.as_ref())?;
+    let twap_window = config
+        .price_guard
+        .as_ref()
+        .map(|guard| guard.twap_window)
+        .unwrap_or_default();
+
+    let mut cache = PriceCache::load(deps.storage)?;
+    cache.refresh(band_protocol.price(), env.block.time.seconds(), twap_window);
+    if let Some(guard) = &config.price_guard {
+        cache.price = guard.clamp_to_twap(cache.price, cache.twap)?;
+    }
+    cache.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::RefreshPrice {
+        price: cache.price,
+        twap: cache.twap,
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .set_data(answer)
+        .add_event(events::price_refreshed(cache.price, cache.twap)))
 }
 
 pub fn get_received_funds(_deps: &DepsMut, info: &MessageInfo) -> Result<Coin, ContractError> {
     match info.funds.get(0) {
-        None => return Err(ContractError::Std(StdError::generic_err("No Funds"))),
+        None => Err(ContractError::NoFunds {}),
         Some(received) => {
             /* Amount of tokens received cannot be zero */
             if received.amount.is_zero() {
-                return Err(ContractError::Std(StdError::generic_err(
-                    "Not Allow Zero Amount",
-                )));
+                return Err(ContractError::ZeroAmount {});
             }
 
             /* Allow to receive only token denomination defined
             on contract instantiation "config.stable_denom" */
-            if received.denom.clone() != "orai" {
-                return Err(ContractError::Std(StdError::generic_err(
-                    "Unsopported token",
-                )));
+            if received.denom.clone() != ORAI {
+                return Err(ContractError::UnsupportedToken {
+                    denom: received.denom.clone(),
+                });
             }
 
             /* Only one token can be received */
             if info.funds.len() > 1 {
-                return Err(ContractError::Std(StdError::generic_err(
-                    "Not Allowed Multiple Funds",
-                )));
+                return Err(ContractError::MultipleFunds {});
             }
             Ok(received.clone())
         }
@@ -279,45 +669,33 @@ fn start_ido(
     assert_admin(&deps, &info.sender.to_string())?;
     let config = Config::load(deps.storage)?;
     if ido.remaining_tokens_per_tier.len() != config.min_tier as usize {
-        return Err(ContractError::Std(StdError::generic_err(
-            "`tokens_per_tier` has wrong size",
-        )));
+        return Err(ContractError::InvalidTokensPerTier {
+            expected: config.min_tier,
+        });
     }
 
     let sum = ido.remaining_tokens_per_tier.iter().sum::<u128>();
     if sum < ido.total_tokens_amount {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Sum of `tokens_per_tier` can't be less than total tokens amount",
-        )));
+        return Err(ContractError::TokensPerTierBelowTotal {});
     }
 
     if ido.start_time >= ido.end_time {
-        return Err(ContractError::Std(StdError::generic_err(
-            "End time must be greater than start time",
-        )));
+        return Err(ContractError::InvalidIdoTime {});
     }
 
     if ido.price == 0 {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Ido price should be initialized",
-        )));
+        return Err(ContractError::ZeroPrice {});
     }
     if env.block.time.seconds() >= ido.end_time {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Ido ends in the past",
-        )));
+        return Err(ContractError::IdoEndsInPast {});
     }
 
     if ido.soft_cap == 0 {
-        return Err(ContractError::Std(StdError::generic_err(
-            "soft_cap should be initialized.",
-        )));
+        return Err(ContractError::ZeroSoftCap {});
     }
 
     if ido.soft_cap > ido.total_tokens_amount {
-        return Err(ContractError::Std(StdError::generic_err(
-            "soft_cap should be less than total amount",
-        )));
+        return Err(ContractError::SoftCapAboveTotal {});
     }
     ido.shared_whitelist = match whitelist {
         Whitelist::Shared { .. } => true,
@@ -370,7 +748,97 @@ fn start_ido(
         status: ResponseStatus::Success,
     })?;
 
-    Ok(Response::new().set_data(answer).add_submessage(sub_msg))
+    let event = events::ido_started(
+        ido_id,
+        &ido.admin,
+        &ido.token_contract,
+        ido.price,
+        ido.total_tokens_amount,
+        ido.start_time,
+        ido.end_time,
+    );
+
+    Ok(Response::new()
+        .set_data(answer)
+        .add_submessage(sub_msg)
+        .add_event(event))
+}
+
+struct BuyQuote {
+    whitelisted: bool,
+    tier: u8,
+    payment: u128,
+    unlock_time: u64,
+    rejection: Option<BuyRejection>,
+}
+
+/// Runs the `BuyTokens` checks for `amount` tokens without touching storage.
+fn quote_buy(
+    deps: Deps,
+    env: &Env,
+    ido: &Ido,
+    sender: &String,
+    amount: u128,
+    viewing_key: Option<String>,
+) -> Result<BuyQuote, ContractError> {
+    let config = Config::load(deps.storage)?;
+    let whitelisted = utils::in_whitelist(deps.storage, sender, ido.id())?;
+
+    // the IDO-wide checks come first: the tier lookup below may query the price source or
+    // the NFT contract, and their errors must not hide these rejections
+    let rejection = if !ido.is_active(env.block.time.seconds()) {
+        Some(BuyRejection::IdoNotActive)
+    } else if amount == 0 {
+        Some(BuyRejection::ZeroAmount)
+    } else if ido.total_tokens_amount == ido.sold_amount {
+        Some(BuyRejection::AllTokensSold)
+    } else {
+        None
+    };
+    if rejection.is_some() {
+        return Ok(BuyQuote {
+            whitelisted,
+            tier: config.min_tier,
+            payment: 0,
+            unlock_time: 0,
+            rejection,
+        });
+    }
+
+    let tier = if whitelisted {
+        get_tier(deps, sender.clone(), viewing_key, env.block.time.seconds())?
+    } else {
+        config.min_tier
+    };
+
+    let payment = amount
+        .checked_div(ido.price)
+        .ok_or(ContractError::ZeroPrice {})?;
+    let lock_period = config.lock_period(tier);
+    let unlock_time = ido
+        .end_time
+        .checked_add(lock_period)
+        .ok_or(ContractError::Overflow {})?;
+
+    let remaining_amount = ido.remaining_tokens_per_tier(tier);
+    let rejection = if remaining_amount == 0 {
+        Some(BuyRejection::TierSoldOut { tier })
+    } else if amount > remaining_amount {
+        Some(BuyRejection::ExceedsTierRemaining {
+            tier,
+            remaining: Uint128::new(remaining_amount),
+        })
+    } else {
+        None
+    };
+
+    Ok(BuyQuote {
+        whitelisted,
+        tier,
+        payment,
+        unlock_time,
+        rejection,
+    })
 }
 
 fn buy_tokens(
@@ -387,51 +855,24 @@ fn buy_tokens(
     let canonical_sender = sender.to_string();
 
     let mut ido = Ido::load(deps.storage, ido_id)?;
-    if !ido.is_active(env.block.time.seconds()) {
-        return Err(ContractError::Std(StdError::generic_err(format!(
-            "IDO is not active {}",
-            env.block.time,
-        ))));
-    }
-
     if ido.is_native_payment() {
         let orai_amount = utils::sent_funds(&info.funds)?;
-        amount = orai_amount.checked_mul(ido.price).unwrap();
-    }
-
-    if amount == 0 {
-        return Err(ContractError::Std(StdError::generic_err("Zero amount")));
-    }
-
-    let config = Config::load(deps.storage)?;
-    let tier = if utils::in_whitelist(deps.storage, &sender, ido_id)? {
-        get_tier(&deps, sender.clone(), viewing_key.clone())?
-    } else {
-        config.min_tier
-    };
-
-    let remaining_amount = ido.remaining_tokens_per_tier(tier);
-    if remaining_amount == 0 {
-        if ido.total_tokens_amount == ido.sold_amount {
-            return Err(ContractError::Std(StdError::generic_err(
-                "All tokens are sold",
-            )));
-        } else {
-            return Err(ContractError::Std(StdError::generic_err(
-                "All tokens are sold for your tier",
-            )));
-        }
+        amount = orai_amount
+            .checked_mul(ido.price)
+            .ok_or(ContractError::Overflow {})?;
     }
 
-    if amount > remaining_amount {
-        let msg = format!("You cannot buy more than {} tokens", remaining_amount);
-        return Err(ContractError::Std(StdError::generic_err(&msg)));
+    let quote = quote_buy(deps.as_ref(), &env, &ido, &sender, amount, viewing_key)?;
+    if let Some(rejection) = quote.rejection {
+        return Err(rejection.into());
     }
 
-    let payment = amount.checked_div(ido.price).unwrap();
-    let lock_period = config.lock_period(tier);
-
-    let unlock_time = ido.end_time.checked_add(lock_period).unwrap();
+    let BuyQuote {
+        tier,
+        payment,
+        unlock_time,
+        ..
+    } = quote;
     let tokens_amount = Uint128::new(amount);
     let purchase = Purchase {
         timestamp: env.block.time.seconds(),
@@ -454,21 +895,33 @@ fn buy_tokens(
         .unwrap_or_default();
 
     if user_ido_info.total_payment == 0 {
-        ido.participants = ido.participants.checked_add(1).unwrap();
+        ido.participants = ido
+            .participants
+            .checked_add(1)
+            .ok_or(ContractError::Overflow {})?;
     }
 
-    user_ido_info.total_payment = user_ido_info.total_payment.checked_add(payment).unwrap();
+    user_ido_info.total_payment = user_ido_info
+        .total_payment
+        .checked_add(payment)
+        .ok_or(ContractError::Overflow {})?;
     user_ido_info.total_tokens_bought = user_ido_info
         .total_tokens_bought
         .checked_add(amount)
-        .unwrap();
+        .ok_or(ContractError::Overflow {})?;
 
     let mut user_info = USERINFO
         .may_load(deps.storage, canonical_sender.to_string())?
         .unwrap_or_default();
 
-    user_info.total_payment = user_info.total_payment.checked_add(payment).unwrap();
-    user_info.total_tokens_bought = user_info.total_tokens_bought.checked_add(amount).unwrap();
+    user_info.total_payment = user_info
+        .total_payment
+        .checked_add(payment)
+        .ok_or(ContractError::Overflow {})?;
+    user_info.total_tokens_bought = user_info
+        .total_tokens_bought
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow {})?;
 
     USERINFO.save(deps.storage, canonical_sender.to_string(), &user_info)?;
 
@@ -479,14 +932,23 @@ fn buy_tokens(
     )?;
 
     ACTIVE_IDOS.save(deps.storage, (canonical_sender.to_string(), ido_id), &true)?;
+    IDO_PARTICIPANTS.save(deps.storage, (ido_id, canonical_sender.to_string()), &tier)?;
 
-    ido.sold_amount = ido.sold_amount.checked_add(amount).unwrap();
-    ido.total_payment = ido.total_payment.checked_add(payment).unwrap();
-
-    let tier_index = tier.checked_sub(1).unwrap() as usize;
+    ido.sold_amount = ido
+        .sold_amount
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow {})?;
+    ido.total_payment = ido
+        .total_payment
+        .checked_add(payment)
+        .ok_or(ContractError::Overflow {})?;
+
+    let tier_index = tier
+        .checked_sub(1)
+        .ok_or(ContractError::InvalidTier { tier })? as usize;
     ido.remaining_tokens_per_tier[tier_index] = ido.remaining_tokens_per_tier[tier_index]
         .checked_sub(amount)
-        .unwrap();
+        .ok_or(ContractError::Overflow {})?;
 
     ido.save(deps.storage)?;
 
@@ -496,6 +958,9 @@ fn buy_tokens(
         status: ResponseStatus::Success,
     })?;
 
+    let event = events::tokens_bought(ido_id, &sender, tier, amount, payment, unlock_time);
+    let response = Response::new().set_data(answer).add_event(event);
+
     if !ido.is_native_payment() {
         let token_contract_canonical = ido.payment_token_contract.unwrap();
         // let token_contract_hash = ido.payment_token_hash.unwrap();
@@ -513,10 +978,10 @@ fn buy_tokens(
             funds: vec![],
         });
 
-        return Ok(Response::new().set_data(answer).add_submessage(sub_msg));
+        return Ok(response.add_submessage(sub_msg));
     }
     // else ---> scrt tokens are in the contract itself.
-    Ok(Response::new().set_data(answer))
+    Ok(response)
 }
 
 fn recv_tokens(
@@ -542,15 +1007,24 @@ fn recv_tokens(
         .unwrap_or_default();
 
     // when ido failed, withdraw the payment tokens.
-    if current_time.seconds() > ido.end_time && ido.soft_cap > ido.sold_amount {
+    if ido.is_failed(current_time.seconds()) {
+        let refund = user_ido_info.total_payment;
+        if refund == 0 {
+            return Err(ContractError::NothingToReceive {});
+        }
+
         user_info.total_payment = user_info
             .total_payment
             .checked_sub(user_ido_info.total_payment)
-            .unwrap_or_default();
+            .ok_or(ContractError::Overflow {})?;
         user_info.total_tokens_bought = user_info
-            .total_payment
+            .total_tokens_bought
             .checked_sub(user_ido_info.total_tokens_bought)
-            .unwrap_or_default();
+            .ok_or(ContractError::Overflow {})?;
+        user_info.total_tokens_received = user_info
+            .total_tokens_received
+            .checked_sub(user_ido_info.total_tokens_received)
+            .ok_or(ContractError::Overflow {})?;
         user_ido_info.total_tokens_received = 0;
         user_ido_info.total_tokens_bought = 0;
         user_ido_info.total_payment = 0;
@@ -565,26 +1039,28 @@ fn recv_tokens(
         ACTIVE_IDOS.remove(deps.storage, (canonical_sender.to_string(), ido_id));
 
         let answer = to_json_binary(&ExecuteResponse::RecvTokens {
-            amount: Uint128::new(user_info.total_payment),
+            amount: Uint128::new(refund),
             status: ResponseStatus::Success,
             ido_success: false,
         })?;
+        let response = Response::new()
+            .set_data(answer)
+            .add_event(events::ido_refunded(ido_id, &canonical_sender, refund));
 
         if ido.is_native_payment() {
             let transfer_msg = CosmosMsg::Bank(BankMsg::Send {
                 to_address: info.sender.to_string(),
-                amount: coins(user_ido_info.total_payment, ORAI),
+                amount: coins(refund, ORAI),
             });
-            return Ok(Response::new().set_data(answer).add_message(transfer_msg));
+            return Ok(response.add_message(transfer_msg));
         } else {
             let token_contract_canonical = ido.payment_token_contract.unwrap();
             // let token_contract_hash = ido.payment_token_hash.unwrap();
             let token_contract = token_contract_canonical.to_string();
 
-            let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-                owner: info.sender.to_string(),
-                recipient: env.contract.address.to_string(),
-                amount: Uint128::new(user_ido_info.total_payment),
+            let transfer_msg = Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: Uint128::new(refund),
             };
 
             let sub_msg = SubMsg::new(WasmMsg::Execute {
@@ -592,7 +1068,7 @@ fn recv_tokens(
                 msg: to_json_binary(&transfer_msg)?,
                 funds: vec![],
             });
-            return Ok(Response::new().set_data(answer).add_submessage(sub_msg));
+            return Ok(response.add_submessage(sub_msg));
         };
     }
     let start = start.unwrap_or(0);
@@ -605,19 +1081,23 @@ fn recv_tokens(
     let mut indices = Vec::new();
     for (i, purchase) in purchases_iter.enumerate() {
         if current_time.seconds() >= purchase.unlock_time {
-            let index = i.checked_add(start as usize).unwrap();
+            let index = i
+                .checked_add(start as usize)
+                .ok_or(ContractError::Overflow {})?;
             indices.push(index);
         }
     }
 
     if let Some(purchase_indices) = purchase_indices {
-        let end = start.checked_add(limit).unwrap();
+        let end = start.checked_add(limit).ok_or(ContractError::Overflow {})?;
         for index in purchase_indices {
             if index >= start && index < end {
                 continue;
             }
 
-            let purchase = purchases.get(index as usize).unwrap();
+            let purchase = purchases
+                .get(index as usize)
+                .ok_or(ContractError::InvalidPurchaseIndex { index })?;
             if current_time.seconds() >= purchase.unlock_time {
                 indices.push(index as usize);
             }
@@ -634,10 +1114,12 @@ fn recv_tokens(
         .unwrap_or_default();
 
     for (shift, index) in indices.into_iter().enumerate() {
-        let position = index.checked_sub(shift).unwrap();
+        let position = index.checked_sub(shift).ok_or(ContractError::Overflow {})?;
         let purchase = purchases.remove(position as usize);
 
-        recv_amount = recv_amount.checked_add(purchase.tokens_amount).unwrap();
+        recv_amount = recv_amount
+            .checked_add(purchase.tokens_amount)
+            .ok_or(ContractError::Overflow {})?;
         archived_purchases.push(purchase);
     }
     PURCHASES.save(
@@ -652,9 +1134,7 @@ fn recv_tokens(
     )?;
 
     if recv_amount == 0 {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Nothing to receive",
-        )));
+        return Err(ContractError::NothingToReceive {});
     }
 
     let answer = to_json_binary(&ExecuteResponse::RecvTokens {
@@ -666,12 +1146,12 @@ fn recv_tokens(
     user_info.total_tokens_received = user_info
         .total_tokens_received
         .checked_add(recv_amount)
-        .unwrap();
+        .ok_or(ContractError::Overflow {})?;
 
     user_ido_info.total_tokens_received = user_ido_info
         .total_tokens_received
         .checked_add(recv_amount)
-        .unwrap();
+        .ok_or(ContractError::Overflow {})?;
 
     USERINFO.save(deps.storage, canonical_sender.to_string(), &user_info)?;
 
@@ -687,9 +1167,8 @@ fn recv_tokens(
 
     let token_contract = ido.token_contract.to_string();
 
-    let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-        owner: info.sender.to_string(),
-        recipient: env.contract.address.to_string(),
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: info.sender.to_string(),
         amount: Uint128::new(recv_amount),
     };
 
@@ -698,7 +1177,12 @@ fn recv_tokens(
         msg: to_json_binary(&transfer_msg)?,
         funds: vec![],
     });
-    return Ok(Response::new().set_data(answer).add_submessage(sub_msg));
+    let event = events::tokens_claimed(ido_id, &canonical_sender, recv_amount);
+
+    Ok(Response::new()
+        .set_data(answer)
+        .add_submessage(sub_msg)
+        .add_event(event))
 }
 
 fn withdraw(
@@ -713,15 +1197,11 @@ fn withdraw(
 
     let mut ido = Ido::load(deps.storage, ido_id)?;
     if ido.withdrawn {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Already withdrawn",
-        )));
+        return Err(ContractError::AlreadyWithdrawn {});
     }
 
     if env.block.time.seconds() < ido.end_time {
-        return Err(ContractError::Std(StdError::generic_err(
-            "IDO is not finished yet",
-        )));
+        return Err(ContractError::IdoNotFinished {});
     }
 
     ido.withdrawn = true;
@@ -739,9 +1219,8 @@ fn withdraw(
     let mut msgs = vec![];
     let mut submsgs = vec![];
     if !remaining_tokens.is_zero() {
-        let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-            owner: ido_admin.to_string(),
-            recipient: env.contract.address.to_string(),
+        let transfer_msg = Cw20ExecuteMsg::Transfer {
+            recipient: ido_admin.to_string(),
             amount: remaining_tokens,
         };
 
@@ -754,21 +1233,20 @@ fn withdraw(
         submsgs.push(sub_msg);
     }
     //withdraw payment tokens.
-    let payment_amount = Uint128::new(ido.sold_amount.checked_div(ido.price).unwrap());
+    let payment_amount = Uint128::new(ido.total_payment);
     if ido.sold_amount >= ido.soft_cap {
         if ido.is_native_payment() {
             msgs.push(CosmosMsg::Bank(BankMsg::Send {
                 to_address: ido_admin,
-                amount: coins(ido.sold_amount.checked_div(ido.price).unwrap(), ORAI),
+                amount: coins(ido.total_payment, ORAI),
             }))
         } else {
             let token_contract_canonical = ido.payment_token_contract.unwrap();
             // let token_contract_hash = ido.payment_token_hash.unwrap();
             let token_contract = token_contract_canonical.to_string();
 
-            let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-                owner: ido_admin.to_string(),
-                recipient: env.contract.address.to_string(),
+            let transfer_msg = Cw20ExecuteMsg::Transfer {
+                recipient: ido_admin.to_string(),
                 amount: payment_amount,
             };
 
@@ -788,10 +1266,18 @@ fn withdraw(
         status: ResponseStatus::Success,
     })?;
 
+    let event = events::ido_withdrawn(
+        ido_id,
+        info.sender.as_str(),
+        remaining_tokens.u128(),
+        payment_amount.u128(),
+    );
+
     return Ok(Response::new()
         .set_data(answer)
         .add_messages(msgs)
-        .add_submessages(submsgs));
+        .add_submessages(submsgs)
+        .add_event(event));
 }
 
 fn whitelist_add(
@@ -805,7 +1291,7 @@ fn whitelist_add(
     assert_ido_admin(&deps, &info.sender.to_string(), ido_id)?;
 
     // let whitelist = state::ido_whitelist(ido_id);
-    for address in addresses {
+    for address in addresses.iter() {
         let canonical_address = address.to_string();
         WHITELIST.save(deps.storage, (ido_id, canonical_address), &true)?;
     }
@@ -813,8 +1299,9 @@ fn whitelist_add(
     let answer = to_json_binary(&ExecuteResponse::WhitelistAdd {
         status: ResponseStatus::Success,
     })?;
+    let event = events::whitelist_updated(ido_id, true, &addresses);
 
-    return Ok(Response::new().set_data(answer));
+    Ok(Response::new().set_data(answer).add_event(event))
 }
 
 fn whitelist_remove(
@@ -829,7 +1316,7 @@ fn whitelist_remove(
 
     // let whitelist = state::ido_whitelist(ido_id);
 
-    for address in addresses {
+    for address in addresses.iter() {
         let canonical_address = address.to_string();
         WHITELIST.save(deps.storage, (ido_id, canonical_address), &false)?;
     }
@@ -837,178 +1324,957 @@ fn whitelist_remove(
     let answer = to_json_binary(&ExecuteResponse::WhitelistRemove {
         status: ResponseStatus::Success,
     })?;
+    let event = events::whitelist_updated(ido_id, false, &addresses);
 
-    return Ok(Response::new().set_data(answer));
+    Ok(Response::new().set_data(answer).add_event(event))
 }
 
-fn try_deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let config = CONFIG_KEY.load(deps.storage)?;
-    config.assert_contract_active()?;
-
-    let received_funds = get_received_funds(&deps, &info)?;
-
-    let mut orai_deposit = received_funds.amount.u128();
-
-    let band_protocol: BandProtocol = BandProtocol::new(&deps)?;
-
-    let usd_deposit = band_protocol.usd_amount(orai_deposit);
+struct DepositOutcome {
+    tier: u8,
+    usd_deposit: u128,
+    orai_deposit: u128,
+    orai_refund: u128,
+}
 
-    let sender = info.sender.to_string();
-    let min_tier = config.min_tier();
+/// Computes the tier reached by adding `orai_amount` to `user_info` and the part of it that
+/// is refunded because it exceeds the tier threshold.
+fn deposit_outcome(
+    config: &Config,
+    band_protocol: &BandProtocol,
+    user_info: &state::TierUserInfo,
+    orai_amount: u128,
+) -> Result<DepositOutcome, ContractError> {
+    let usd_deposit = band_protocol.usd_amount(orai_amount)?;
 
-    let mut user_info =
-        TIER_USER_INFOS
-            .may_load(deps.storage, sender)?
-            .unwrap_or(state::TierUserInfo {
-                tier: min_tier,
-                ..Default::default()
-            });
     let current_tier = user_info.tier;
     let old_usd_deposit = user_info.usd_deposit;
-    let new_usd_deposit = old_usd_deposit.checked_add(usd_deposit).unwrap();
+    let new_usd_deposit = old_usd_deposit
+        .checked_add(usd_deposit)
+        .ok_or(ContractError::Overflow {})?;
 
     let new_tier = config.tier_by_deposit(new_usd_deposit);
 
     if current_tier == new_tier {
         if current_tier == config.max_tier() {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Reached max tier",
-            )));
+            return Err(ContractError::MaxTierReached {});
         }
 
-        let next_tier = current_tier.checked_sub(1).unwrap();
+        let next_tier = config.next_tier(current_tier)?;
         let next_tier_deposit = config.deposit_by_tier(next_tier);
 
-        let expected_deposit_usd = next_tier_deposit.checked_sub(old_usd_deposit).unwrap();
-        let expected_deposit_scrt = band_protocol.orai_amount(expected_deposit_usd);
+        let expected_deposit_usd = next_tier_deposit
+            .checked_sub(old_usd_deposit)
+            .ok_or(ContractError::Overflow {})?;
+        let expected_deposit_orai = band_protocol.orai_amount(expected_deposit_usd)?;
 
-        let err_msg = format!(
-            "You should deposit at least {} USD ({} ORAI)",
-            expected_deposit_usd, expected_deposit_scrt
-        );
-
-        return Err(ContractError::Std(StdError::generic_err(&err_msg)));
+        return Err(ContractError::BelowNextTier {
+            usd_needed: Uint128::new(expected_deposit_usd),
+            orai_needed: Uint128::new(expected_deposit_orai),
+        });
     }
 
-    let mut messages: Vec<SubMsg> = Vec::with_capacity(2);
     let new_tier_deposit = config.deposit_by_tier(new_tier);
 
-    let usd_refund = new_usd_deposit.checked_sub(new_tier_deposit).unwrap();
-    let orai_refund = band_protocol.orai_amount(usd_refund);
+    let usd_refund = new_usd_deposit
+        .checked_sub(new_tier_deposit)
+        .ok_or(ContractError::Overflow {})?;
+    let orai_refund = band_protocol.orai_amount(usd_refund)?;
 
-    if orai_refund != 0 {
-        orai_deposit = orai_deposit.checked_sub(orai_refund).unwrap();
+    Ok(DepositOutcome {
+        tier: new_tier,
+        usd_deposit: new_tier_deposit,
+        orai_deposit: orai_amount
+            .checked_sub(orai_refund)
+            .ok_or(ContractError::Overflow {})?,
+        orai_refund,
+    })
+}
 
-        let send_msg = BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: coins(orai_refund, ORAI),
-        };
+fn try_deposit(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG_KEY.load(deps.storage)?;
+    config.assert_contract_active()?;
 
-        let msg = CosmosMsg::Bank(send_msg);
-        messages.push(SubMsg::new(msg));
-    }
-    let old_orai_deposit = user_info.orai_deposit;
-    user_info.tier = new_tier;
-    user_info.timestamp = env.block.time.seconds();
-    user_info.usd_deposit = new_tier_deposit;
-    user_info.orai_deposit = user_info.orai_deposit.checked_add(orai_deposit).unwrap();
-    TIER_USER_INFOS.save(deps.storage, info.sender.to_string(), &user_info)?;
+    CircuitBreaker::load(deps.storage)?.assert_closed()?;
 
-    let validators = config.validators;
+    let received_funds = get_received_funds(&deps, &info)?;
 
-    for validator in validators {
-        let individual_amount = user_info
-            .orai_deposit
-            .checked_sub(old_orai_deposit)
-            .unwrap()
-            * validator.weight
-            / 100;
-        let delegate_msg = StakingMsg::Delegate {
-            validator: validator.address,
-            amount: coin(individual_amount, ORAI),
+    let sender = info.sender.to_string();
+    let min_tier = config.min_tier();
+
+    let now = env.block.time.seconds();
+    let band_protocol = match BandProtocol::guarded(deps.as_ref(), now) {
+        Ok(band_protocol) => band_protocol,
+        Err(
+            err @ (ContractError::PriceUnavailable {} | ContractError::PriceSourcesDisagree { .. }),
+        ) => return trip_circuit_breaker(deps, env, info, &config, err),
+        Err(err) => return Err(err),
+    };
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+
+    let stored_user_info = TierUserInfo::may_load(deps.storage, &sender)?;
+    let is_new_user = stored_user_info.is_none();
+    let mut user_info = stored_user_info.unwrap_or(state::TierUserInfo {
+        tier: min_tier,
+        slashing_index: Some(slashing::index(deps.storage)?),
+        ..Default::default()
+    });
+
+    // a mark-to-market tier is synced before the deposit adds to it
+    let marked = marked_user_info(&config, &band_protocol, &user_info, now)?;
+    let outcome = deposit_outcome(
+        &config,
+        &band_protocol,
+        &marked,
+        received_funds.amount.u128(),
+    )?;
+
+    let mut messages: Vec<SubMsg> = harvest.messages;
+    if outcome.orai_refund != 0 {
+        let send_msg = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(outcome.orai_refund, ORAI),
         };
 
-        let msg: CosmosMsg = CosmosMsg::Staking(delegate_msg);
+        let msg = CosmosMsg::Bank(send_msg);
         messages.push(SubMsg::new(msg));
     }
+    let mut stats = StakingStats::load(deps.storage)?;
+    if !is_new_user {
+        stats.remove_user(user_info.tier);
+    }
+    stats.add_user(outcome.tier);
+    stats.total_orai_deposit = stats
+        .total_orai_deposit
+        .checked_add(outcome.orai_deposit)
+        .ok_or(ContractError::Overflow {})?;
+    stats.total_usd_deposit = stats
+        .total_usd_deposit
+        .checked_add(outcome.usd_deposit)
+        .and_then(|v| v.checked_sub(user_info.usd_deposit))
+        .ok_or(ContractError::Overflow {})?;
+    stats.save(deps.storage)?;
+
+    let old_orai_deposit = user_info.orai_deposit;
+    rewards::settle(deps.storage, info.sender.as_str(), old_orai_deposit)?;
+    user_info.tier = outcome.tier;
+    user_info.below_tier_since = None;
+    user_info.timestamp = now;
+    user_info.usd_deposit = outcome.usd_deposit;
+    user_info.orai_deposit = user_info
+        .orai_deposit
+        .checked_add(outcome.orai_deposit)
+        .ok_or(ContractError::Overflow {})?;
+    TIER_USER_INFOS.save(deps.storage, info.sender.to_string(), &user_info)?;
+
+    let delegated_amount = outcome
+        .orai_deposit
+        .checked_add(harvest.restake)
+        .ok_or(ContractError::Overflow {})?;
+    messages.extend(delegations::delegate(
+        deps.storage,
+        &config.validators,
+        delegated_amount,
+    )?);
 
     let answer = to_json_binary(&ExecuteResponse::Deposit {
         usd_deposit: Uint128::new(user_info.usd_deposit),
         orai_deposit: Uint128::new(user_info.orai_deposit),
-        tier: new_tier,
+        tier: outcome.tier,
         status: ResponseStatus::Success,
     })?;
+    let event = events::tier_deposit(
+        info.sender.as_str(),
+        received_funds.amount.u128(),
+        outcome.orai_refund,
+        user_info.orai_deposit,
+        user_info.usd_deposit,
+        outcome.tier,
+    );
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_event(event))
+}
+
+/// Pauses tier deposits once no trustworthy price is left. The deposit is refunded instead of
+/// failing so that the tripped breaker is persisted.
+fn trip_circuit_breaker(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+    err: ContractError,
+) -> Result<Response, ContractError> {
+    let reason = err.to_string();
+    let breaker = CircuitBreaker {
+        tripped_at: Some(env.block.time.seconds()),
+        reason: Some(reason.clone()),
+    };
+    breaker.save(deps.storage)?;
+
+    let user_info = TierUserInfo::may_load(deps.storage, info.sender.as_str())?;
+    let (usd_deposit, orai_deposit, tier) = user_info
+        .map(|user_info| {
+            (
+                user_info.usd_deposit,
+                user_info.orai_deposit,
+                user_info.tier,
+            )
+        })
+        .unwrap_or((0, 0, config.min_tier()));
+
+    let answer = to_json_binary(&ExecuteResponse::Deposit {
+        usd_deposit: Uint128::new(usd_deposit),
+        orai_deposit: Uint128::new(orai_deposit),
+        tier,
+        status: ResponseStatus::Failure,
+    })?;
 
-    Ok(Response::new().add_submessages(messages).set_data(answer))
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: info.funds,
+        })
+        .set_data(answer)
+        .add_event(events::circuit_breaker_tripped(&reason)))
 }
 
+/// Unbonds `amount` uORAI from the sender's tier deposit, or all of it. A partial withdrawal
+/// moves the sender down to the tier the remaining ORAI is worth, so like a deposit it needs a
+/// price the guard accepts; while none can be had only full withdrawals go through.
 pub fn withdraw_from_tier(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG_KEY.load(deps.storage)?;
     config.assert_contract_active()?;
 
     let sender = info.sender.to_string();
 
-    let min_tier = config.min_tier();
-    let user_info =
-        TIER_USER_INFOS
-            .may_load(deps.storage, sender)?
-            .unwrap_or(state::TierUserInfo {
-                tier: min_tier,
-                ..Default::default()
-            });
+    let mut user_info = TierUserInfo::may_load(deps.storage, &sender)?
+        .ok_or(ContractError::NothingToWithdraw {})?;
+    let amount = match amount {
+        None => user_info.orai_deposit,
+        Some(amount) if amount.is_zero() => return Err(ContractError::ZeroAmount {}),
+        Some(amount) if amount.u128() > user_info.orai_deposit => {
+            return Err(ContractError::InsufficientDeposit {
+                available: Uint128::new(user_info.orai_deposit),
+            })
+        }
+        Some(amount) => amount.u128(),
+    };
 
-    let amount = user_info.orai_deposit;
+    let now = env.block.time.seconds();
+    let unlock_time = user_info.unlock_time(&config.tier_locks);
+    let (penalty, penalty_recipient) = match &config.tier_locks.early_exit {
+        _ if now >= unlock_time => (0, None),
+        EarlyExitPolicy::Reject {} => return Err(ContractError::TierLocked { unlock_time }),
+        EarlyExitPolicy::Penalty { bps, recipient } => {
+            let penalty = Uint128::new(amount).multiply_ratio(*bps, 10_000u128);
+            (penalty.u128(), Some(recipient))
+        }
+    };
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+    rewards::settle(deps.storage, &sender, user_info.orai_deposit)?;
 
-    TIER_USER_INFOS.remove(deps.storage, info.sender.to_string());
+    let mut stats = StakingStats::load(deps.storage)?;
+    stats.remove_user(user_info.tier);
+    stats.total_orai_deposit = stats
+        .total_orai_deposit
+        .checked_sub(amount)
+        .ok_or(ContractError::Overflow {})?;
+    stats.total_usd_deposit = stats
+        .total_usd_deposit
+        .checked_sub(user_info.usd_deposit)
+        .ok_or(ContractError::Overflow {})?;
+
+    let remaining = user_info
+        .orai_deposit
+        .checked_sub(amount)
+        .ok_or(ContractError::Overflow {})?;
+    let tier = if remaining == 0 {
+        TIER_USER_INFOS.remove(deps.storage, sender.clone());
+        config.min_tier()
+    } else {
+        let band_protocol = BandProtocol::guarded(deps.as_ref(), now)?;
+        let usd_value = band_protocol.usd_amount(remaining)?;
+        // never a better tier than the one the full deposit held
+        let tier = config.tier_by_deposit(usd_value).max(user_info.tier);
+
+        user_info.tier = tier;
+        user_info.usd_deposit = config.tier_usd_deposit(tier, usd_value);
+        user_info.orai_deposit = remaining;
+        user_info.below_tier_since = None;
+        TIER_USER_INFOS.save(deps.storage, sender.clone(), &user_info)?;
+
+        stats.add_user(tier);
+        stats.total_usd_deposit = stats
+            .total_usd_deposit
+            .checked_add(user_info.usd_deposit)
+            .ok_or(ContractError::Overflow {})?;
+        tier
+    };
 
-    let current_time = env.block.time.seconds();
-    let claim_time = current_time.checked_add(UNBOUND_LATENCY).unwrap();
-    let withdrawal: UserWithdrawal = UserWithdrawal {
+    let payout = amount - penalty;
+    let mut unbonded = payout;
+    let unbonding = unbonding::current(&deps.querier, &config.unbonding)?;
+    let claim_time = queue_withdrawal(deps.storage, &unbonding, &sender, payout, now)?;
+    let mut penalty_events = vec![];
+    match penalty_recipient {
+        None => {}
+        Some(PenaltyRecipient::Treasury { address }) => {
+            queue_withdrawal(deps.storage, &unbonding, address, penalty, now)?;
+            unbonded = amount;
+            penalty_events.push(events::early_exit_penalty(&sender, penalty, "treasury"));
+        }
+        Some(PenaltyRecipient::Stakers {}) => {
+            // the sender's remaining deposit takes no share of their own penalty
+            let others_staked = stats
+                .total_orai_deposit
+                .checked_sub(remaining)
+                .ok_or(ContractError::Overflow {})?;
+            let mut pool = RewardPool::load(deps.storage)?;
+            pool.distribute(penalty, others_staked)?;
+            pool.save(deps.storage)?;
+            let mut rewards = StakerRewards::load(deps.storage, &sender)?;
+            rewards.index = pool.index;
+            rewards.save(deps.storage, &sender)?;
+            penalty_events.push(events::early_exit_penalty(&sender, penalty, "stakers"));
+        }
+    }
+
+    stats.total_pending_withdrawals = stats
+        .total_pending_withdrawals
+        .checked_add(unbonded)
+        .ok_or(ContractError::Overflow {})?;
+    stats.save(deps.storage)?;
+
+    let mut messages = harvest.messages;
+    messages.extend(delegations::delegate(
+        deps.storage,
+        &config.validators,
+        harvest.restake,
+    )?);
+
+    let answer = to_json_binary(&ExecuteResponse::WithdrawFromTier {
+        status: ResponseStatus::Success,
+    })?;
+    let event = events::tier_withdraw(info.sender.as_str(), payout, claim_time, tier);
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_event(event)
+        .add_events(penalty_events))
+}
+
+/// Queues `amount` uORAI for `address` in the open unbonding epoch, and returns the estimated
+/// claim time.
+fn queue_withdrawal(
+    storage: &mut dyn Storage,
+    unbonding: &Unbonding,
+    address: &str,
+    amount: u128,
+    now: u64,
+) -> Result<u64, ContractError> {
+    if amount == 0 {
+        return Ok(UnbondingEpoch::open(storage)?.claim_time(unbonding, now));
+    }
+
+    let epoch = unbonding::queue(storage, amount)?;
+    let claim_time = epoch.claim_time(unbonding, now);
+    let slashing_index = slashing::index(storage)?;
+    let mut withdrawals = WITHDRAWALS_LIST
+        .may_load(storage, address.to_string())?
+        .unwrap_or_default();
+    withdrawals.push(UserWithdrawal {
         amount,
-        timestamp: current_time,
+        timestamp: now,
         claim_time,
-    };
+        epoch: Some(epoch.id),
+        slashing_index: Some(slashing_index),
+    });
+    WITHDRAWALS_LIST.save(storage, address.to_string(), &withdrawals)?;
 
-    let mut withdrawals = WITHDRAWALS_LIST
-        .may_load(deps.storage, info.sender.to_string())?
+    Ok(claim_time)
+}
+
+fn update_tier_locks(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    tier_locks: TierLocks,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let mut config = Config::load(deps.storage)?;
+    validate_tier_locks(deps.as_ref(), &config, &tier_locks)?;
+
+    let event = events::tier_locks_updated(&tier_locks);
+    config.tier_locks = tier_locks;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdateTierLocks {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn validate_tier_locks(
+    deps: Deps,
+    config: &Config,
+    tier_locks: &TierLocks,
+) -> Result<(), ContractError> {
+    let tiers = config.usd_deposits.len();
+    if !tier_locks.min_lock_durations.is_empty() && tier_locks.min_lock_durations.len() != tiers {
+        return Err(ContractError::InvalidTierLocks {
+            expected: tiers as u8,
+        });
+    }
+
+    if let EarlyExitPolicy::Penalty { bps, recipient } = &tier_locks.early_exit {
+        if *bps > 10_000 {
+            return Err(ContractError::InvalidPenalty {});
+        }
+        if let PenaltyRecipient::Treasury { address } = recipient {
+            deps.api.addr_validate(address)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn update_protocol_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    protocol_fee: Option<ProtocolFee>,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+    if let Some(protocol_fee) = &protocol_fee {
+        validate_protocol_fee(deps.as_ref(), protocol_fee)?;
+    }
+
+    let mut config = Config::load(deps.storage)?;
+    let event = events::protocol_fee_updated(protocol_fee.as_ref());
+    config.protocol_fee = protocol_fee;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::UpdateProtocolFee {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn validate_protocol_fee(deps: Deps, protocol_fee: &ProtocolFee) -> Result<(), ContractError> {
+    if protocol_fee.bps > 10_000 {
+        return Err(ContractError::InvalidProtocolFee {});
+    }
+    deps.api.addr_validate(&protocol_fee.recipient)?;
+
+    Ok(())
+}
+
+/// Unbonds the sender's share of staking rewards and early-exit penalties.
+fn claim_staking_rewards(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = Config::load(deps.storage)?;
+    config.assert_contract_active()?;
+
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+
+    let sender = info.sender.to_string();
+    let staked = TierUserInfo::may_load(deps.storage, &sender)?
+        .map(|user_info| user_info.orai_deposit)
         .unwrap_or_default();
+    let mut staker_rewards = rewards::settle(deps.storage, &sender, staked)?;
+    let amount = staker_rewards.pending;
+    if amount == 0 {
+        return Err(ContractError::NoRewards {});
+    }
+    staker_rewards.pending = 0;
+    staker_rewards.save(deps.storage, &sender)?;
 
-    withdrawals.push(withdrawal);
-    WITHDRAWALS_LIST.save(deps.storage, info.sender.to_string(), &withdrawals)?;
+    let mut pool = RewardPool::load(deps.storage)?;
+    pool.outstanding = pool
+        .outstanding
+        .checked_sub(amount)
+        .ok_or(ContractError::Overflow {})?;
+    pool.save(deps.storage)?;
+
+    let unbonding = unbonding::current(&deps.querier, &config.unbonding)?;
+    let claim_time = queue_withdrawal(
+        deps.storage,
+        &unbonding,
+        &sender,
+        amount,
+        env.block.time.seconds(),
+    )?;
+    let mut stats = StakingStats::load(deps.storage)?;
+    stats.total_pending_withdrawals = stats
+        .total_pending_withdrawals
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow {})?;
+    stats.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::ClaimStakingRewards {
+        amount: Uint128::new(amount),
+        status: ResponseStatus::Success,
+    })?;
+    let mut messages = harvest.messages;
+    messages.extend(delegations::delegate(
+        deps.storage,
+        &config.validators,
+        harvest.restake,
+    )?);
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_event(events::staking_rewards_claimed(&sender, amount, claim_time)))
+}
+
+fn set_auto_compound(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.to_string();
+    let mut staker_rewards = StakerRewards::load(deps.storage, &sender)?;
+    staker_rewards.auto_compound = enabled;
+    staker_rewards.save(deps.storage, &sender)?;
+
+    let answer = to_json_binary(&ExecuteResponse::SetAutoCompound {
+        enabled,
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .set_data(answer)
+        .add_event(events::auto_compound_set(&sender, enabled)))
+}
+
+/// Adds the pending staking rewards of `addresses` to their tier deposits, skipping those
+/// that did not opt in or have no deposit. The rewards are already delegated, and the
+/// compounded USD value can lift the tier. Anyone may call it.
+fn compound(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = Config::load(deps.storage)?;
+    config.assert_contract_active()?;
+    CircuitBreaker::load(deps.storage)?.assert_closed()?;
+
+    let band_protocol = BandProtocol::guarded(deps.as_ref(), env.block.time.seconds())?;
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+
+    let mut stats = StakingStats::load(deps.storage)?;
+    let mut pool = RewardPool::load(deps.storage)?;
+    let mut compounded: u128 = 0;
+    let mut compound_events = vec![];
+    for address in addresses {
+        let mut user_info = match TierUserInfo::may_load(deps.storage, &address)? {
+            Some(user_info) => user_info,
+            None => continue,
+        };
+        let mut staker_rewards = StakerRewards::load(deps.storage, &address)?;
+        if !staker_rewards.auto_compound {
+            continue;
+        }
+        staker_rewards.settle(&pool, user_info.orai_deposit)?;
+        let amount = staker_rewards.pending;
+        staker_rewards.pending = 0;
+        staker_rewards.save(deps.storage, &address)?;
+        if amount == 0 {
+            continue;
+        }
+
+        let usd_deposit = user_info
+            .usd_deposit
+            .checked_add(band_protocol.usd_amount(amount)?)
+            .ok_or(ContractError::Overflow {})?;
+        // compounding never costs a tier
+        let tier = config.tier_by_deposit(usd_deposit).min(user_info.tier);
+
+        stats.remove_user(user_info.tier);
+        stats.add_user(tier);
+        stats.total_orai_deposit = stats
+            .total_orai_deposit
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow {})?;
+        stats.total_usd_deposit = stats
+            .total_usd_deposit
+            .checked_sub(user_info.usd_deposit)
+            .and_then(|v| v.checked_add(usd_deposit))
+            .ok_or(ContractError::Overflow {})?;
+        pool.outstanding = pool
+            .outstanding
+            .checked_sub(amount)
+            .ok_or(ContractError::Overflow {})?;
+
+        user_info.tier = tier;
+        user_info.usd_deposit = usd_deposit;
+        user_info.orai_deposit = user_info
+            .orai_deposit
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow {})?;
+        TIER_USER_INFOS.save(deps.storage, address.clone(), &user_info)?;
+
+        compounded = compounded
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow {})?;
+        compound_events.push(events::rewards_compounded(
+            &address,
+            amount,
+            user_info.orai_deposit,
+            tier,
+        ));
+    }
+    stats.save(deps.storage)?;
+    pool.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::Compound {
+        amount: Uint128::new(compounded),
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .add_submessages(harvest.messages)
+        .add_submessages(delegations::delegate(
+            deps.storage,
+            &config.validators,
+            harvest.restake,
+        )?)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_events(compound_events))
+}
+
+/// Adds a validator with no weight. `SetWeights` and `Rebalance` then move stake to it.
+fn add_validator(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let mut config = Config::load(deps.storage)?;
+    if config
+        .validators
+        .iter()
+        .any(|validator| validator.address == address)
+    {
+        return Err(ContractError::DuplicateValidator { address });
+    }
+    if deps.querier.query_validator(&address)?.is_none() {
+        return Err(ContractError::UnknownValidator { address });
+    }
+
+    let event = events::validator_added(&address);
+    config
+        .validators
+        .push(ValidatorWithWeight { address, weight: 0 });
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::AddValidator {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+/// Removes a validator once its weight is zero and `Rebalance` moved its delegation away.
+fn remove_validator(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let mut config = Config::load(deps.storage)?;
+    let index = config
+        .validators
+        .iter()
+        .position(|validator| validator.address == address)
+        .ok_or_else(|| ContractError::UnknownValidator {
+            address: address.clone(),
+        })?;
+    let delegated = delegations::delegated(deps.storage, &address)?;
+    if config.validators[index].weight != 0 || delegated != 0 {
+        return Err(ContractError::ValidatorInUse { address });
+    }
+
+    config.validators.remove(index);
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::RemoveValidator {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .set_data(answer)
+        .add_event(events::validator_removed(&address)))
+}
+
+/// Sets the weight of every validator in the set. New deposits follow the weights right away,
+/// existing delegations once `Rebalance` runs.
+fn set_weights(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    validators: Vec<ValidatorWithWeight>,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let mut config = Config::load(deps.storage)?;
+    validate_validators(&validators)?;
+    let same_set = validators.len() == config.validators.len()
+        && config.validators.iter().all(|validator| {
+            validators
+                .iter()
+                .any(|other| other.address == validator.address)
+        });
+    if !same_set {
+        return Err(ContractError::InvalidValidatorWeights {});
+    }
+
+    let event = events::validator_weights_set(&validators);
+    config.validators = validators;
+    config.save(deps.storage)?;
+
+    let answer = to_json_binary(&ExecuteResponse::SetWeights {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
+
+fn validate_validators(validators: &[ValidatorWithWeight]) -> Result<(), ContractError> {
+    if validators.is_empty() {
+        return Err(ContractError::InvalidValidatorWeights {});
+    }
+    for (index, validator) in validators.iter().enumerate() {
+        if validators[..index]
+            .iter()
+            .any(|other| other.address == validator.address)
+        {
+            return Err(ContractError::DuplicateValidator {
+                address: validator.address.clone(),
+            });
+        }
+    }
+
+    let total_weight = validators.iter().try_fold(0u128, |total, validator| {
+        total.checked_add(validator.weight)
+    });
+    if total_weight != Some(10_000) {
+        return Err(ContractError::InvalidValidatorWeights {});
+    }
+
+    Ok(())
+}
+
+/// Redelegates from validators above their target weight to those below it. Only the
+/// redelegatable part of a delegation moves, so a recently redelegated stake may need another
+/// `Rebalance` once its redelegation matures.
+fn rebalance(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+
+    let config = Config::load(deps.storage)?;
+    // redelegating pays out the rewards of both validators
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+
+    let mut held = Vec::with_capacity(config.validators.len());
+    for validator in &config.validators {
+        let can_redelegate = utils::query_delegation(&deps, &env, &validator.address)?
+            .map(|delegation| delegation.can_redelegate.amount.u128())
+            .unwrap_or_default();
+        let delegated = delegations::delegated(deps.storage, &validator.address)?;
+        held.push((delegated, can_redelegate));
+    }
+    let total: u128 = held.iter().map(|(amount, _)| amount).sum();
+    let targets = delegations::split_by_weight(&config.validators, total);
+
+    let mut surpluses = vec![];
+    let mut deficits = vec![];
+    for ((validator, target), (amount, can_redelegate)) in targets.into_iter().zip(held) {
+        if amount > target {
+            surpluses.push((validator, (amount - target).min(can_redelegate)));
+        } else if target > amount {
+            deficits.push((validator, target - amount));
+        }
+    }
+
+    let mut messages = harvest.messages;
+    let mut redelegate_events = vec![];
+    let mut moved: u128 = 0;
+    let mut deficits = deficits.into_iter().peekable();
+    for (src_validator, mut surplus) in surpluses {
+        while surplus != 0 {
+            let (dst_validator, deficit) = match deficits.peek_mut() {
+                Some(deficit) => deficit,
+                None => break,
+            };
+            let amount = surplus.min(*deficit);
+            surplus -= amount;
+            *deficit -= amount;
+            moved += amount;
+
+            redelegate_events.push(events::redelegated(&src_validator, dst_validator, amount));
+            messages.push(delegations::redelegate(
+                deps.storage,
+                &src_validator,
+                dst_validator,
+                amount,
+            )?);
+            if *deficit == 0 {
+                deficits.next();
+            }
+        }
+    }
+    messages.extend(delegations::delegate(
+        deps.storage,
+        &config.validators,
+        harvest.restake,
+    )?);
+
+    let answer = to_json_binary(&ExecuteResponse::Rebalance {
+        amount: Uint128::new(moved),
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_events(redelegate_events))
+}
+
+/// Undelegates the uORAI withdrawn during the open epoch in one batch and opens the next
+/// epoch. Anyone may call it once the epoch duration passed.
+fn process_unbonding_epoch(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = Config::load(deps.storage)?;
+    config.assert_contract_active()?;
+
+    let now = env.block.time.seconds();
+    let unbonding = unbonding::current(&deps.querier, &config.unbonding)?;
+    let mut epoch = UnbondingEpoch::open(deps.storage)?;
+    epoch.process(deps.storage, &unbonding, now)?;
+
+    // undelegating pays out the pending rewards anyway
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+    let mut messages = harvest.messages;
+    messages.extend(delegations::undelegate(deps.storage, epoch.amount)?);
+    messages.extend(delegations::delegate(
+        deps.storage,
+        &config.validators,
+        harvest.restake,
+    )?);
+
+    let claim_time = epoch.claim_time(&unbonding, now);
+    let answer = to_json_binary(&ExecuteResponse::ProcessUnbondingEpoch {
+        epoch: epoch.id,
+        amount: Uint128::new(epoch.amount),
+        claim_time,
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new()
+        .add_submessages(messages)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_event(events::unbonding_epoch_processed(
+            epoch.id,
+            epoch.amount,
+            claim_time,
+        )))
+}
+
+fn update_unbonding(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    unbonding: Unbonding,
+) -> Result<Response, ContractError> {
+    assert_admin(&deps, &info.sender.to_string())?;
+    validate_unbonding(deps.as_ref(), &unbonding)?;
 
-    let validators = config.validators;
-    let amount = coin(amount - 4, ORAI);
+    let mut config = Config::load(deps.storage)?;
+    let event = events::unbonding_updated(&unbonding);
+    config.unbonding = unbonding;
+    config.save(deps.storage)?;
 
-    let mut messages: Vec<SubMsg> = Vec::with_capacity(2);
+    let answer = to_json_binary(&ExecuteResponse::UpdateUnbonding {
+        status: ResponseStatus::Success,
+    })?;
+    Ok(Response::new().set_data(answer).add_event(event))
+}
 
-    for validator in validators {
-        let weight_as_uint128 = Uint128::from(validator.weight);
+fn validate_unbonding(deps: Deps, unbonding: &Unbonding) -> Result<(), ContractError> {
+    if unbonding.epoch_duration == 0 || unbonding.unbonding_time == 0 {
+        return Err(ContractError::InvalidUnbonding {});
+    }
+    // fail now rather than on the next withdrawal
+    if unbonding.from_chain {
+        unbonding::chain_unbonding_time(&deps.querier)?;
+    }
 
-        // Perform the multiplication - Uint128 * Uint128
-        let multiplied = amount
-            .amount
-            .multiply_ratio(weight_as_uint128, Uint128::from(100_u128));
+    Ok(())
+}
 
-        // Now, `multiplied` is Uint128, but we want the result as u128
-        let individual_amount: u128 = multiplied.u128();
+/// Writes validator slashes off the delegation ledger and shares the loss pro rata between
+/// everything the delegations back. Anyone may call it.
+fn reconcile_delegations(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = Config::load(deps.storage)?;
+    config.assert_contract_active()?;
 
-        let withdraw_msg = StakingMsg::Undelegate {
-            validator: validator.address,
-            amount: coin(individual_amount, ORAI),
-        };
-        let msg = CosmosMsg::Staking(withdraw_msg);
-        messages.push(SubMsg::new(msg));
+    let ledger = delegations::ledger(deps.storage)?;
+    let total: u128 = ledger.iter().map(|(_, delegated)| delegated).sum();
+    let mut slashed: u128 = 0;
+    let mut slash_events = vec![];
+    for (validator, _) in ledger {
+        let live = deps
+            .querier
+            .query_delegation(&env.contract.address, &validator)?
+            .map(|delegation| delegation.amount.amount.u128())
+            .unwrap_or_default();
+        let amount = delegations::write_down(deps.storage, &validator, live)?;
+        if amount != 0 {
+            slashed += amount;
+            slash_events.push(events::validator_slashed(&validator, amount));
+        }
+    }
+    if slashed == 0 {
+        return Err(ContractError::NoSlashing {});
     }
 
-    let answer = to_json_binary(&ExecuteResponse::WithdrawFromTier {
+    let ratio = Decimal::from_ratio(total - slashed, total);
+    let index = slashing::record(deps.storage, ratio, env.block.time.seconds())?;
+
+    let answer = to_json_binary(&ExecuteResponse::ReconcileDelegations {
+        slashed: Uint128::new(slashed),
+        ratio,
         status: ResponseStatus::Success,
     })?;
-
-    Ok(Response::new().add_submessages(messages).set_data(answer))
+    Ok(Response::new()
+        .set_data(answer)
+        .add_events(slash_events)
+        .add_event(events::delegations_reconciled(slashed, ratio, index)))
 }
 
 pub fn try_claim(
@@ -1024,15 +2290,13 @@ pub fn try_claim(
 
     let sender = info.sender.to_string();
     let mut withdrawals = WITHDRAWALS_LIST
-        .may_load(deps.storage, sender)?
+        .may_load(deps.storage, sender.clone())?
         .unwrap_or_default();
 
     let length = withdrawals.len();
 
     if length == 0 {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Nothing to claim",
-        )));
+        return Err(ContractError::NothingToClaim {});
     }
 
     let recipient = recipient.unwrap_or(info.sender.to_string());
@@ -1046,25 +2310,32 @@ pub fn try_claim(
     let mut claim_amount = 0u128;
 
     for (index, withdrawal) in withdrawals_iter.enumerate() {
-        let claim_time = withdrawal.claim_time;
-
-        if current_time >= claim_time {
-            remove_indices.push(index);
-            claim_amount = claim_amount.checked_add(withdrawal.amount).unwrap();
+        let claimable =
+            unbonding::claimable(deps.storage, &config.unbonding, withdrawal, current_time)?;
+
+        if claimable.unbonded && current_time >= claimable.claim_time {
+            remove_indices.push(index.checked_add(start).ok_or(ContractError::Overflow {})?);
+            claim_amount = claim_amount
+                .checked_add(claimable.amount)
+                .ok_or(ContractError::Overflow {})?;
         }
     }
 
     if claim_amount == 0 {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Nothing to claim",
-        )));
+        return Err(ContractError::NothingToClaim {});
     }
 
     for (shift, index) in remove_indices.into_iter().enumerate() {
-        let position = index.checked_sub(shift).unwrap();
+        let position = index.checked_sub(shift).ok_or(ContractError::Overflow {})?;
         withdrawals.remove(position);
     }
+    WITHDRAWALS_LIST.save(deps.storage, sender, &withdrawals)?;
 
+    let mut stats = StakingStats::load(deps.storage)?;
+    stats.total_pending_withdrawals = stats.total_pending_withdrawals.saturating_sub(claim_amount);
+    stats.save(deps.storage)?;
+
+    let event = events::withdrawal_claimed(info.sender.as_str(), &recipient, claim_amount);
     let send_msg = BankMsg::Send {
         to_address: recipient,
         amount: coins(claim_amount, ORAI),
@@ -1076,57 +2347,48 @@ pub fn try_claim(
         status: ResponseStatus::Success,
     })?;
 
-    Ok(Response::new().add_message(msg).set_data(answer))
+    Ok(Response::new()
+        .add_message(msg)
+        .set_data(answer)
+        .add_event(event))
 }
 
+/// Harvests the staking rewards of every validator for the stakers. Anyone may call it.
 pub fn try_withdraw_rewards(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
-    _recipient: Option<String>,
+    _info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let config: Config = CONFIG_KEY.load(deps.storage)?;
-    if info.sender.clone() != config.admin {
-        return Err(ContractError::Std(StdError::generic_err("Unauthorized")));
-    }
-
-    let temp_validator = &config.validators[0];
-    let validator = temp_validator.clone().address;
-    let delegation = utils::query_delegation(&deps, &env, &validator);
-
-    let can_withdraw = delegation
-        .map(|d| d.unwrap().accumulated_rewards[0].amount.u128())
-        .unwrap_or(0);
-
-    if can_withdraw == 0 {
-        return Err(ContractError::Std(StdError::generic_err(
-            "There is nothing to withdraw",
-        )));
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+    if harvest.rewards == 0 {
+        return Err(ContractError::NoRewards {});
     }
+    let delegate_messages =
+        delegations::delegate(deps.storage, &config.validators, harvest.restake)?;
 
-    // let admin = config.admin;
-    // let recipient = recipient.unwrap_or(admin);
-    let withdraw_msg = DistributionMsg::WithdrawDelegatorReward { validator };
-
-    let msg = CosmosMsg::Distribution(withdraw_msg);
     let answer = to_json_binary(&ExecuteResponse::WithdrawRewards {
-        amount: Uint128::new(can_withdraw),
+        amount: Uint128::new(harvest.rewards),
         status: ResponseStatus::Success,
     })?;
 
-    Ok(Response::new().add_message(msg).set_data(answer))
+    Ok(Response::new()
+        .add_submessages(harvest.messages)
+        .add_submessages(delegate_messages)
+        .set_data(answer)
+        .add_events(harvest.events))
 }
 
 pub fn try_redelegate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     validator_address: String,
-    recipient: Option<String>,
+    _recipient: Option<String>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = CONFIG_KEY.load(deps.storage)?;
     if info.sender.clone() != config.admin {
-        return Err(ContractError::Std(StdError::generic_err("Unauthorized")));
+        return Err(ContractError::Unauthorized {});
     }
 
     let first_validator = &config.validators[0];
@@ -1134,64 +2396,59 @@ pub fn try_redelegate(
     let delegation = utils::query_delegation(&deps, &env, &old_validator);
 
     if old_validator == validator_address {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Redelegation to the same validator",
-        )));
+        return Err(ContractError::SameValidator {});
     }
-
-    if delegation.is_err() {
-        config.validators[0].address = validator_address;
-        CONFIG_KEY.save(deps.storage, &config)?;
-
-        let answer = to_json_binary(&ExecuteResponse::Redelegate {
-            amount: Uint128::zero(),
-            status: ResponseStatus::Success,
-        })?;
-
-        return Ok(Response::new().set_data(answer));
+    if config
+        .validators
+        .iter()
+        .any(|validator| validator.address == validator_address)
+    {
+        return Err(ContractError::DuplicateValidator {
+            address: validator_address,
+        });
     }
 
-    let delegation = delegation.unwrap().unwrap();
-    let can_withdraw = delegation.accumulated_rewards[0].amount.u128();
-    let can_redelegate = delegation.can_redelegate.amount.u128();
-    let delegated_amount = delegation.amount.amount.u128();
-
-    if can_redelegate != delegated_amount {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Cannot redelegate full delegation amount",
-        )));
+    // the old validator's rewards are paid out on redelegation anyway
+    let harvest = rewards::harvest(deps.branch(), &env, &config)?;
+    let delegated = delegations::delegated(deps.storage, &old_validator)?;
+    let can_redelegate = delegation
+        .ok()
+        .flatten()
+        .map(|delegation| delegation.can_redelegate.amount.u128())
+        .unwrap_or_default();
+    if can_redelegate < delegated {
+        return Err(ContractError::PartialRedelegation {});
     }
 
     config.validators[0].address = validator_address.clone();
     CONFIG_KEY.save(deps.storage, &config)?;
 
-    let mut messages = Vec::with_capacity(2);
-    if can_withdraw != 0 {
-        let admin = config.admin;
-        let _recipient = recipient.unwrap_or(admin);
-        let withdraw_msg = DistributionMsg::WithdrawDelegatorReward {
-            validator: old_validator.clone(),
-        };
-
-        let msg = CosmosMsg::Distribution(withdraw_msg);
-
-        messages.push(msg);
+    let mut messages = harvest.messages;
+    if delegated != 0 {
+        messages.push(delegations::redelegate(
+            deps.storage,
+            &old_validator,
+            &validator_address,
+            delegated,
+        )?);
     }
+    messages.extend(delegations::delegate(
+        deps.storage,
+        &config.validators,
+        harvest.restake,
+    )?);
 
-    let coin = coin(can_redelegate, ORAI);
-    let redelegate_msg = StakingMsg::Redelegate {
-        src_validator: old_validator,
-        dst_validator: validator_address,
-        amount: coin,
-    };
-
-    messages.push(CosmosMsg::Staking(redelegate_msg));
+    let event = events::redelegated(&old_validator, &validator_address, delegated);
     let answer = to_json_binary(&ExecuteResponse::Redelegate {
-        amount: Uint128::new(can_redelegate),
+        amount: Uint128::new(delegated),
         status: ResponseStatus::Success,
     })?;
 
-    return Ok(Response::new().add_messages(messages).set_data(answer));
+    Ok(Response::new()
+        .add_submessages(messages)
+        .set_data(answer)
+        .add_events(harvest.events)
+        .add_event(event))
 }
 
 pub fn query_config(deps: Deps) -> StdResult<QueryResponse> {
@@ -1203,19 +2460,112 @@ pub fn query_tier_user_info(deps: Deps, address: String) -> StdResult<QueryRespo
     let config = CONFIG_KEY.load(deps.storage)?;
     let min_tier = config.min_tier();
     let tier_user_info =
-        TIER_USER_INFOS
-            .may_load(deps.storage, address)?
-            .unwrap_or(state::TierUserInfo {
-                tier: min_tier,
-                ..Default::default()
-            });
+        TierUserInfo::may_load(deps.storage, &address)?.unwrap_or(state::TierUserInfo {
+            tier: min_tier,
+            ..Default::default()
+        });
 
     let answer = tier_user_info.to_answer();
     return Ok(answer);
 }
 
+/// Prices the next tier like a deposit would, so a price guard rejecting the live quote fails
+/// the query. Without `nft_token_ids`, or when they can't be looked up, the NFT tier is the
+/// lowest one.
+pub fn query_tier_info(
+    deps: Deps,
+    env: Env,
+    address: String,
+    nft_token_ids: Option<Vec<String>>,
+) -> Result<QueryResponse, ContractError> {
+    let config = CONFIG_KEY.load(deps.storage)?;
+    let min_tier = config.min_tier();
+    let tier_user_info =
+        TierUserInfo::may_load(deps.storage, &address)?.unwrap_or(state::TierUserInfo {
+            tier: min_tier,
+            ..Default::default()
+        });
+    let now = env.block.time.seconds();
+    let stake_tier = stake_tier(deps, &config, &tier_user_info, now)?;
+
+    let nft_tier = nft_token_ids
+        .and_then(|token_ids| {
+            get_tier_from_nft_tokens(deps, &address, &config, &token_ids)
+                .ok()
+                .flatten()
+        })
+        .unwrap_or(min_tier);
+
+    let mut next_tier = None;
+    let mut usd_to_next_tier = 0;
+    let mut orai_to_next_tier = 0;
+    if stake_tier > config.max_tier() {
+        let tier = config.next_tier(stake_tier)?;
+        let band_protocol = BandProtocol::guarded(deps, now)?;
+
+        next_tier = Some(tier);
+        usd_to_next_tier = config
+            .deposit_by_tier(tier)
+            .saturating_sub(tier_user_info.usd_deposit);
+        orai_to_next_tier = band_protocol.orai_amount_ceil(usd_to_next_tier)?;
+    }
+
+    Ok(QueryResponse::TierInfo(TierInfoResponse {
+        tier: stake_tier.min(nft_tier),
+        stake_tier,
+        nft_tier,
+        next_tier,
+        usd_to_next_tier: Uint128::new(usd_to_next_tier),
+        orai_to_next_tier: Uint128::new(orai_to_next_tier),
+    }))
+}
+
+/// Prices the deposit like `Deposit` does, so a price guard rejecting the live quote fails the
+/// query. Under a mark-to-market policy it starts from the tier `TierInfo` reports as
+/// `stake_tier`.
+pub fn query_simulate_deposit(
+    deps: Deps,
+    env: Env,
+    address: String,
+    target_tier: u8,
+) -> Result<QueryResponse, ContractError> {
+    let config = CONFIG_KEY.load(deps.storage)?;
+    let min_tier = config.min_tier();
+    let tier_user_info =
+        TierUserInfo::may_load(deps.storage, &address)?.unwrap_or(state::TierUserInfo {
+            tier: min_tier,
+            ..Default::default()
+        });
+
+    let now = env.block.time.seconds();
+    let band_protocol = BandProtocol::guarded(deps, now)?;
+    let tier_user_info = marked_user_info(&config, &band_protocol, &tier_user_info, now)?;
+
+    if target_tier < config.max_tier()
+        || target_tier >= min_tier
+        || target_tier >= tier_user_info.tier
+    {
+        return Err(ContractError::InvalidTargetTier { tier: target_tier });
+    }
+
+    let usd_needed = config
+        .deposit_by_tier(target_tier)
+        .saturating_sub(tier_user_info.usd_deposit);
+    let orai_needed = band_protocol.orai_amount_ceil(usd_needed)?;
+
+    let outcome = deposit_outcome(&config, &band_protocol, &tier_user_info, orai_needed)?;
+
+    Ok(QueryResponse::SimulateDeposit(SimulateDepositResponse {
+        usd_needed: Uint128::new(usd_needed),
+        orai_needed: Uint128::new(orai_needed),
+        tier: outcome.tier,
+        orai_refund: Uint128::new(outcome.orai_refund),
+    }))
+}
+
 pub fn query_withdrawals(
     deps: Deps,
+    env: Env,
     address: String,
     start: Option<u32>,
     limit: Option<u32>,
@@ -1231,25 +2581,153 @@ pub fn query_withdrawals(
     // let withdrawals = withdrawals.partition_point(pred) .paging(&deps.storage, start, limit)?;
     // let serialized_withdrawals = withdrawals.into_iter().map(|w| w.to_serialized()).collect();
 
+    let config = Config::load(deps.storage)?;
+    let unbonding = unbonding::current(&deps.querier, &config.unbonding)?;
+    let now = env.block.time.seconds();
     let mut serialized_withdrawals: Vec<SerializedWithdrawals> = Vec::new();
     for i in start..start + limit {
         let index: usize = i.try_into().unwrap();
         if index < amount {
-            serialized_withdrawals.push(withdrawals[index].to_serialized())
+            let withdrawal = &withdrawals[index];
+            let claimable = unbonding::claimable(deps.storage, &unbonding, withdrawal, now)?;
+            serialized_withdrawals.push(withdrawal.to_serialized(
+                claimable.amount,
+                claimable.claim_time,
+                !claimable.unbonded,
+            ))
         }
     }
 
-    let answer = QueryResponse::Withdrawals {
+    let answer = QueryResponse::Withdrawals(WithdrawalsResponse {
         amount: amount.try_into().unwrap(),
         withdrawals: serialized_withdrawals,
-    };
+    });
 
     Ok(answer)
 }
 
+pub fn query_staking_stats(deps: Deps) -> StdResult<QueryResponse> {
+    let stats = StakingStats::load(deps.storage)?;
+    let pool = RewardPool::load(deps.storage)?;
+    Ok(QueryResponse::StakingStats(StakingStatsResponse {
+        total_orai_deposit: Uint128::new(stats.total_orai_deposit),
+        total_usd_deposit: Uint128::new(stats.total_usd_deposit),
+        users_per_tier: stats.users_per_tier,
+        total_pending_withdrawals: Uint128::new(stats.total_pending_withdrawals),
+        outstanding_rewards: Uint128::new(pool.outstanding),
+        slashing_index: slashing::index(deps.storage)?,
+    }))
+}
+
+pub fn query_price_cache(deps: Deps) -> StdResult<QueryResponse> {
+    let cache = PriceCache::load(deps.storage)?;
+    Ok(cache.to_answer())
+}
+
+pub fn query_circuit_breaker(deps: Deps) -> StdResult<QueryResponse> {
+    let breaker = CircuitBreaker::load(deps.storage)?;
+    Ok(breaker.to_answer())
+}
+
+pub fn query_tier_lock(deps: Deps, env: Env, address: String) -> StdResult<QueryResponse> {
+    let config = Config::load(deps.storage)?;
+    let tier_user_info =
+        TierUserInfo::may_load(deps.storage, &address)?.unwrap_or(state::TierUserInfo {
+            tier: config.min_tier(),
+            ..Default::default()
+        });
+
+    let unlock_time = tier_user_info.unlock_time(&config.tier_locks);
+    Ok(QueryResponse::TierLock(TierLockResponse {
+        tier: tier_user_info.tier,
+        unlock_time,
+        locked: env.block.time.seconds() < unlock_time,
+    }))
+}
+
+/// Includes the share of staking rewards that the next harvest would distribute.
+pub fn query_pending_rewards(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> Result<QueryResponse, ContractError> {
+    let staked = TierUserInfo::may_load(deps.storage, &address)?
+        .map(|user_info| user_info.orai_deposit)
+        .unwrap_or_default();
+
+    let config = Config::load(deps.storage)?;
+    let accrued: u128 =
+        rewards::accrued_rewards(&deps.querier, &env.contract.address, &config.validators)?
+            .into_iter()
+            .map(|(_, rewards)| rewards)
+            .sum();
+    let stats = StakingStats::load(deps.storage)?;
+    let mut pool = RewardPool::load(deps.storage)?;
+    pool.distribute(
+        accrued - rewards::protocol_fee(&config, accrued),
+        stats.total_orai_deposit,
+    )?;
+    let mut staker_rewards = StakerRewards::load(deps.storage, &address)?;
+    staker_rewards.settle(&pool, staked)?;
+
+    Ok(QueryResponse::PendingRewards(PendingRewardsResponse {
+        amount: Uint128::new(staker_rewards.pending),
+        auto_compound: staker_rewards.auto_compound,
+    }))
+}
+
+/// The delegation ledger next to what the chain reports, for the validator set and any
+/// validator the ledger still holds uORAI with.
+pub fn query_delegations(deps: Deps, env: Env) -> StdResult<QueryResponse> {
+    let config = Config::load(deps.storage)?;
+    let mut validators: Vec<(String, u128)> = config
+        .validators
+        .into_iter()
+        .map(|validator| (validator.address, validator.weight))
+        .collect();
+    for (validator, _) in delegations::ledger(deps.storage)? {
+        if !validators.iter().any(|(address, _)| *address == validator) {
+            validators.push((validator, 0));
+        }
+    }
+
+    let mut response = Vec::with_capacity(validators.len());
+    for (validator, weight) in validators {
+        let live = deps
+            .querier
+            .query_delegation(&env.contract.address, &validator)?
+            .map(|delegation| delegation.amount.amount)
+            .unwrap_or_default();
+        response.push(ValidatorDelegation {
+            delegated: Uint128::new(delegations::delegated(deps.storage, &validator)?),
+            validator,
+            weight,
+            live,
+        });
+    }
+
+    Ok(QueryResponse::Delegations(DelegationsResponse {
+        delegations: response,
+    }))
+}
+
+/// The open epoch unless `id` is set.
+pub fn query_unbonding_epoch(deps: Deps, env: Env, id: Option<u64>) -> StdResult<QueryResponse> {
+    let config = Config::load(deps.storage)?;
+    let unbonding = unbonding::current(&deps.querier, &config.unbonding)?;
+    let epoch = match id {
+        Some(id) => UnbondingEpoch::load(deps.storage, id)?,
+        None => UnbondingEpoch::open(deps.storage)?,
+    };
+
+    Ok(QueryResponse::UnbondingEpoch(
+        epoch.to_answer(&unbonding, env.block.time.seconds()),
+    ))
+}
+
 pub fn query_ido_amount(deps: Deps) -> StdResult<QueryResponse> {
     let amount = Ido::len(deps.storage)?;
-    let ido_amount = QueryResponse::IdoAmount { amount };
+    let ido_amount = QueryResponse::IdoAmount(IdoAmountResponse { amount });
     return Ok(ido_amount);
 }
 
@@ -1260,7 +2738,7 @@ pub fn query_ido_info(deps: Deps, ido_id: u32) -> StdResult<QueryResponse> {
 
 pub fn query_in_whitelist(deps: Deps, address: String, ido_id: u32) -> StdResult<QueryResponse> {
     let in_whitelist = utils::in_whitelist(deps.storage, &address, ido_id)?;
-    let value = QueryResponse::InWhitelist { in_whitelist };
+    let value = QueryResponse::InWhitelist(InWhitelistResponse { in_whitelist });
     return Ok(value);
 }
 
@@ -1287,7 +2765,7 @@ pub fn query_ido_list_owned(
         ido_ids.push(ido_list[i as usize]);
     }
 
-    let response = QueryResponse::IdoListOwnedBy { ido_ids, amount };
+    let response = QueryResponse::IdoListOwnedBy(IdoListOwnedByResponse { ido_ids, amount });
     Ok(response)
 }
 
@@ -1317,7 +2795,7 @@ pub fn query_purchase(
 
     let purchases = raw_purchases.into_iter().map(|p| p.to_answer()).collect();
 
-    let response = QueryResponse::Purchases { purchases, amount };
+    let response = QueryResponse::Purchases(PurchasesResponse { purchases, amount });
 
     return Ok(response);
 }
@@ -1344,7 +2822,8 @@ pub fn query_archived_purchase(
 
     let purchases = raw_purchases.into_iter().map(|p| p.to_answer()).collect();
 
-    let response = QueryResponse::ArchivedPurchases { purchases, amount };
+    let response =
+        QueryResponse::ArchivedPurchases(ArchivedPurchasesResponse { purchases, amount });
 
     return Ok(response);
 }
@@ -1371,229 +2850,556 @@ pub fn query_user_info(
     return Ok(response);
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use std::marker::PhantomData;
-//     use std::time::{SystemTime, UNIX_EPOCH};
-
-//     use crate::tier::manual;
-
-//     use super::*;
-//     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
-//     use cosmwasm_std::{from_binary, OwnedDeps};
-//     use rand::{thread_rng, Rng};
-
-//     fn get_init_msg() -> InstantiateMsg {
-//         InstantiateMsg {
-//             admin: None,
-//             tier_contract: "tier".to_string(),
-//             nft_contract: "nft".to_string(),
-//             lock_periods: vec![250, 200, 150, 100],
-//         }
-//     }
-
-//     fn initialize_with(
-//         msg: InstantiateMsg,
-//     ) -> OwnedDeps<cosmwasm_std::MemoryStorage, MockApi, MockQuerier, PhantomData> {
-//         let mut deps = OwnedDeps {
-//             storage: MockStorage::default(),
-//             api: MockApi::default(),
-//             querier: MockQuerier::default(),
-//             custom_query_type: PhantomData::default(),
-//         };
-//         let info: MessageInfo = mock_info("admin", &coins(2, "orai"));
-
-//         instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
-
-//         deps
-//     }
-
-//     fn initialize_with_default(
-//     ) -> OwnedDeps<cosmwasm_std::MemoryStorage, MockApi, MockQuerier, PhantomData> {
-//         let msg = get_init_msg();
-//         initialize_with(msg).unwrap()
-//     }
-
-//     fn start_ido_msg() -> ExecuteMsg {
-//         let mut rng = thread_rng();
-//         let token_contract = format!("token_{}", rng.gen_range(0..1000));
-
-//         let start_time = SystemTime::now()
-//             .duration_since(UNIX_EPOCH)
-//             .unwrap()
-//             .as_secs();
-
-//         let end_time = start_time + rng.gen::<u64>();
-
-//         let price = rng.gen();
-//         let total_amount = rng.gen();
-
-//         let mut whitelist = Vec::new();
-//         for i in 0..rng.gen_range(20..100) {
-//             let address = format!("address_{}", i);
-//             whitelist.push(address);
-//         }
-
-//         let mut tokens_per_tier = Vec::new();
-//         let mut remaining_tokens = total_amount;
-//         for _ in 0..3 {
-//             let tokens_amount = rng.gen_range(0..=remaining_tokens);
-//             tokens_per_tier.push(Uint128::new(tokens_amount));
-//             remaining_tokens -= tokens_amount;
-//         }
-//         tokens_per_tier.push(Uint128::new(remaining_tokens));
-
-//         ExecuteMsg::StartIdo {
-//             start_time,
-//             end_time,
-//             token_contract: token_contract,
-//             payment: PaymentMethod::Token {
-//                 contract: "token".to_string(),
-//                 code_hash: String::from("token_hash"),
-//             },
-//             price: Uint128::new(price),
-//             total_amount: Uint128::new(total_amount),
-//             soft_cap: Uint128::new(total_amount),
-//             whitelist: Whitelist::Empty {
-//                 with: Some(whitelist),
-//             },
-//             tokens_per_tier,
-//             padding: None,
-//         }
-//     }
-
-//     fn extract_error(response: Result<Response, ContractError>) -> String {
-//         match response {
-//             Ok(_) => panic!("Response is not an error"),
-//             Err(err) => match err {
-//                 ContractError::Std(StdError::GenericErr { msg, .. }) => msg,
-//                 ContractError::Unauthorized { .. } => "Unauthorized".into(),
-//                 _ => panic!("Unexpected error"),
-//             },
-//         }
-//     }
-
-//     #[test]
-//     fn initialize() {
-//         let msg = get_init_msg();
-//         let mut deps = initialize_with(msg.clone()).unwrap();
-
-//         let config: Config = Config::load(&deps.storage).unwrap();
-
-//         let min_tier = manual::get_min_tier(&deps.as_mut(), &config).unwrap();
-
-//         let admin = "admin".to_string();
-
-//         assert_eq!(config.admin, admin);
-//         assert_eq!(config.lock_periods, msg.lock_periods);
-//         assert_eq!(config.tier_contract, msg.tier_contract.to_string());
-//         assert_eq!(config.nft_contract, msg.nft_contract.to_string());
-//         assert_eq!(config.min_tier, min_tier);
-//     }
-
-//     #[test]
-//     fn initialize_with_wrong_lock_periods() {
-//         let mut msg = get_init_msg();
-//         msg.lock_periods = vec![1, 2, 3];
-
-//         let mut deps = OwnedDeps {
-//             storage: MockStorage::default(),
-//             api: MockApi::default(),
-//             querier: MockQuerier::default(),
-//             custom_query_type: PhantomData::default(),
-//         };
-//         let info: MessageInfo = mock_info("admin", &coins(2, "orai"));
-
-//         let response = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
-//         let error = extract_error(response);
-
-//         assert!(error.contains("Lock periods array must have 4 items"));
-//     }
-
-//     #[test]
-//     fn start_ido() {
-//         let mut deps = initialize_with_default();
-
-//         let ido_admin = "admin".to_string();
-//         let canonical_ido_admin = ido_admin.to_string();
-//         let info: MessageInfo = mock_info(&ido_admin, &[]);
-//         let env = mock_env();
-//         let msg = start_ido_msg();
-
-//         let startup_ido_list = OWNER_TO_IDOS
-//             .may_load(&deps.storage, canonical_ido_admin.clone())
-//             .unwrap_or_default()
-//             .unwrap_or_default();
-//         assert_eq!(startup_ido_list.len(), 0);
-//         assert_eq!(Ido::len(&deps.storage), Ok(0));
-
-//         let response = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-//         let messages = response.messages;
-//         let data = response.data;
-
-//         match from_binary(&data.unwrap()).unwrap() {
-//             ExecuteResponse::StartIdo { ido_id, status, .. } => {
-//                 assert_eq!(ido_id, 0);
-//                 assert_eq!(status, ResponseStatus::Success);
-//             }
-//             _ => unreachable!(),
-//         }
-
-//         assert_eq!(Ido::len(&deps.storage), Ok(1));
-//         let ido = Ido::load(&deps.storage, 0).unwrap();
-
-//         let startup_ido_list = OWNER_TO_IDOS
-//             .may_load(&deps.storage, canonical_ido_admin)
-//             .unwrap_or_default()
-//             .unwrap_or_default();
-
-//         assert_eq!(startup_ido_list.len(), 1);
-
-//         if let ExecuteMsg::StartIdo {
-//             start_time,
-//             end_time,
-//             token_contract,
-//             price,
-//             total_amount,
-//             whitelist: _whitelist,
-//             payment,
-//             ..
-//         } = msg
-//         {
-//             let sender = info.sender.to_string();
-//             let token_contract_canonical = token_contract.to_string();
-
-//             let payment_token_contract_canonical = match payment {
-//                 PaymentMethod::Native => None,
-//                 PaymentMethod::Token { contract, .. } => Some(contract),
-//             };
-
-//             assert_eq!(ido.admin, sender);
-//             assert_eq!(ido.start_time, start_time);
-//             assert_eq!(ido.end_time, end_time);
-//             assert_eq!(ido.token_contract, token_contract_canonical);
-//             assert_eq!(ido.price, price.u128());
-//             assert_eq!(ido.participants, 0);
-//             assert_eq!(ido.sold_amount, 0);
-//             assert_eq!(ido.total_tokens_amount, total_amount.u128());
-//             assert_eq!(ido.payment_token_contract, payment_token_contract_canonical);
-//             assert_eq!(ido.payment_token_hash, Some(String::from("token_hash")));
-
-//             let transfer_msg = Cw20ExecuteMsg::TransferFrom {
-//                 owner: info.sender.to_string(),
-//                 recipient: env.contract.address.to_string(),
-//                 amount: total_amount,
-//             };
-
-//             let sub_msg = SubMsg::new(WasmMsg::Execute {
-//                 contract_addr: token_contract,
-//                 msg: to_json_binary(&transfer_msg).unwrap(),
-//                 funds: vec![],
-//             });
-//             assert_eq!(messages.len(), 1);
-//             assert_eq!(messages[0], sub_msg);
-//         } else {
-//             unreachable!();
-//         }
-//     }
-// }
+pub fn query_ido_participants(
+    deps: Deps,
+    ido_id: u32,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<QueryResponse> {
+    let ido = Ido::load(deps.storage, ido_id)?;
+    let limit = limit.unwrap_or(50).min(100) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let mut participants = Vec::new();
+    for item in IDO_PARTICIPANTS
+        .prefix(ido_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+    {
+        let (address, tier) = item?;
+        let user_ido_info = IDO_TO_INFO
+            .may_load(deps.storage, (address.clone(), ido_id))?
+            .unwrap_or_default();
+
+        participants.push(ParticipantAnswer {
+            address,
+            total_payment: Uint128::new(user_ido_info.total_payment),
+            total_tokens_bought: Uint128::new(user_ido_info.total_tokens_bought),
+            total_tokens_received: Uint128::new(user_ido_info.total_tokens_received),
+            tier,
+        });
+    }
+
+    let response = QueryResponse::IdoParticipants(IdoParticipantsResponse {
+        participants,
+        amount: ido.participants,
+    });
+
+    Ok(response)
+}
+
+pub fn query_user_portfolio(deps: Deps, env: Env, address: String) -> StdResult<QueryResponse> {
+    let current_time = env.block.time.seconds();
+
+    let ido_ids = ACTIVE_IDOS
+        .prefix(address.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<u32>>>()?;
+
+    let mut positions = Vec::with_capacity(ido_ids.len());
+    for ido_id in ido_ids {
+        let ido = Ido::load(deps.storage, ido_id)?;
+        let user_ido_info = IDO_TO_INFO
+            .may_load(deps.storage, (address.clone(), ido_id))?
+            .unwrap_or_default();
+        let purchases = PURCHASES
+            .may_load(deps.storage, (address.clone(), ido_id))?
+            .unwrap_or_default();
+
+        let refund_eligible = ido.is_failed(current_time) && user_ido_info.total_payment != 0;
+
+        let mut claimable_tokens: u128 = 0;
+        let mut next_unlock_time: Option<u64> = None;
+        if !ido.is_failed(current_time) {
+            for purchase in purchases.iter() {
+                if current_time >= purchase.unlock_time {
+                    claimable_tokens = claimable_tokens.saturating_add(purchase.tokens_amount);
+                } else {
+                    next_unlock_time = Some(
+                        next_unlock_time
+                            .map_or(purchase.unlock_time, |t| t.min(purchase.unlock_time)),
+                    );
+                }
+            }
+        }
+
+        positions.push(PortfolioPosition {
+            ido_id,
+            total_payment: Uint128::new(user_ido_info.total_payment),
+            total_tokens_bought: Uint128::new(user_ido_info.total_tokens_bought),
+            total_tokens_received: Uint128::new(user_ido_info.total_tokens_received),
+            claimable_tokens: Uint128::new(claimable_tokens),
+            next_unlock_time,
+            refund_eligible,
+        });
+    }
+
+    Ok(QueryResponse::UserPortfolio(UserPortfolioResponse {
+        positions,
+    }))
+}
+
+pub fn query_simulate_buy(
+    deps: Deps,
+    env: Env,
+    ido_id: u32,
+    address: String,
+    amount: u128,
+    viewing_key: Option<String>,
+) -> Result<QueryResponse, ContractError> {
+    let ido = Ido::load(deps.storage, ido_id)?;
+    if ido.price == 0 {
+        return Err(ContractError::ZeroPrice {});
+    }
+
+    // native payments are converted to whole payment units before buying
+    let amount = if ido.is_native_payment() {
+        amount
+            .checked_div(ido.price)
+            .and_then(|payment| payment.checked_mul(ido.price))
+            .unwrap_or_default()
+    } else {
+        amount
+    };
+
+    let quote = quote_buy(deps, &env, &ido, &address, amount, viewing_key)?;
+    let accepted = quote.rejection.is_none();
+
+    Ok(QueryResponse::SimulateBuy(SimulateBuyResponse {
+        whitelisted: quote.whitelisted,
+        tier: quote.tier,
+        amount: Uint128::new(if accepted { amount } else { 0 }),
+        payment: Uint128::new(if accepted { quote.payment } else { 0 }),
+        unlock_time: if accepted {
+            Some(quote.unlock_time)
+        } else {
+            None
+        },
+        rejection: quote.rejection,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coin, from_json, FullDelegation, OwnedDeps};
+
+    use super::*;
+    use crate::msg::OraiswapContract;
+
+    fn get_init_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            admin: None,
+            lock_periods: vec![250, 200, 150, 100, 50],
+            nft_contract: "nft".to_string(),
+            validators: vec![ValidatorWithWeight {
+                address: "validator".to_string(),
+                weight: 10_000,
+            }],
+            deposits: vec![
+                Uint128::new(400),
+                Uint128::new(300),
+                Uint128::new(200),
+                Uint128::new(100),
+            ],
+            oraiswap_contract: OraiswapContract {
+                orai_contract: "router".to_string(),
+                usdt_contract: "usdt".to_string(),
+                route: vec![],
+                offer_amount: None,
+            },
+            price_source: None,
+            price_guard: None,
+            tier_policy: None,
+            tier_locks: None,
+            protocol_fee: None,
+            unbonding: None,
+        }
+    }
+
+    fn initialize_with_default() -> OwnedDeps<
+        cosmwasm_std::MemoryStorage,
+        cosmwasm_std::testing::MockApi,
+        cosmwasm_std::testing::MockQuerier,
+    > {
+        let mut deps = mock_dependencies();
+        let info = mock_info("admin", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, get_init_msg()).unwrap();
+        deps
+    }
+
+    fn start_ido_msg() -> ExecuteMsg {
+        let start_time = mock_env().block.time.seconds();
+
+        ExecuteMsg::StartIdo {
+            start_time,
+            end_time: start_time + 1000,
+            token_contract: "token".to_string(),
+            price: Uint128::new(10),
+            soft_cap: Uint128::new(500),
+            payment: PaymentMethod::Native,
+            total_amount: Uint128::new(1000),
+            tokens_per_tier: vec![Uint128::new(250); 5],
+            padding: None,
+            whitelist: Whitelist::Empty {
+                with: Some(vec!["user".to_string()]),
+            },
+        }
+    }
+
+    fn set_start_ido(
+        msg: &mut ExecuteMsg,
+        patch: impl FnOnce(&mut Vec<Uint128>, &mut u64, &mut Uint128, &mut Uint128),
+    ) {
+        if let ExecuteMsg::StartIdo {
+            tokens_per_tier,
+            end_time,
+            price,
+            soft_cap,
+            ..
+        } = msg
+        {
+            patch(tokens_per_tier, end_time, price, soft_cap);
+        }
+    }
+
+    #[test]
+    fn initialize() {
+        let deps = initialize_with_default();
+        let config = Config::load(&deps.storage).unwrap();
+
+        assert_eq!(config.admin, "admin");
+        assert_eq!(config.lock_periods, get_init_msg().lock_periods);
+        assert_eq!(config.nft_contract, "nft");
+        assert_eq!(config.min_tier, 5);
+
+        let mut msg = get_init_msg();
+        msg.lock_periods = vec![1, 2, 3];
+        let mut deps = mock_dependencies();
+        let response = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+        assert_eq!(
+            response.unwrap_err(),
+            ContractError::InvalidLockPeriods { expected: 5 }
+        );
+
+        let mut msg = get_init_msg();
+        msg.deposits = vec![];
+        let response = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+        assert_eq!(response.unwrap_err(), ContractError::EmptyDeposits {});
+
+        let mut msg = get_init_msg();
+        msg.validators[0].weight = 100;
+        let response = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+        assert_eq!(
+            response.unwrap_err(),
+            ContractError::InvalidValidatorWeights {}
+        );
+
+        let mut msg = get_init_msg();
+        msg.validators[0].weight = 5_000;
+        msg.validators.push(msg.validators[0].clone());
+        let response = instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+        assert_eq!(
+            response.unwrap_err(),
+            ContractError::DuplicateValidator {
+                address: "validator".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn start_ido() {
+        let mut deps = initialize_with_default();
+        let env = mock_env();
+        let info = mock_info("admin", &[]);
+
+        let response = execute(deps.as_mut(), env.clone(), info.clone(), start_ido_msg()).unwrap();
+        match from_json(response.data.unwrap()).unwrap() {
+            ExecuteResponse::StartIdo { ido_id, status } => {
+                assert_eq!(ido_id, 0);
+                assert_eq!(status, ResponseStatus::Success);
+            }
+            _ => unreachable!(),
+        }
+
+        let transfer_msg = Cw20ExecuteMsg::TransferFrom {
+            owner: "admin".to_string(),
+            recipient: env.contract.address.to_string(),
+            amount: Uint128::new(1000),
+        };
+        let sub_msg = SubMsg::new(WasmMsg::Execute {
+            contract_addr: "token".to_string(),
+            msg: to_json_binary(&transfer_msg).unwrap(),
+            funds: vec![],
+        });
+        assert_eq!(response.messages, vec![sub_msg]);
+
+        let ido = Ido::load(&deps.storage, 0).unwrap();
+        assert_eq!(ido.admin, "admin");
+        assert_eq!(ido.total_tokens_amount, 1000);
+        assert_eq!(ido.payment_token_contract, None);
+        assert_eq!(Ido::len(&deps.storage), Ok(1));
+        assert_eq!(
+            OWNER_TO_IDOS.load(&deps.storage, "admin".to_string()),
+            Ok(vec![0])
+        );
+        assert!(utils::in_whitelist(&deps.storage, &"user".to_string(), 0).unwrap());
+        assert!(!utils::in_whitelist(&deps.storage, &"other".to_string(), 0).unwrap());
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user", &[]),
+            start_ido_msg(),
+        );
+        assert_eq!(response.unwrap_err(), ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn start_ido_validation() {
+        let mut deps = initialize_with_default();
+        let info = mock_info("admin", &[]);
+
+        type Case = (fn(&mut ExecuteMsg), ContractError);
+        let cases: Vec<Case> = vec![
+            (
+                |msg| set_start_ido(msg, |tokens_per_tier, _, _, _| tokens_per_tier.truncate(4)),
+                ContractError::InvalidTokensPerTier { expected: 5 },
+            ),
+            (
+                |msg| {
+                    set_start_ido(msg, |tokens_per_tier, _, _, _| {
+                        *tokens_per_tier = vec![Uint128::new(100); 5]
+                    })
+                },
+                ContractError::TokensPerTierBelowTotal {},
+            ),
+            (
+                |msg| set_start_ido(msg, |_, end_time, _, _| *end_time = 0),
+                ContractError::InvalidIdoTime {},
+            ),
+            (
+                |msg| set_start_ido(msg, |_, _, price, _| *price = Uint128::zero()),
+                ContractError::ZeroPrice {},
+            ),
+            (
+                |msg| set_start_ido(msg, |_, _, _, soft_cap| *soft_cap = Uint128::zero()),
+                ContractError::ZeroSoftCap {},
+            ),
+            (
+                |msg| set_start_ido(msg, |_, _, _, soft_cap| *soft_cap = Uint128::new(1001)),
+                ContractError::SoftCapAboveTotal {},
+            ),
+        ];
+
+        for (patch, error) in cases {
+            let mut msg = start_ido_msg();
+            patch(&mut msg);
+            let response = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+            assert_eq!(response.unwrap_err(), error);
+        }
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1000);
+        let response = execute(deps.as_mut(), env, info, start_ido_msg());
+        assert_eq!(response.unwrap_err(), ContractError::IdoEndsInPast {});
+        assert_eq!(Ido::len(&deps.storage), Ok(0));
+    }
+
+    #[test]
+    fn deposit_funds_validation() {
+        let mut deps = initialize_with_default();
+        let deposit = || ExecuteMsg::Deposit { padding: None };
+
+        let cases = vec![
+            (vec![], ContractError::NoFunds {}),
+            (
+                coins(100, "uusd"),
+                ContractError::UnsupportedToken {
+                    denom: "uusd".to_string(),
+                },
+            ),
+            (
+                vec![coin(100, ORAI), coin(100, ORAI)],
+                ContractError::MultipleFunds {},
+            ),
+        ];
+        for (funds, error) in cases {
+            let info = mock_info("user", &funds);
+            let response = execute(deps.as_mut(), mock_env(), info, deposit());
+            assert_eq!(response.unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn next_tier_bounds() {
+        let deps = initialize_with_default();
+        let config = Config::load(&deps.storage).unwrap();
+
+        assert_eq!(config.next_tier(5), Ok(4));
+        assert_eq!(config.next_tier(2), Ok(1));
+        for tier in [0, 1, 6] {
+            assert_eq!(
+                config.next_tier(tier),
+                Err(ContractError::InvalidTier { tier })
+            );
+        }
+    }
+
+    #[test]
+    fn redelegate_requires_full_redelegation() {
+        let mut deps = initialize_with_default();
+        let env = mock_env();
+        let config = Config::load(&deps.storage).unwrap();
+        delegations::delegate(&mut deps.storage, &config.validators, 1000).unwrap();
+
+        deps.querier.update_staking(
+            ORAI,
+            &[],
+            &[FullDelegation {
+                delegator: env.contract.address.clone(),
+                validator: "validator".to_string(),
+                amount: coin(1000, ORAI),
+                can_redelegate: coin(400, ORAI),
+                accumulated_rewards: vec![],
+            }],
+        );
+        let msg = ExecuteMsg::Redelegate {
+            validator_address: "other".to_string(),
+            recipient: None,
+            padding: None,
+        };
+        let response = execute(deps.as_mut(), env, mock_info("admin", &[]), msg);
+        assert_eq!(response.unwrap_err(), ContractError::PartialRedelegation {});
+    }
+
+    #[test]
+    fn change_admin_and_status() {
+        let mut deps = initialize_with_default();
+
+        let msg = ExecuteMsg::ChangeStatus {
+            status: ContractStatus::Stopped,
+            padding: None,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            msg.clone(),
+        );
+        assert_eq!(response.unwrap_err(), ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            start_ido_msg(),
+        );
+        assert_eq!(response.unwrap_err(), ContractError::ContractNotActive {});
+
+        let msg = ExecuteMsg::ChangeAdmin {
+            admin: "new_admin".to_string(),
+            padding: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        assert_eq!(Config::load(&deps.storage).unwrap().admin, "new_admin");
+    }
+
+    #[test]
+    fn migrate_rebuilds_staking_stats() {
+        let mut deps = initialize_with_default();
+        for (address, tier, usd_deposit, orai_deposit) in [
+            ("a", 1, 400, 4_000),
+            ("b", 3, 200, 2_000),
+            ("c", 3, 250, 2_600),
+        ] {
+            let user_info = TierUserInfo {
+                tier,
+                usd_deposit,
+                orai_deposit,
+                ..Default::default()
+            };
+            TIER_USER_INFOS
+                .save(&mut deps.storage, address.to_string(), &user_info)
+                .unwrap();
+        }
+
+        // one withdrawal from before unbonding epochs and one queued in the open epoch
+        let epoch = unbonding::queue(&mut deps.storage, 300).unwrap();
+        let withdrawals = vec![
+            UserWithdrawal {
+                amount: 500,
+                claim_time: 100,
+                timestamp: 0,
+                epoch: None,
+                slashing_index: None,
+            },
+            UserWithdrawal {
+                amount: 300,
+                claim_time: 100,
+                timestamp: 0,
+                epoch: Some(epoch.id),
+                slashing_index: Some(Decimal::one()),
+            },
+        ];
+        WITHDRAWALS_LIST
+            .save(&mut deps.storage, "a".to_string(), &withdrawals)
+            .unwrap();
+
+        let expected = StakingStats {
+            total_orai_deposit: 8_600,
+            total_usd_deposit: 850,
+            users_per_tier: vec![1, 0, 2],
+            total_pending_withdrawals: 800,
+        };
+        for _ in 0..2 {
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+            assert_eq!(StakingStats::load(&deps.storage).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn migrate_backfills_ido_participants() {
+        let mut deps = initialize_with_default();
+        let user_info = TierUserInfo {
+            tier: 2,
+            ..Default::default()
+        };
+        TIER_USER_INFOS
+            .save(&mut deps.storage, "a".to_string(), &user_info)
+            .unwrap();
+        for (address, ido_id, total_payment) in [("a", 0, 100), ("b", 0, 50), ("c", 1, 0)] {
+            let user_ido_info = crate::state::UserInfo {
+                total_payment,
+                ..Default::default()
+            };
+            IDO_TO_INFO
+                .save(
+                    &mut deps.storage,
+                    (address.to_string(), ido_id),
+                    &user_ido_info,
+                )
+                .unwrap();
+        }
+        // bought after the index was added
+        IDO_PARTICIPANTS
+            .save(&mut deps.storage, (1, "d".to_string()), &1)
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let participants: Vec<((u32, String), u8)> = IDO_PARTICIPANTS
+            .range(&deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        let min_tier = Config::load(&deps.storage).unwrap().min_tier();
+        assert_eq!(
+            participants,
+            vec![
+                ((0, "a".to_string()), 2),
+                ((0, "b".to_string()), min_tier),
+                ((1, "d".to_string()), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Ledger of the uORAI the contract delegated to each validator.
+//!
+//! Every delegation change goes through this module, so the ledger matches the chain unless
+//! a validator gets slashed. Splits by weight round down and give the remainder to the
+//! heaviest validator, the first one on ties. Undelegations follow the ledger instead of the
+//! weights, so they never ask a validator for more than it holds. Slashes are written off by
+//! `ReconcileDelegations`.
+use cosmwasm_std::{coin, CosmosMsg, Order, StakingMsg, StdResult, Storage, SubMsg, Uint128};
+use cw_storage_plus::Map;
+
+use crate::contract::ORAI;
+use crate::error::ContractError;
+use crate::msg::ValidatorWithWeight;
+
+pub const DELEGATIONS: Map<String, u128> = Map::new("delegations");
+
+/// uORAI the ledger holds with `validator`.
+pub fn delegated(storage: &dyn Storage, validator: &str) -> StdResult<u128> {
+    Ok(DELEGATIONS
+        .may_load(storage, validator.to_string())?
+        .unwrap_or_default())
+}
+
+/// Every validator the ledger holds uORAI with, in address order.
+pub fn ledger(storage: &dyn Storage) -> StdResult<Vec<(String, u128)>> {
+    DELEGATIONS
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+fn add(storage: &mut dyn Storage, validator: &str, amount: u128) -> Result<(), ContractError> {
+    let delegated = delegated(storage, validator)?
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow {})?;
+    DELEGATIONS.save(storage, validator.to_string(), &delegated)?;
+    Ok(())
+}
+
+fn sub(storage: &mut dyn Storage, validator: &str, amount: u128) -> Result<(), ContractError> {
+    let delegated = delegated(storage, validator)?
+        .checked_sub(amount)
+        .ok_or(ContractError::Overflow {})?;
+    if delegated == 0 {
+        DELEGATIONS.remove(storage, validator.to_string());
+    } else {
+        DELEGATIONS.save(storage, validator.to_string(), &delegated)?;
+    }
+    Ok(())
+}
+
+/// Writes the ledger of `validator` down to the `live` uORAI the chain reports after a slash,
+/// and returns by how much.
+pub fn write_down(
+    storage: &mut dyn Storage,
+    validator: &str,
+    live: u128,
+) -> Result<u128, ContractError> {
+    let delegated = delegated(storage, validator)?;
+    if live >= delegated {
+        return Ok(0);
+    }
+    sub(storage, validator, delegated - live)?;
+
+    Ok(delegated - live)
+}
+
+/// Splits `amount` between the validators by weight. The parts always add up to `amount`.
+pub fn split_by_weight(validators: &[ValidatorWithWeight], amount: u128) -> Vec<(String, u128)> {
+    let total_weight: u128 = validators.iter().map(|validator| validator.weight).sum();
+    let mut parts: Vec<(String, u128)> = validators
+        .iter()
+        .map(|validator| {
+            let part = if total_weight == 0 {
+                0
+            } else {
+                Uint128::new(amount)
+                    .multiply_ratio(validator.weight, total_weight)
+                    .u128()
+            };
+            (validator.address.clone(), part)
+        })
+        .collect();
+
+    let remainder = amount - parts.iter().map(|(_, part)| part).sum::<u128>();
+    let heaviest = validators
+        .iter()
+        .enumerate()
+        .max_by(|(a_index, a), (b_index, b)| a.weight.cmp(&b.weight).then(b_index.cmp(a_index)))
+        .map(|(index, _)| index);
+    if let Some(index) = heaviest {
+        parts[index].1 += remainder;
+    }
+
+    parts
+}
+
+/// Splits `amount` between the ledger entries pro rata to what they hold. The remainder goes
+/// to the first entries with room left, so no part exceeds its delegation.
+fn split_by_ledger(
+    storage: &dyn Storage,
+    amount: u128,
+) -> Result<Vec<(String, u128)>, ContractError> {
+    let ledger = ledger(storage)?;
+    let total: u128 = ledger.iter().map(|(_, delegated)| delegated).sum();
+    if amount > total {
+        return Err(ContractError::Overflow {});
+    }
+    if amount == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut parts: Vec<(String, u128, u128)> = ledger
+        .into_iter()
+        .map(|(validator, delegated)| {
+            let part = Uint128::new(amount).multiply_ratio(delegated, total).u128();
+            (validator, part, delegated)
+        })
+        .collect();
+    let mut remainder = amount - parts.iter().map(|(_, part, _)| part).sum::<u128>();
+    for (_, part, delegated) in parts.iter_mut() {
+        let extra = remainder.min(*delegated - *part);
+        *part += extra;
+        remainder -= extra;
+    }
+
+    Ok(parts
+        .into_iter()
+        .map(|(validator, part, _)| (validator, part))
+        .collect())
+}
+
+/// Delegates `amount` uORAI to the validators according to their weights.
+pub fn delegate(
+    storage: &mut dyn Storage,
+    validators: &[ValidatorWithWeight],
+    amount: u128,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let mut messages = vec![];
+    for (validator, part) in split_by_weight(validators, amount) {
+        if part == 0 {
+            continue;
+        }
+        add(storage, &validator, part)?;
+        let msg = StakingMsg::Delegate {
+            validator,
+            amount: coin(part, ORAI),
+        };
+        messages.push(SubMsg::new(CosmosMsg::Staking(msg)));
+    }
+
+    Ok(messages)
+}
+
+/// Undelegates `amount` uORAI pro rata to the ledger.
+pub fn undelegate(storage: &mut dyn Storage, amount: u128) -> Result<Vec<SubMsg>, ContractError> {
+    let mut messages = vec![];
+    for (validator, part) in split_by_ledger(storage, amount)? {
+        if part == 0 {
+            continue;
+        }
+        sub(storage, &validator, part)?;
+        let msg = StakingMsg::Undelegate {
+            validator,
+            amount: coin(part, ORAI),
+        };
+        messages.push(SubMsg::new(CosmosMsg::Staking(msg)));
+    }
+
+    Ok(messages)
+}
+
+/// Moves `amount` uORAI from `src_validator` to `dst_validator`.
+pub fn redelegate(
+    storage: &mut dyn Storage,
+    src_validator: &str,
+    dst_validator: &str,
+    amount: u128,
+) -> Result<SubMsg, ContractError> {
+    sub(storage, src_validator, amount)?;
+    add(storage, dst_validator, amount)?;
+    let msg = StakingMsg::Redelegate {
+        src_validator: src_validator.to_string(),
+        dst_validator: dst_validator.to_string(),
+        amount: coin(amount, ORAI),
+    };
+
+    Ok(SubMsg::new(CosmosMsg::Staking(msg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    fn validators(weights: &[u128]) -> Vec<ValidatorWithWeight> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(index, weight)| ValidatorWithWeight {
+                address: format!("validator{}", index + 1),
+                weight: *weight,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn remainders() {
+        let parts = split_by_weight(&validators(&[3_000, 4_000, 3_000]), 101);
+        let parts: Vec<u128> = parts.into_iter().map(|(_, part)| part).collect();
+        assert_eq!(parts, vec![30, 41, 30]);
+
+        // a new validator without weight never gets the remainder
+        let parts = split_by_weight(&validators(&[5_000, 5_000, 0]), 101);
+        let parts: Vec<u128> = parts.into_iter().map(|(_, part)| part).collect();
+        assert_eq!(parts, vec![51, 50, 0]);
+    }
+
+    #[test]
+    fn ledger_follows_messages() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+
+        let messages = delegate(storage, &validators(&[6_000, 4_000, 0]), 1_001).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            ledger(storage).unwrap(),
+            vec![
+                ("validator1".to_string(), 601),
+                ("validator2".to_string(), 400)
+            ]
+        );
+
+        redelegate(storage, "validator2", "validator3", 400).unwrap();
+        assert_eq!(delegated(storage, "validator2").unwrap(), 0);
+        assert_eq!(delegated(storage, "validator3").unwrap(), 400);
+
+        // 3 uORAI split 601:400, the remainder goes to the first validator
+        undelegate(storage, 3).unwrap();
+        assert_eq!(delegated(storage, "validator1").unwrap(), 599);
+        assert_eq!(delegated(storage, "validator3").unwrap(), 399);
+
+        undelegate(storage, 998).unwrap();
+        assert_eq!(ledger(storage).unwrap(), vec![]);
+        assert_eq!(
+            undelegate(storage, 1).unwrap_err(),
+            ContractError::Overflow {}
+        );
+    }
+}
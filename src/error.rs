@@ -1,13 +1,196 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Uint128};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+use crate::msg::BuyRejection;
+
+#[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
     #[error("Unauthorized")]
     Unauthorized {},
-    // Add any other custom errors you like here.
-    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+
+    #[error("Contract is not active")]
+    ContractNotActive {},
+
+    #[error("Arithmetic overflow")]
+    Overflow {},
+
+    // Instantiate
+    #[error("Deposits array is empty")]
+    EmptyDeposits {},
+
+    #[error("Lock periods array must have {expected} items")]
+    InvalidLockPeriods { expected: u8 },
+
+    // Funds
+    #[error("No funds")]
+    NoFunds {},
+
+    #[error("Zero amount is not allowed")]
+    ZeroAmount {},
+
+    #[error("Unsupported token: {denom}")]
+    UnsupportedToken { denom: String },
+
+    #[error("Multiple funds are not allowed")]
+    MultipleFunds {},
+
+    // Ido
+    #[error("`tokens_per_tier` must have {expected} items")]
+    InvalidTokensPerTier { expected: u8 },
+
+    #[error("Sum of `tokens_per_tier` can't be less than total tokens amount")]
+    TokensPerTierBelowTotal {},
+
+    #[error("End time must be greater than start time")]
+    InvalidIdoTime {},
+
+    #[error("Ido ends in the past")]
+    IdoEndsInPast {},
+
+    #[error("Ido price should be initialized")]
+    ZeroPrice {},
+
+    #[error("Soft cap should be initialized")]
+    ZeroSoftCap {},
+
+    #[error("Soft cap should be less than total amount")]
+    SoftCapAboveTotal {},
+
+    #[error("IDO is not active")]
+    IdoNotActive {},
+
+    #[error("IDO is not finished yet")]
+    IdoNotFinished {},
+
+    #[error("All tokens are sold")]
+    AllTokensSold {},
+
+    #[error("Tier {tier} allocation has only {remaining} tokens left")]
+    TierSoldOut { tier: u8, remaining: Uint128 },
+
+    #[error("Purchase index {index} is out of range")]
+    InvalidPurchaseIndex { index: u32 },
+
+    #[error("Nothing to receive")]
+    NothingToReceive {},
+
+    #[error("Already withdrawn")]
+    AlreadyWithdrawn {},
+
+    // Tier
+    #[error("Price source returned an invalid price")]
+    InvalidPrice {},
+
+    #[error("Cached price is missing or older than {max_age} seconds")]
+    StalePrice { max_age: u64 },
+
+    #[error("Price {price} deviates too far from the reference price {reference}")]
+    PriceDeviation { price: Decimal, reference: Decimal },
+
+    #[error("Price guard needs a non-zero TWAP window")]
+    InvalidPriceGuard {},
+
+    #[error("Price route must start from ORAI, chain its hops and offer a non-zero amount")]
+    InvalidPriceRoute {},
+
+    #[error("No price source returned a price")]
+    PriceUnavailable {},
+
+    #[error("Price sources disagree: {price} against {other}")]
+    PriceSourcesDisagree { price: Decimal, other: Decimal },
+
+    #[error("Tier deposits are paused by the circuit breaker")]
+    DepositsPaused {},
+
+    #[error("Reached max tier")]
+    MaxTierReached {},
+
+    #[error("Tier {tier} is out of range")]
+    InvalidTier { tier: u8 },
+
+    #[error("You should deposit at least {usd_needed} USD ({orai_needed} ORAI)")]
+    BelowNextTier {
+        usd_needed: Uint128,
+        orai_needed: Uint128,
+    },
+
+    #[error("Tiers are locked at deposit time")]
+    TierPolicyLocked {},
+
+    #[error("Address has no tier deposit")]
+    NoTierDeposit {},
+
+    #[error("Tier deposit is locked until {unlock_time}")]
+    TierLocked { unlock_time: u64 },
+
+    #[error("`min_lock_durations` must be empty or have {expected} items")]
+    InvalidTierLocks { expected: u8 },
+
+    #[error("Early exit penalty can't exceed 10000 bps")]
+    InvalidPenalty {},
+
+    #[error("Protocol fee can't exceed 10000 bps")]
+    InvalidProtocolFee {},
+
+    #[error("Tier {tier} is not a valid target tier")]
+    InvalidTargetTier { tier: u8 },
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("Only {available} uORAI are deposited")]
+    InsufficientDeposit { available: Uint128 },
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("There are no rewards to withdraw")]
+    NoRewards {},
+
+    #[error("Redelegation to the same validator")]
+    SameValidator {},
+
+    #[error("Cannot redelegate full delegation amount")]
+    PartialRedelegation {},
+
+    #[error("Validator weights must be unique and add up to 10000 bps")]
+    InvalidValidatorWeights {},
+
+    #[error("Validator {address} is already in the set")]
+    DuplicateValidator { address: String },
+
+    #[error("Validator {address} is unknown")]
+    UnknownValidator { address: String },
+
+    #[error("Validator {address} still has weight or delegations")]
+    ValidatorInUse { address: String },
+
+    #[error("Unbonding epoch can be processed from {processable_at}")]
+    EpochNotOver { processable_at: u64 },
+
+    #[error("Unbonding epoch duration must be positive")]
+    InvalidUnbonding {},
+
+    #[error("No validator was slashed since the last reconciliation")]
+    NoSlashing {},
+}
+
+impl From<BuyRejection> for ContractError {
+    fn from(rejection: BuyRejection) -> Self {
+        match rejection {
+            BuyRejection::IdoNotActive => ContractError::IdoNotActive {},
+            BuyRejection::ZeroAmount => ContractError::ZeroAmount {},
+            BuyRejection::AllTokensSold => ContractError::AllTokensSold {},
+            BuyRejection::TierSoldOut { tier } => ContractError::TierSoldOut {
+                tier,
+                remaining: Uint128::zero(),
+            },
+            BuyRejection::ExceedsTierRemaining { tier, remaining } => {
+                ContractError::TierSoldOut { tier, remaining }
+            }
+        }
+    }
 }
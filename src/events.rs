@@ -0,0 +1,649 @@
+//! Custom events emitted by the execute and migrate handlers.
+//!
+//! `wasmd` prefixes custom event types with `wasm-`, so indexers receive them as
+//! `wasm-ido_started`, `wasm-tokens_bought` and so on. Every amount is an integer
+//! in base units and every time is a unix timestamp in seconds.
+//!
+//! | event                | attributes                                                           |
+//! |----------------------|----------------------------------------------------------------------|
+//! | `ido_started`        | `ido_id`, `admin`, `token_contract`, `price`, `total_amount`, `start_time`, `end_time` |
+//! | `tokens_bought`      | `ido_id`, `buyer`, `tier`, `amount`, `payment`, `unlock_time`        |
+//! | `tokens_claimed`     | `ido_id`, `recipient`, `amount`                                      |
+//! | `ido_refunded`       | `ido_id`, `recipient`, `payment`                                     |
+//! | `ido_withdrawn`      | `ido_id`, `admin`, `ido_amount`, `payment_amount`                    |
+//! | `whitelist_updated`  | `ido_id`, `whitelisted`, `addresses`                                 |
+//! | `status_changed`     | `status`                                                             |
+//! | `admin_changed`      | `previous_admin`, `new_admin`                                        |
+//! | `tier_deposit`       | `user`, `amount`, `refund`, `orai_deposit`, `usd_deposit`, `tier`    |
+//! | `tier_withdraw`      | `user`, `amount`, `claim_time`, `tier`                               |
+//! | `withdrawal_claimed` | `user`, `recipient`, `amount`                                        |
+//! | `rewards_withdrawn`  | `validator`, `amount`                                                |
+//! | `redelegated`        | `src_validator`, `dst_validator`, `amount`                           |
+//! | `price_source_updated` | `source`                                                           |
+//! | `price_guard_updated`  | `enabled`, then `max_age`, `max_deviation_bps`, `twap_window` when enabled |
+//! | `price_refreshed`      | `price`, `twap`                                                    |
+//! | `price_route_updated`  | `hops`, `offer_amount`                                             |
+//! | `price_fallbacks_updated` | `sources` (comma separated), `max_divergence_bps` (empty when unset) |
+//! | `circuit_breaker_tripped` | `reason`                                                        |
+//! | `circuit_breaker_reset`   | no attributes                                                   |
+//! | `tier_policy_updated`     | `policy`, then `grace_period` for `mark_to_market`              |
+//! | `tier_synced`             | `user`, `previous_tier`, `tier`, `usd_deposit`                  |
+//! | `tier_locks_updated`      | `min_lock_durations` (comma separated), `early_exit`, then `bps`, `recipient` for `penalty` |
+//! | `early_exit_penalty`      | `user`, `amount`, `recipient` (`treasury` or `stakers`)         |
+//! | `staking_rewards_claimed` | `user`, `amount`, `claim_time`                                  |
+//! | `rewards_harvested`       | `amount`, `protocol_fee`, `restaked`                            |
+//! | `protocol_fee_updated`    | `enabled`, then `bps`, `recipient` when enabled                 |
+//! | `auto_compound_set`       | `user`, `enabled`                                               |
+//! | `rewards_compounded`      | `user`, `amount`, `orai_deposit`, `tier`                        |
+//! | `validator_added`         | `validator`                                                     |
+//! | `validator_removed`       | `validator`                                                     |
+//! | `validator_weights_set`   | `weights` (comma separated `validator:bps`)                     |
+//! | `unbonding_epoch_processed` | `epoch`, `amount`, `claim_time`                               |
+//! | `unbonding_updated`       | `epoch_duration`, `unbonding_time`, `from_chain`                |
+//! | `validator_slashed`       | `validator`, `amount`                                           |
+//! | `delegations_reconciled`  | `slashed`, `ratio`, `slashing_index`                            |
+//! | `staking_stats_rebuilt`   | `total_orai_deposit`, `total_usd_deposit`, `users`, `total_pending_withdrawals` |
+use cosmwasm_std::{Decimal, Event};
+
+use crate::msg::{
+    ContractStatus, EarlyExitPolicy, PenaltyRecipient, PriceGuard, PriceSourceConfig, ProtocolFee,
+    TierLocks, TierPolicy, Unbonding, ValidatorWithWeight,
+};
+use crate::state::StakingStats;
+
+pub fn ido_started(
+    ido_id: u32,
+    admin: &str,
+    token_contract: &str,
+    price: u128,
+    total_amount: u128,
+    start_time: u64,
+    end_time: u64,
+) -> Event {
+    Event::new("ido_started")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("admin", admin)
+        .add_attribute("token_contract", token_contract)
+        .add_attribute("price", price.to_string())
+        .add_attribute("total_amount", total_amount.to_string())
+        .add_attribute("start_time", start_time.to_string())
+        .add_attribute("end_time", end_time.to_string())
+}
+
+pub fn tokens_bought(
+    ido_id: u32,
+    buyer: &str,
+    tier: u8,
+    amount: u128,
+    payment: u128,
+    unlock_time: u64,
+) -> Event {
+    Event::new("tokens_bought")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("buyer", buyer)
+        .add_attribute("tier", tier.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("payment", payment.to_string())
+        .add_attribute("unlock_time", unlock_time.to_string())
+}
+
+pub fn tokens_claimed(ido_id: u32, recipient: &str, amount: u128) -> Event {
+    Event::new("tokens_claimed")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount.to_string())
+}
+
+pub fn ido_refunded(ido_id: u32, recipient: &str, payment: u128) -> Event {
+    Event::new("ido_refunded")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("recipient", recipient)
+        .add_attribute("payment", payment.to_string())
+}
+
+pub fn ido_withdrawn(ido_id: u32, admin: &str, ido_amount: u128, payment_amount: u128) -> Event {
+    Event::new("ido_withdrawn")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("admin", admin)
+        .add_attribute("ido_amount", ido_amount.to_string())
+        .add_attribute("payment_amount", payment_amount.to_string())
+}
+
+pub fn whitelist_updated(ido_id: u32, whitelisted: bool, addresses: &[String]) -> Event {
+    Event::new("whitelist_updated")
+        .add_attribute("ido_id", ido_id.to_string())
+        .add_attribute("whitelisted", whitelisted.to_string())
+        .add_attribute("addresses", addresses.join(","))
+}
+
+pub fn status_changed(status: &ContractStatus) -> Event {
+    let status = match status {
+        ContractStatus::Active => "active",
+        ContractStatus::Stopped => "stopped",
+    };
+
+    Event::new("status_changed").add_attribute("status", status)
+}
+
+pub fn admin_changed(previous_admin: &str, new_admin: &str) -> Event {
+    Event::new("admin_changed")
+        .add_attribute("previous_admin", previous_admin)
+        .add_attribute("new_admin", new_admin)
+}
+
+pub fn tier_deposit(
+    user: &str,
+    amount: u128,
+    refund: u128,
+    orai_deposit: u128,
+    usd_deposit: u128,
+    tier: u8,
+) -> Event {
+    Event::new("tier_deposit")
+        .add_attribute("user", user)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("refund", refund.to_string())
+        .add_attribute("orai_deposit", orai_deposit.to_string())
+        .add_attribute("usd_deposit", usd_deposit.to_string())
+        .add_attribute("tier", tier.to_string())
+}
+
+pub fn tier_withdraw(user: &str, amount: u128, claim_time: u64, tier: u8) -> Event {
+    Event::new("tier_withdraw")
+        .add_attribute("user", user)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("claim_time", claim_time.to_string())
+        .add_attribute("tier", tier.to_string())
+}
+
+pub fn withdrawal_claimed(user: &str, recipient: &str, amount: u128) -> Event {
+    Event::new("withdrawal_claimed")
+        .add_attribute("user", user)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount.to_string())
+}
+
+pub fn rewards_withdrawn(validator: &str, amount: u128) -> Event {
+    Event::new("rewards_withdrawn")
+        .add_attribute("validator", validator)
+        .add_attribute("amount", amount.to_string())
+}
+
+pub fn redelegated(src_validator: &str, dst_validator: &str, amount: u128) -> Event {
+    Event::new("redelegated")
+        .add_attribute("src_validator", src_validator)
+        .add_attribute("dst_validator", dst_validator)
+        .add_attribute("amount", amount.to_string())
+}
+
+fn source_name(price_source: &PriceSourceConfig) -> &'static str {
+    match price_source {
+        PriceSourceConfig::OraiswapRouter {} => "oraiswap_router",
+        PriceSourceConfig::BandStdReference { .. } => "band_std_reference",
+        PriceSourceConfig::PushOracle { .. } => "push_oracle",
+        PriceSourceConfig::FixedPrice { .. } => "fixed_price",
+    }
+}
+
+pub fn price_source_updated(price_source: &PriceSourceConfig) -> Event {
+    Event::new("price_source_updated").add_attribute("source", source_name(price_source))
+}
+
+pub fn price_guard_updated(price_guard: Option<&PriceGuard>) -> Event {
+    let event = Event::new("price_guard_updated");
+    match price_guard {
+        None => event.add_attribute("enabled", "false"),
+        Some(guard) => event
+            .add_attribute("enabled", "true")
+            .add_attribute("max_age", guard.max_age.to_string())
+            .add_attribute("max_deviation_bps", guard.max_deviation_bps.to_string())
+            .add_attribute("twap_window", guard.twap_window.to_string()),
+    }
+}
+
+pub fn price_refreshed(price: Decimal, twap: Decimal) -> Event {
+    Event::new("price_refreshed")
+        .add_attribute("price", price.to_string())
+        .add_attribute("twap", twap.to_string())
+}
+
+pub fn price_route_updated(hops: usize, offer_amount: u128) -> Event {
+    Event::new("price_route_updated")
+        .add_attribute("hops", hops.to_string())
+        .add_attribute("offer_amount", offer_amount.to_string())
+}
+
+pub fn price_fallbacks_updated(
+    fallbacks: &[PriceSourceConfig],
+    max_divergence_bps: Option<u16>,
+) -> Event {
+    let sources = fallbacks.iter().map(source_name).collect::<Vec<_>>();
+    let max_divergence_bps = max_divergence_bps
+        .map(|bps| bps.to_string())
+        .unwrap_or_default();
+
+    Event::new("price_fallbacks_updated")
+        .add_attribute("sources", sources.join(","))
+        .add_attribute("max_divergence_bps", max_divergence_bps)
+}
+
+pub fn circuit_breaker_tripped(reason: &str) -> Event {
+    Event::new("circuit_breaker_tripped").add_attribute("reason", reason)
+}
+
+pub fn circuit_breaker_reset() -> Event {
+    Event::new("circuit_breaker_reset")
+}
+
+pub fn tier_policy_updated(tier_policy: &TierPolicy) -> Event {
+    let event = Event::new("tier_policy_updated");
+    match tier_policy {
+        TierPolicy::LockedAtDeposit {} => event.add_attribute("policy", "locked_at_deposit"),
+        TierPolicy::MarkToMarket { grace_period } => event
+            .add_attribute("policy", "mark_to_market")
+            .add_attribute("grace_period", grace_period.to_string()),
+    }
+}
+
+pub fn tier_synced(user: &str, previous_tier: u8, tier: u8, usd_deposit: u128) -> Event {
+    Event::new("tier_synced")
+        .add_attribute("user", user)
+        .add_attribute("previous_tier", previous_tier.to_string())
+        .add_attribute("tier", tier.to_string())
+        .add_attribute("usd_deposit", usd_deposit.to_string())
+}
+
+pub fn tier_locks_updated(tier_locks: &TierLocks) -> Event {
+    let min_lock_durations = tier_locks
+        .min_lock_durations
+        .iter()
+        .map(|duration| duration.to_string())
+        .collect::<Vec<_>>();
+    let event = Event::new("tier_locks_updated")
+        .add_attribute("min_lock_durations", min_lock_durations.join(","));
+
+    match &tier_locks.early_exit {
+        EarlyExitPolicy::Reject {} => event.add_attribute("early_exit", "reject"),
+        EarlyExitPolicy::Penalty { bps, recipient } => {
+            let recipient = match recipient {
+                PenaltyRecipient::Treasury { address } => address.as_str(),
+                PenaltyRecipient::Stakers {} => "stakers",
+            };
+            event
+                .add_attribute("early_exit", "penalty")
+                .add_attribute("bps", bps.to_string())
+                .add_attribute("recipient", recipient)
+        }
+    }
+}
+
+pub fn early_exit_penalty(user: &str, amount: u128, recipient: &str) -> Event {
+    Event::new("early_exit_penalty")
+        .add_attribute("user", user)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("recipient", recipient)
+}
+
+pub fn staking_rewards_claimed(user: &str, amount: u128, claim_time: u64) -> Event {
+    Event::new("staking_rewards_claimed")
+        .add_attribute("user", user)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("claim_time", claim_time.to_string())
+}
+
+pub fn rewards_harvested(amount: u128, protocol_fee: u128, restaked: u128) -> Event {
+    Event::new("rewards_harvested")
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("protocol_fee", protocol_fee.to_string())
+        .add_attribute("restaked", restaked.to_string())
+}
+
+pub fn auto_compound_set(user: &str, enabled: bool) -> Event {
+    Event::new("auto_compound_set")
+        .add_attribute("user", user)
+        .add_attribute("enabled", enabled.to_string())
+}
+
+pub fn rewards_compounded(user: &str, amount: u128, orai_deposit: u128, tier: u8) -> Event {
+    Event::new("rewards_compounded")
+        .add_attribute("user", user)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("orai_deposit", orai_deposit.to_string())
+        .add_attribute("tier", tier.to_string())
+}
+
+pub fn validator_added(validator: &str) -> Event {
+    Event::new("validator_added").add_attribute("validator", validator)
+}
+
+pub fn validator_removed(validator: &str) -> Event {
+    Event::new("validator_removed").add_attribute("validator", validator)
+}
+
+pub fn validator_weights_set(validators: &[ValidatorWithWeight]) -> Event {
+    let weights = validators
+        .iter()
+        .map(|validator| format!("{}:{}", validator.address, validator.weight))
+        .collect::<Vec<_>>()
+        .join(",");
+    Event::new("validator_weights_set").add_attribute("weights", weights)
+}
+
+pub fn protocol_fee_updated(protocol_fee: Option<&ProtocolFee>) -> Event {
+    let event = Event::new("protocol_fee_updated");
+    match protocol_fee {
+        None => event.add_attribute("enabled", "false"),
+        Some(fee) => event
+            .add_attribute("enabled", "true")
+            .add_attribute("bps", fee.bps.to_string())
+            .add_attribute("recipient", &fee.recipient),
+    }
+}
+
+pub fn unbonding_epoch_processed(epoch: u64, amount: u128, claim_time: u64) -> Event {
+    Event::new("unbonding_epoch_processed")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("claim_time", claim_time.to_string())
+}
+
+pub fn unbonding_updated(unbonding: &Unbonding) -> Event {
+    Event::new("unbonding_updated")
+        .add_attribute("epoch_duration", unbonding.epoch_duration.to_string())
+        .add_attribute("unbonding_time", unbonding.unbonding_time.to_string())
+        .add_attribute("from_chain", unbonding.from_chain.to_string())
+}
+
+pub fn validator_slashed(validator: &str, amount: u128) -> Event {
+    Event::new("validator_slashed")
+        .add_attribute("validator", validator)
+        .add_attribute("amount", amount.to_string())
+}
+
+pub fn delegations_reconciled(slashed: u128, ratio: Decimal, slashing_index: Decimal) -> Event {
+    Event::new("delegations_reconciled")
+        .add_attribute("slashed", slashed.to_string())
+        .add_attribute("ratio", ratio.to_string())
+        .add_attribute("slashing_index", slashing_index.to_string())
+}
+
+pub fn staking_stats_rebuilt(stats: &StakingStats) -> Event {
+    let users: u64 = stats.users_per_tier.iter().sum();
+    Event::new("staking_stats_rebuilt")
+        .add_attribute("total_orai_deposit", stats.total_orai_deposit.to_string())
+        .add_attribute("total_usd_deposit", stats.total_usd_deposit.to_string())
+        .add_attribute("users", users.to_string())
+        .add_attribute(
+            "total_pending_withdrawals",
+            stats.total_pending_withdrawals.to_string(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(event: &Event) -> Vec<&str> {
+        event.attributes.iter().map(|a| a.key.as_str()).collect()
+    }
+
+    #[test]
+    fn event_schema() {
+        let event = ido_started(1, "admin", "token", 10, 1000, 100, 200);
+        assert_eq!(event.ty, "ido_started");
+        assert_eq!(
+            keys(&event),
+            vec![
+                "ido_id",
+                "admin",
+                "token_contract",
+                "price",
+                "total_amount",
+                "start_time",
+                "end_time"
+            ]
+        );
+
+        let event = tokens_bought(1, "buyer", 2, 500, 50, 300);
+        assert_eq!(event.ty, "tokens_bought");
+        assert_eq!(
+            keys(&event),
+            vec![
+                "ido_id",
+                "buyer",
+                "tier",
+                "amount",
+                "payment",
+                "unlock_time"
+            ]
+        );
+
+        let event = tokens_claimed(1, "user", 500);
+        assert_eq!(event.ty, "tokens_claimed");
+        assert_eq!(keys(&event), vec!["ido_id", "recipient", "amount"]);
+
+        let event = ido_refunded(1, "user", 50);
+        assert_eq!(event.ty, "ido_refunded");
+        assert_eq!(keys(&event), vec!["ido_id", "recipient", "payment"]);
+
+        let event = ido_withdrawn(1, "admin", 500, 50);
+        assert_eq!(event.ty, "ido_withdrawn");
+        assert_eq!(
+            keys(&event),
+            vec!["ido_id", "admin", "ido_amount", "payment_amount"]
+        );
+
+        let event = whitelist_updated(1, true, &["a".to_string(), "b".to_string()]);
+        assert_eq!(event.ty, "whitelist_updated");
+        assert_eq!(keys(&event), vec!["ido_id", "whitelisted", "addresses"]);
+        assert_eq!(event.attributes[2].value, "a,b");
+
+        let event = status_changed(&ContractStatus::Stopped);
+        assert_eq!(event.ty, "status_changed");
+        assert_eq!(event.attributes[0].value, "stopped");
+
+        let event = admin_changed("old", "new");
+        assert_eq!(event.ty, "admin_changed");
+        assert_eq!(keys(&event), vec!["previous_admin", "new_admin"]);
+
+        let event = tier_deposit("user", 100, 5, 95, 10, 3);
+        assert_eq!(event.ty, "tier_deposit");
+        assert_eq!(
+            keys(&event),
+            vec![
+                "user",
+                "amount",
+                "refund",
+                "orai_deposit",
+                "usd_deposit",
+                "tier"
+            ]
+        );
+
+        let event = tier_withdraw("user", 95, 1000, 3);
+        assert_eq!(event.ty, "tier_withdraw");
+        assert_eq!(keys(&event), vec!["user", "amount", "claim_time", "tier"]);
+
+        let event = withdrawal_claimed("user", "recipient", 95);
+        assert_eq!(event.ty, "withdrawal_claimed");
+        assert_eq!(keys(&event), vec!["user", "recipient", "amount"]);
+
+        let event = rewards_withdrawn("validator", 7);
+        assert_eq!(event.ty, "rewards_withdrawn");
+        assert_eq!(keys(&event), vec!["validator", "amount"]);
+
+        let event = redelegated("src", "dst", 95);
+        assert_eq!(event.ty, "redelegated");
+        assert_eq!(
+            keys(&event),
+            vec!["src_validator", "dst_validator", "amount"]
+        );
+
+        let event = price_source_updated(&PriceSourceConfig::FixedPrice {
+            price: Decimal::percent(150),
+        });
+        assert_eq!(event.ty, "price_source_updated");
+        assert_eq!(event.attributes[0].value, "fixed_price");
+
+        let event = price_guard_updated(None);
+        assert_eq!(keys(&event), vec!["enabled"]);
+        let event = price_guard_updated(Some(&PriceGuard {
+            max_age: 600,
+            max_deviation_bps: 500,
+            twap_window: 3600,
+        }));
+        assert_eq!(event.ty, "price_guard_updated");
+        assert_eq!(
+            keys(&event),
+            vec!["enabled", "max_age", "max_deviation_bps", "twap_window"]
+        );
+
+        let event = price_refreshed(Decimal::percent(150), Decimal::percent(140));
+        assert_eq!(event.ty, "price_refreshed");
+        assert_eq!(keys(&event), vec!["price", "twap"]);
+        assert_eq!(event.attributes[0].value, "1.5");
+
+        let event = price_route_updated(2, 1_000_000);
+        assert_eq!(event.ty, "price_route_updated");
+        assert_eq!(keys(&event), vec!["hops", "offer_amount"]);
+
+        let event = price_fallbacks_updated(
+            &[
+                PriceSourceConfig::OraiswapRouter {},
+                PriceSourceConfig::FixedPrice {
+                    price: Decimal::one(),
+                },
+            ],
+            Some(300),
+        );
+        assert_eq!(event.ty, "price_fallbacks_updated");
+        assert_eq!(keys(&event), vec!["sources", "max_divergence_bps"]);
+        assert_eq!(event.attributes[0].value, "oraiswap_router,fixed_price");
+
+        let event = circuit_breaker_tripped("No price source returned a price");
+        assert_eq!(event.ty, "circuit_breaker_tripped");
+        assert_eq!(keys(&event), vec!["reason"]);
+
+        let event = circuit_breaker_reset();
+        assert_eq!(event.ty, "circuit_breaker_reset");
+        assert!(event.attributes.is_empty());
+
+        let event = tier_policy_updated(&TierPolicy::LockedAtDeposit {});
+        assert_eq!(event.ty, "tier_policy_updated");
+        assert_eq!(keys(&event), vec!["policy"]);
+        let event = tier_policy_updated(&TierPolicy::MarkToMarket { grace_period: 3600 });
+        assert_eq!(keys(&event), vec!["policy", "grace_period"]);
+        assert_eq!(event.attributes[0].value, "mark_to_market");
+
+        let event = tier_synced("user", 2, 4, 1000);
+        assert_eq!(event.ty, "tier_synced");
+        assert_eq!(
+            keys(&event),
+            vec!["user", "previous_tier", "tier", "usd_deposit"]
+        );
+
+        let event = tier_locks_updated(&TierLocks::default());
+        assert_eq!(event.ty, "tier_locks_updated");
+        assert_eq!(keys(&event), vec!["min_lock_durations", "early_exit"]);
+        let event = tier_locks_updated(&TierLocks {
+            min_lock_durations: vec![400, 300],
+            early_exit: EarlyExitPolicy::Penalty {
+                bps: 500,
+                recipient: PenaltyRecipient::Stakers {},
+            },
+        });
+        assert_eq!(
+            keys(&event),
+            vec!["min_lock_durations", "early_exit", "bps", "recipient"]
+        );
+        assert_eq!(event.attributes[0].value, "400,300");
+        assert_eq!(event.attributes[3].value, "stakers");
+
+        let event = early_exit_penalty("user", 50, "treasury");
+        assert_eq!(event.ty, "early_exit_penalty");
+        assert_eq!(keys(&event), vec!["user", "amount", "recipient"]);
+
+        let event = staking_rewards_claimed("user", 50, 1000);
+        assert_eq!(event.ty, "staking_rewards_claimed");
+        assert_eq!(keys(&event), vec!["user", "amount", "claim_time"]);
+
+        let event = rewards_harvested(100, 10, 90);
+        assert_eq!(event.ty, "rewards_harvested");
+        assert_eq!(keys(&event), vec!["amount", "protocol_fee", "restaked"]);
+
+        let event = protocol_fee_updated(None);
+        assert_eq!(event.ty, "protocol_fee_updated");
+        assert_eq!(keys(&event), vec!["enabled"]);
+
+        let event = protocol_fee_updated(Some(&ProtocolFee {
+            bps: 1_000,
+            recipient: "treasury".to_string(),
+        }));
+        assert_eq!(keys(&event), vec!["enabled", "bps", "recipient"]);
+
+        let event = auto_compound_set("user", true);
+        assert_eq!(event.ty, "auto_compound_set");
+        assert_eq!(keys(&event), vec!["user", "enabled"]);
+
+        let event = rewards_compounded("user", 50, 1050, 3);
+        assert_eq!(event.ty, "rewards_compounded");
+        assert_eq!(keys(&event), vec!["user", "amount", "orai_deposit", "tier"]);
+
+        let event = validator_added("validator");
+        assert_eq!(event.ty, "validator_added");
+        assert_eq!(keys(&event), vec!["validator"]);
+
+        let event = validator_removed("validator");
+        assert_eq!(event.ty, "validator_removed");
+        assert_eq!(keys(&event), vec!["validator"]);
+
+        let event = validator_weights_set(&[
+            ValidatorWithWeight {
+                address: "a".to_string(),
+                weight: 6_000,
+            },
+            ValidatorWithWeight {
+                address: "b".to_string(),
+                weight: 4_000,
+            },
+        ]);
+        assert_eq!(event.ty, "validator_weights_set");
+        assert_eq!(event.attributes[0].value, "a:6000,b:4000");
+
+        let event = unbonding_epoch_processed(3, 500, 1_000);
+        assert_eq!(event.ty, "unbonding_epoch_processed");
+        assert_eq!(keys(&event), vec!["epoch", "amount", "claim_time"]);
+
+        let event = unbonding_updated(&Unbonding::default());
+        assert_eq!(event.ty, "unbonding_updated");
+        assert_eq!(
+            keys(&event),
+            vec!["epoch_duration", "unbonding_time", "from_chain"]
+        );
+
+        let event = validator_slashed("validator", 50);
+        assert_eq!(event.ty, "validator_slashed");
+        assert_eq!(keys(&event), vec!["validator", "amount"]);
+
+        let event = delegations_reconciled(50, Decimal::percent(95), Decimal::percent(95));
+        assert_eq!(event.ty, "delegations_reconciled");
+        assert_eq!(keys(&event), vec!["slashed", "ratio", "slashing_index"]);
+
+        let stats = StakingStats {
+            users_per_tier: vec![1, 0, 2],
+            ..Default::default()
+        };
+        let event = staking_stats_rebuilt(&stats);
+        assert_eq!(event.ty, "staking_stats_rebuilt");
+        assert_eq!(
+            keys(&event),
+            vec![
+                "total_orai_deposit",
+                "total_usd_deposit",
+                "users",
+                "total_pending_withdrawals"
+            ]
+        );
+        assert_eq!(event.attributes[2].value, "3");
+    }
+}
@@ -0,0 +1,729 @@
+//! Typed client for contracts and off-chain Rust code talking to the launchpad.
+//!
+//! Execute helpers build ready-to-dispatch `CosmosMsg`s and query helpers unwrap
+//! the matching `QueryResponse` variant, so callers never touch raw JSON.
+use cosmwasm_std::{
+    to_json_binary, Addr, Coin, CosmosMsg, QuerierWrapper, StdError, StdResult, Uint128, WasmMsg,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::band::Operation;
+use crate::msg::{
+    ArchivedPurchasesResponse, CircuitBreakerResponse, ConfigResponse, ContractStatus,
+    DelegationsResponse, ExecuteMsg, IdoAmountResponse, IdoInfoResponse, IdoListOwnedByResponse,
+    IdoParticipantsResponse, InWhitelistResponse, PaymentMethod, PendingRewardsResponse,
+    PriceCacheResponse, PriceGuard, PriceSourceConfig, ProtocolFee, PurchasesResponse, QueryMsg,
+    QueryResponse, SimulateBuyResponse, SimulateDepositResponse, StakingStatsResponse,
+    TierInfoResponse, TierLockResponse, TierLocks, TierPolicy, TierUserInfoResponse, Unbonding,
+    UnbondingEpochResponse, UserInfoResponse, UserPortfolioResponse, ValidatorWithWeight,
+    Whitelist, WithdrawalsResponse,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LaunchpadContract(pub Addr);
+
+impl LaunchpadContract {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
+        self.call_with_funds(msg, vec![])
+    }
+
+    pub fn call_with_funds<T: Into<ExecuteMsg>>(
+        &self,
+        msg: T,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = to_json_binary(&msg.into())?;
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg,
+            funds,
+        }
+        .into())
+    }
+
+    // Ido
+
+    pub fn change_admin(&self, admin: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ChangeAdmin {
+            admin: admin.into(),
+            padding: None,
+        })
+    }
+
+    pub fn change_status(&self, status: ContractStatus) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ChangeStatus {
+            status,
+            padding: None,
+        })
+    }
+
+    /// The sender must have approved `total_amount` of `token_contract` for the
+    /// launchpad beforehand.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_ido(
+        &self,
+        start_time: u64,
+        end_time: u64,
+        token_contract: impl Into<String>,
+        price: Uint128,
+        soft_cap: Uint128,
+        payment: PaymentMethod,
+        total_amount: Uint128,
+        tokens_per_tier: Vec<Uint128>,
+        whitelist: Whitelist,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::StartIdo {
+            start_time,
+            end_time,
+            token_contract: token_contract.into(),
+            price,
+            soft_cap,
+            payment,
+            total_amount,
+            tokens_per_tier,
+            padding: None,
+            whitelist,
+        })
+    }
+
+    pub fn whitelist_add(&self, ido_id: u32, addresses: Vec<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::WhitelistAdd {
+            addresses,
+            ido_id,
+            padding: None,
+        })
+    }
+
+    pub fn whitelist_remove(&self, ido_id: u32, addresses: Vec<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::WhitelistRemove {
+            addresses,
+            ido_id,
+            padding: None,
+        })
+    }
+
+    /// `funds` must carry the payment for IDOs paid in the native token and be
+    /// empty for IDOs paid in a cw20 token.
+    pub fn buy_tokens(
+        &self,
+        ido_id: u32,
+        amount: Uint128,
+        viewing_key: Option<String>,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        self.call_with_funds(
+            ExecuteMsg::BuyTokens {
+                ido_id,
+                amount,
+                viewing_key,
+                padding: None,
+            },
+            funds,
+        )
+    }
+
+    pub fn recv_tokens(
+        &self,
+        ido_id: u32,
+        start: Option<u32>,
+        limit: Option<u32>,
+        purchase_indices: Option<Vec<u32>>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::RecvTokens {
+            ido_id,
+            start,
+            limit,
+            purchase_indices,
+            padding: None,
+        })
+    }
+
+    pub fn withdraw(&self, ido_id: u32) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Withdraw {
+            ido_id,
+            padding: None,
+        })
+    }
+
+    // Tier
+
+    pub fn deposit(&self, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        self.call_with_funds(ExecuteMsg::Deposit { padding: None }, funds)
+    }
+
+    pub fn withdraw_from_tier(&self, amount: Option<Uint128>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::WithdrawFromTier {
+            amount,
+            padding: None,
+        })
+    }
+
+    pub fn claim(
+        &self,
+        recipient: Option<String>,
+        start: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Claim {
+            recipient,
+            start,
+            limit,
+            padding: None,
+        })
+    }
+
+    pub fn withdraw_rewards(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::WithdrawRewards { padding: None })
+    }
+
+    pub fn redelegate(
+        &self,
+        validator_address: impl Into<String>,
+        recipient: Option<String>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Redelegate {
+            validator_address: validator_address.into(),
+            recipient,
+            padding: None,
+        })
+    }
+
+    pub fn update_price_source(&self, price_source: PriceSourceConfig) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdatePriceSource {
+            price_source,
+            padding: None,
+        })
+    }
+
+    pub fn update_price_guard(&self, price_guard: Option<PriceGuard>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdatePriceGuard {
+            price_guard,
+            padding: None,
+        })
+    }
+
+    pub fn refresh_price(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::RefreshPrice { padding: None })
+    }
+
+    pub fn update_price_route(
+        &self,
+        route: Vec<Operation>,
+        offer_amount: Option<Uint128>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdatePriceRoute {
+            route,
+            offer_amount,
+            padding: None,
+        })
+    }
+
+    pub fn update_price_fallbacks(
+        &self,
+        fallbacks: Vec<PriceSourceConfig>,
+        max_divergence_bps: Option<u16>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdatePriceFallbacks {
+            fallbacks,
+            max_divergence_bps,
+            padding: None,
+        })
+    }
+
+    pub fn reset_circuit_breaker(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ResetCircuitBreaker { padding: None })
+    }
+
+    pub fn update_tier_policy(&self, tier_policy: TierPolicy) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateTierPolicy {
+            tier_policy,
+            padding: None,
+        })
+    }
+
+    pub fn sync_tier(&self, address: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::SyncTier {
+            address: address.into(),
+            padding: None,
+        })
+    }
+
+    pub fn update_tier_locks(&self, tier_locks: TierLocks) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateTierLocks {
+            tier_locks,
+            padding: None,
+        })
+    }
+
+    pub fn claim_staking_rewards(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ClaimStakingRewards { padding: None })
+    }
+
+    pub fn set_auto_compound(&self, enabled: bool) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::SetAutoCompound {
+            enabled,
+            padding: None,
+        })
+    }
+
+    pub fn compound(&self, addresses: Vec<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Compound {
+            addresses,
+            padding: None,
+        })
+    }
+
+    pub fn add_validator(&self, address: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::AddValidator {
+            address: address.into(),
+            padding: None,
+        })
+    }
+
+    pub fn remove_validator(&self, address: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::RemoveValidator {
+            address: address.into(),
+            padding: None,
+        })
+    }
+
+    pub fn set_weights(&self, validators: Vec<ValidatorWithWeight>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::SetWeights {
+            validators,
+            padding: None,
+        })
+    }
+
+    pub fn rebalance(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Rebalance { padding: None })
+    }
+
+    pub fn update_protocol_fee(&self, protocol_fee: Option<ProtocolFee>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateProtocolFee {
+            protocol_fee,
+            padding: None,
+        })
+    }
+
+    pub fn process_unbonding_epoch(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ProcessUnbondingEpoch { padding: None })
+    }
+
+    pub fn update_unbonding(&self, unbonding: Unbonding) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateUnbonding {
+            unbonding,
+            padding: None,
+        })
+    }
+
+    pub fn reconcile_delegations(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ReconcileDelegations { padding: None })
+    }
+
+    // Queries
+
+    pub fn query(&self, querier: &QuerierWrapper, msg: &QueryMsg) -> StdResult<QueryResponse> {
+        querier.query_wasm_smart(self.addr(), msg)
+    }
+
+    pub fn config(&self, querier: &QuerierWrapper) -> StdResult<ConfigResponse> {
+        match self.query(querier, &QueryMsg::Config {})? {
+            QueryResponse::Config(response) => Ok(*response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn ido_amount(&self, querier: &QuerierWrapper) -> StdResult<IdoAmountResponse> {
+        match self.query(querier, &QueryMsg::IdoAmount {})? {
+            QueryResponse::IdoAmount(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn ido_info(&self, querier: &QuerierWrapper, ido_id: u32) -> StdResult<IdoInfoResponse> {
+        match self.query(querier, &QueryMsg::IdoInfo { ido_id })? {
+            QueryResponse::IdoInfo(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn in_whitelist(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+        ido_id: u32,
+    ) -> StdResult<InWhitelistResponse> {
+        let msg = QueryMsg::InWhitelist {
+            address: address.into(),
+            ido_id,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::InWhitelist(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn ido_list_owned_by(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+        start: u32,
+        limit: u32,
+    ) -> StdResult<IdoListOwnedByResponse> {
+        let msg = QueryMsg::IdoListOwnedBy {
+            address: address.into(),
+            start,
+            limit,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::IdoListOwnedBy(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn purchases(
+        &self,
+        querier: &QuerierWrapper,
+        ido_id: u32,
+        address: impl Into<String>,
+        start: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<PurchasesResponse> {
+        let msg = QueryMsg::Purchases {
+            ido_id,
+            address: address.into(),
+            start,
+            limit,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::Purchases(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn archived_purchases(
+        &self,
+        querier: &QuerierWrapper,
+        ido_id: u32,
+        address: impl Into<String>,
+        start: u32,
+        limit: u32,
+    ) -> StdResult<ArchivedPurchasesResponse> {
+        let msg = QueryMsg::ArchivedPurchases {
+            ido_id,
+            address: address.into(),
+            start,
+            limit,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::ArchivedPurchases(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn user_info(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+        ido_id: Option<u32>,
+    ) -> StdResult<UserInfoResponse> {
+        let msg = QueryMsg::UserInfo {
+            address: address.into(),
+            ido_id,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::UserInfo(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn ido_participants(
+        &self,
+        querier: &QuerierWrapper,
+        ido_id: u32,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<IdoParticipantsResponse> {
+        let msg = QueryMsg::IdoParticipants {
+            ido_id,
+            start_after,
+            limit,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::IdoParticipants(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn user_portfolio(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+    ) -> StdResult<UserPortfolioResponse> {
+        let msg = QueryMsg::UserPortfolio {
+            address: address.into(),
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::UserPortfolio(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn simulate_buy(
+        &self,
+        querier: &QuerierWrapper,
+        ido_id: u32,
+        address: impl Into<String>,
+        amount: Uint128,
+        viewing_key: Option<String>,
+    ) -> StdResult<SimulateBuyResponse> {
+        let msg = QueryMsg::SimulateBuy {
+            ido_id,
+            address: address.into(),
+            amount,
+            viewing_key,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::SimulateBuy(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn tier_user_info(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+    ) -> StdResult<TierUserInfoResponse> {
+        let msg = QueryMsg::TierUserInfo {
+            address: address.into(),
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::TierUserInfo(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn tier_info(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+        nft_token_ids: Option<Vec<String>>,
+    ) -> StdResult<TierInfoResponse> {
+        let msg = QueryMsg::TierInfo {
+            address: address.into(),
+            nft_token_ids,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::TierInfo(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn simulate_deposit(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+        target_tier: u8,
+    ) -> StdResult<SimulateDepositResponse> {
+        let msg = QueryMsg::SimulateDeposit {
+            address: address.into(),
+            target_tier,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::SimulateDeposit(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn withdrawals(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+        start: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<WithdrawalsResponse> {
+        let msg = QueryMsg::Withdrawals {
+            address: address.into(),
+            start,
+            limit,
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::Withdrawals(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn staking_stats(&self, querier: &QuerierWrapper) -> StdResult<StakingStatsResponse> {
+        match self.query(querier, &QueryMsg::StakingStats {})? {
+            QueryResponse::StakingStats(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn price_cache(&self, querier: &QuerierWrapper) -> StdResult<PriceCacheResponse> {
+        match self.query(querier, &QueryMsg::PriceCache {})? {
+            QueryResponse::PriceCache(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn tier_lock(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+    ) -> StdResult<TierLockResponse> {
+        let msg = QueryMsg::TierLock {
+            address: address.into(),
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::TierLock(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn pending_rewards(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+    ) -> StdResult<PendingRewardsResponse> {
+        let msg = QueryMsg::PendingRewards {
+            address: address.into(),
+        };
+        match self.query(querier, &msg)? {
+            QueryResponse::PendingRewards(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn delegations(&self, querier: &QuerierWrapper) -> StdResult<DelegationsResponse> {
+        match self.query(querier, &QueryMsg::Delegations {})? {
+            QueryResponse::Delegations(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn unbonding_epoch(
+        &self,
+        querier: &QuerierWrapper,
+        id: Option<u64>,
+    ) -> StdResult<UnbondingEpochResponse> {
+        match self.query(querier, &QueryMsg::UnbondingEpoch { id })? {
+            QueryResponse::UnbondingEpoch(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    pub fn circuit_breaker(&self, querier: &QuerierWrapper) -> StdResult<CircuitBreakerResponse> {
+        match self.query(querier, &QueryMsg::CircuitBreaker {})? {
+            QueryResponse::CircuitBreaker(response) => Ok(response),
+            _ => Err(unexpected_response()),
+        }
+    }
+}
+
+fn unexpected_response() -> StdError {
+    StdError::generic_err("Unexpected launchpad query response")
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coins, from_json,
+        testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier},
+        to_json_binary, ContractResult, Deps, SystemResult, WasmQuery,
+    };
+
+    use super::*;
+    use crate::contract::{instantiate, query};
+    use crate::msg::{InstantiateMsg, OraiswapContract};
+
+    #[test]
+    fn execute_messages() {
+        let launchpad = LaunchpadContract(Addr::unchecked("launchpad"));
+        let msg = launchpad.deposit(coins(100, "orai")).unwrap();
+
+        match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                assert_eq!(contract_addr, "launchpad");
+                assert_eq!(funds, coins(100, "orai"));
+                assert_eq!(
+                    msg,
+                    to_json_binary(&ExecuteMsg::Deposit { padding: None }).unwrap()
+                );
+            }
+            _ => panic!("Unexpected message"),
+        }
+    }
+
+    #[test]
+    fn typed_queries() {
+        let mut deps = mock_dependencies();
+        let init_msg = InstantiateMsg {
+            admin: Some("admin".to_string()),
+            lock_periods: vec![10, 20, 30, 40, 50],
+            nft_contract: "nft".to_string(),
+            validators: vec![ValidatorWithWeight {
+                address: "validator".to_string(),
+                weight: 10_000,
+            }],
+            deposits: vec![
+                400u128.into(),
+                300u128.into(),
+                200u128.into(),
+                100u128.into(),
+            ],
+            oraiswap_contract: OraiswapContract {
+                orai_contract: "orai".to_string(),
+                usdt_contract: "usdt".to_string(),
+                route: vec![],
+                offer_amount: None,
+            },
+            price_source: None,
+            price_guard: None,
+            tier_policy: None,
+            tier_locks: None,
+            protocol_fee: None,
+            unbonding: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), init_msg).unwrap();
+
+        // Answer wasm queries from a snapshot of the launchpad storage.
+        let storage = deps.storage;
+        let mut querier = deps.querier;
+        querier.update_wasm(move |request| {
+            let WasmQuery::Smart { msg, .. } = request else {
+                panic!("Unexpected query");
+            };
+            let querier = MockQuerier::default();
+            let deps = Deps {
+                storage: &storage,
+                api: &MockApi::default(),
+                querier: QuerierWrapper::new(&querier),
+            };
+            let msg = from_json(msg).unwrap();
+            SystemResult::Ok(ContractResult::Ok(query(deps, mock_env(), msg).unwrap()))
+        });
+
+        let launchpad = LaunchpadContract(Addr::unchecked("launchpad"));
+        let querier = QuerierWrapper::new(&querier);
+
+        let config = launchpad.config(&querier).unwrap();
+        assert_eq!(config.admin, "admin");
+        assert_eq!(config.lock_periods, vec![10, 20, 30, 40, 50]);
+
+        let ido_amount = launchpad.ido_amount(&querier).unwrap();
+        assert_eq!(ido_amount.amount, 0);
+
+        let stats = launchpad.staking_stats(&querier).unwrap();
+        assert_eq!(stats.total_orai_deposit, Uint128::zero());
+    }
+}
@@ -1,7 +1,14 @@
 pub mod band;
 pub mod contract;
+pub mod delegations;
 pub mod error;
+pub mod events;
+#[cfg(feature = "library")]
+pub mod helpers;
 pub mod msg;
+pub mod rewards;
+pub mod slashing;
 pub mod state;
 pub mod tier;
+pub mod unbonding;
 pub mod utils;
@@ -1,7 +1,10 @@
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::band::Operation;
+use crate::contract::UNBOUND_LATENCY;
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum ResponseStatus {
@@ -25,6 +28,7 @@ pub struct NftToken {
 #[derive(Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct ValidatorWithWeight {
     pub address: String,
+    /// Share of the delegations in basis points. The weights of all validators add up to 10000.
     pub weight: u128,
 }
 
@@ -41,6 +45,12 @@ impl Clone for ValidatorWithWeight {
 pub struct OraiswapContract {
     pub orai_contract: String,
     pub usdt_contract: String,
+    /// Swap operations simulated to price ORAI; empty means ORAI -> `usdt_contract`.
+    #[serde(default)]
+    pub route: Vec<Operation>,
+    /// uorai offered in the simulation, 1 ORAI by default.
+    #[serde(default)]
+    pub offer_amount: Option<Uint128>,
 }
 
 impl Clone for OraiswapContract {
@@ -48,10 +58,129 @@ impl Clone for OraiswapContract {
         OraiswapContract {
             orai_contract: self.orai_contract.clone(),
             usdt_contract: self.usdt_contract.clone(),
+            route: self.route.clone(),
+            offer_amount: self.offer_amount,
+        }
+    }
+}
+
+/// Where the ORAI price in USD used for tier deposits comes from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSourceConfig {
+    /// Simulated swap of one ORAI for USDT on the `oraiswap_contract` router.
+    OraiswapRouter {},
+    /// Band Protocol std reference contract quoting ORAI/USD.
+    BandStdReference { contract: String },
+    /// Pyth-style push oracle publishing the ORAI/USD feed `price_id`.
+    PushOracle { contract: String, price_id: String },
+    /// Price set by the admin, in USD per ORAI.
+    FixedPrice { price: Decimal },
+}
+
+impl Default for PriceSourceConfig {
+    fn default() -> Self {
+        PriceSourceConfig::OraiswapRouter {}
+    }
+}
+
+/// Bounds a tier deposit's live price quote against the price cached by `RefreshPrice`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PriceGuard {
+    /// Seconds after which the cached price no longer backs deposits.
+    pub max_age: u64,
+    /// Largest allowed gap between the live quote and the cached price or TWAP. Refreshed
+    /// quotes are clamped to this gap around the TWAP before they are cached.
+    pub max_deviation_bps: u16,
+    /// Seconds the TWAP averages over.
+    pub twap_window: u64,
+}
+
+/// How a staker's tier follows the ORAI price after the deposit.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TierPolicy {
+    /// The tier reached at deposit time is kept whatever the price does.
+    LockedAtDeposit {},
+    /// The tier is re-derived from `orai_deposit` at the current price. Upgrades apply at once,
+    /// downgrades once the deposit has been worth less than its tier for `grace_period` seconds.
+    MarkToMarket { grace_period: u64 },
+}
+
+impl Default for TierPolicy {
+    fn default() -> Self {
+        TierPolicy::LockedAtDeposit {}
+    }
+}
+
+/// Where early-exit penalties go.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyRecipient {
+    /// Queued as a withdrawal the treasury claims after unbonding.
+    Treasury { address: String },
+    /// Kept delegated and shared by the remaining stakers pro rata to their ORAI deposit.
+    Stakers {},
+}
+
+/// What leaving a tier before its lock ends does.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EarlyExitPolicy {
+    Reject {},
+    Penalty {
+        bps: u16,
+        recipient: PenaltyRecipient,
+    },
+}
+
+impl Default for EarlyExitPolicy {
+    fn default() -> Self {
+        EarlyExitPolicy::Reject {}
+    }
+}
+
+/// Minimum time a tier deposit stays in, counted from the user's last deposit.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TierLocks {
+    /// Seconds per tier, starting from tier 1. Empty when tiers are not locked.
+    pub min_lock_durations: Vec<u64>,
+    pub early_exit: EarlyExitPolicy,
+}
+
+/// How tier withdrawals are unbonded.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case", default)]
+pub struct Unbonding {
+    /// Minimum seconds between two batched undelegations.
+    pub epoch_duration: u64,
+    /// Seconds undelegated uORAI takes to come back.
+    pub unbonding_time: u64,
+    /// Read the unbonding time from the chain's staking params instead of `unbonding_time`.
+    pub from_chain: bool,
+}
+
+impl Default for Unbonding {
+    fn default() -> Self {
+        // the chain keeps up to 7 unbonding entries per validator over 21 days
+        Unbonding {
+            epoch_duration: 3 * 24 * 60 * 60,
+            unbonding_time: UNBOUND_LATENCY,
+            from_chain: false,
         }
     }
 }
 
+/// Share of harvested staking rewards kept by the protocol.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProtocolFee {
+    pub bps: u16,
+    pub recipient: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
@@ -61,8 +190,20 @@ pub struct InstantiateMsg {
     pub validators: Vec<ValidatorWithWeight>, // Tier Contract
     pub deposits: Vec<Uint128>,               // Tier Contract
     pub oraiswap_contract: OraiswapContract,
+    pub price_source: Option<PriceSourceConfig>,
+    pub price_guard: Option<PriceGuard>,
+    pub tier_policy: Option<TierPolicy>,
+    pub tier_locks: Option<TierLocks>,
+    pub protocol_fee: Option<ProtocolFee>,
+    pub unbonding: Option<Unbonding>,
 }
 
+/// Rebuilds the staking aggregates, which deployments from before they were tracked start
+/// without.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum PaymentMethod {
@@ -132,6 +273,10 @@ pub enum ExecuteMsg {
         padding: Option<String>,
     },
     WithdrawFromTier {
+        /// uORAI to unbond. The whole deposit when unset. A partial withdrawal is priced to
+        /// re-tier the rest, and fails while the price guard rejects the quote.
+        #[serde(default)]
+        amount: Option<Uint128>,
         padding: Option<String>,
     },
     Claim {
@@ -141,7 +286,6 @@ pub enum ExecuteMsg {
         padding: Option<String>,
     },
     WithdrawRewards {
-        recipient: Option<String>,
         padding: Option<String>,
     },
     Redelegate {
@@ -149,6 +293,82 @@ pub enum ExecuteMsg {
         recipient: Option<String>,
         padding: Option<String>,
     },
+    UpdatePriceSource {
+        price_source: PriceSourceConfig,
+        padding: Option<String>,
+    },
+    UpdatePriceGuard {
+        price_guard: Option<PriceGuard>,
+        padding: Option<String>,
+    },
+    RefreshPrice {
+        padding: Option<String>,
+    },
+    UpdatePriceRoute {
+        route: Vec<Operation>,
+        offer_amount: Option<Uint128>,
+        padding: Option<String>,
+    },
+    UpdatePriceFallbacks {
+        fallbacks: Vec<PriceSourceConfig>,
+        max_divergence_bps: Option<u16>,
+        padding: Option<String>,
+    },
+    ResetCircuitBreaker {
+        padding: Option<String>,
+    },
+    UpdateTierPolicy {
+        tier_policy: TierPolicy,
+        padding: Option<String>,
+    },
+    SyncTier {
+        address: String,
+        padding: Option<String>,
+    },
+    UpdateTierLocks {
+        tier_locks: TierLocks,
+        padding: Option<String>,
+    },
+    ClaimStakingRewards {
+        padding: Option<String>,
+    },
+    UpdateProtocolFee {
+        protocol_fee: Option<ProtocolFee>,
+        padding: Option<String>,
+    },
+    SetAutoCompound {
+        enabled: bool,
+        padding: Option<String>,
+    },
+    Compound {
+        addresses: Vec<String>,
+        padding: Option<String>,
+    },
+    AddValidator {
+        address: String,
+        padding: Option<String>,
+    },
+    RemoveValidator {
+        address: String,
+        padding: Option<String>,
+    },
+    SetWeights {
+        validators: Vec<ValidatorWithWeight>,
+        padding: Option<String>,
+    },
+    Rebalance {
+        padding: Option<String>,
+    },
+    ProcessUnbondingEpoch {
+        padding: Option<String>,
+    },
+    UpdateUnbonding {
+        unbonding: Unbonding,
+        padding: Option<String>,
+    },
+    ReconcileDelegations {
+        padding: Option<String>,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
@@ -207,6 +427,79 @@ pub enum ExecuteResponse {
         amount: Uint128,
         status: ResponseStatus,
     },
+    UpdatePriceSource {
+        status: ResponseStatus,
+    },
+    UpdatePriceGuard {
+        status: ResponseStatus,
+    },
+    RefreshPrice {
+        price: Decimal,
+        twap: Decimal,
+        status: ResponseStatus,
+    },
+    UpdatePriceRoute {
+        status: ResponseStatus,
+    },
+    UpdatePriceFallbacks {
+        status: ResponseStatus,
+    },
+    ResetCircuitBreaker {
+        status: ResponseStatus,
+    },
+    UpdateTierPolicy {
+        status: ResponseStatus,
+    },
+    SyncTier {
+        tier: u8,
+        status: ResponseStatus,
+    },
+    UpdateTierLocks {
+        status: ResponseStatus,
+    },
+    ClaimStakingRewards {
+        amount: Uint128,
+        status: ResponseStatus,
+    },
+    UpdateProtocolFee {
+        status: ResponseStatus,
+    },
+    SetAutoCompound {
+        enabled: bool,
+        status: ResponseStatus,
+    },
+    Compound {
+        amount: Uint128,
+        status: ResponseStatus,
+    },
+    AddValidator {
+        status: ResponseStatus,
+    },
+    RemoveValidator {
+        status: ResponseStatus,
+    },
+    SetWeights {
+        status: ResponseStatus,
+    },
+    Rebalance {
+        amount: Uint128,
+        status: ResponseStatus,
+    },
+    ProcessUnbondingEpoch {
+        epoch: u64,
+        amount: Uint128,
+        claim_time: u64,
+        status: ResponseStatus,
+    },
+    UpdateUnbonding {
+        status: ResponseStatus,
+    },
+    ReconcileDelegations {
+        slashed: Uint128,
+        /// Share of the delegated uORAI that was left.
+        ratio: Decimal,
+        status: ResponseStatus,
+    },
     // ............
 }
 
@@ -243,14 +536,50 @@ pub enum QueryMsg {
         address: String,
         ido_id: Option<u32>,
     },
+    IdoParticipants {
+        ido_id: u32,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    UserPortfolio {
+        address: String,
+    },
+    SimulateBuy {
+        ido_id: u32,
+        address: String,
+        amount: Uint128,
+        viewing_key: Option<String>,
+    },
     TierUserInfo {
         address: String,
     },
+    TierInfo {
+        address: String,
+        nft_token_ids: Option<Vec<String>>,
+    },
+    SimulateDeposit {
+        address: String,
+        target_tier: u8,
+    },
     Withdrawals {
         address: String,
         start: Option<u32>,
         limit: Option<u32>,
     },
+    StakingStats {},
+    PriceCache {},
+    CircuitBreaker {},
+    TierLock {
+        address: String,
+    },
+    PendingRewards {
+        address: String,
+    },
+    Delegations {},
+    /// The open epoch when `id` is not set.
+    UnbondingEpoch {
+        id: Option<u64>,
+    },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
@@ -260,77 +589,271 @@ pub struct PurchaseAnswer {
     pub unlock_time: u64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ParticipantAnswer {
+    pub address: String,
+    pub total_payment: Uint128,
+    pub total_tokens_bought: Uint128,
+    pub total_tokens_received: Uint128,
+    /// Tier of the latest purchase, or of the deposit at the migration for earlier buyers.
+    pub tier: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PortfolioPosition {
+    pub ido_id: u32,
+    pub total_payment: Uint128,
+    pub total_tokens_bought: Uint128,
+    pub total_tokens_received: Uint128,
+    pub claimable_tokens: Uint128,
+    pub next_unlock_time: Option<u64>,
+    pub refund_eligible: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BuyRejection {
+    IdoNotActive,
+    ZeroAmount,
+    AllTokensSold,
+    TierSoldOut { tier: u8 },
+    ExceedsTierRemaining { tier: u8, remaining: Uint128 },
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct SerializedWithdrawals {
     pub amount: Uint128,
+    /// Estimated until the withdrawal's epoch is undelegated.
     pub claim_time: u64,
     pub timestamp: u64,
+    pub epoch: Option<u64>,
+    /// Whether the epoch still waits for `ProcessUnbondingEpoch`.
+    pub queued: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryResponse {
-    Config {
-        admin: String,
-        nft_contract: String,
-        lock_periods: Vec<u64>,
-        validators: Vec<ValidatorWithWeight>,
-        status: u8,
-        usd_deposits: Vec<Uint128>,
-        min_tier: u8,
-    },
-    IdoAmount {
-        amount: u32,
-    },
-    IdoInfo {
-        admin: String,
-        start_time: u64,
-        end_time: u64,
-        token_contract: String,
-        price: Uint128,
-        participants: u64,
-        payment: PaymentMethod,
-        sold_amount: Uint128,
-        total_tokens_amount: Uint128,
-        total_payment: Uint128,
-        soft_cap: Uint128,
-        withdrawn: bool,
-        shared_whitelist: bool,
-        remaining_per_tiers: Vec<Uint128>,
-    },
-    InWhitelist {
-        in_whitelist: bool,
-    },
-    IdoListOwnedBy {
-        ido_ids: Vec<u32>,
-        amount: u32,
-    },
-    Purchases {
-        purchases: Vec<PurchaseAnswer>,
-        amount: u32,
-    },
-    ArchivedPurchases {
-        purchases: Vec<PurchaseAnswer>,
-        amount: u32,
-    },
-    UserInfo {
-        total_payment: Uint128,
-        total_tokens_bought: Uint128,
-        total_tokens_received: Uint128,
-    },
-    TierUserInfo {
-        tier: u8,
-        timestamp: u64,
-        usd_deposit: Uint128,
-        orai_deposit: Uint128,
-    },
-    TierInfo {
-        tier: u8,
-        nft_tier: u8,
-    },
-    Withdrawals {
-        amount: u32,
-        withdrawals: Vec<SerializedWithdrawals>,
-    },
+    Config(Box<ConfigResponse>),
+    IdoAmount(IdoAmountResponse),
+    IdoInfo(IdoInfoResponse),
+    InWhitelist(InWhitelistResponse),
+    IdoListOwnedBy(IdoListOwnedByResponse),
+    Purchases(PurchasesResponse),
+    ArchivedPurchases(ArchivedPurchasesResponse),
+    UserInfo(UserInfoResponse),
+    IdoParticipants(IdoParticipantsResponse),
+    UserPortfolio(UserPortfolioResponse),
+    SimulateBuy(SimulateBuyResponse),
+    TierUserInfo(TierUserInfoResponse),
+    TierInfo(TierInfoResponse),
+    SimulateDeposit(SimulateDepositResponse),
+    Withdrawals(WithdrawalsResponse),
+    StakingStats(StakingStatsResponse),
+    PriceCache(PriceCacheResponse),
+    CircuitBreaker(CircuitBreakerResponse),
+    TierLock(TierLockResponse),
+    PendingRewards(PendingRewardsResponse),
+    Delegations(DelegationsResponse),
+    UnbondingEpoch(UnbondingEpochResponse),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigResponse {
+    pub admin: String,
+    pub nft_contract: String,
+    pub lock_periods: Vec<u64>,
+    pub validators: Vec<ValidatorWithWeight>,
+    pub status: u8,
+    pub usd_deposits: Vec<Uint128>,
+    pub min_tier: u8,
+    pub price_source: PriceSourceConfig,
+    pub price_guard: Option<PriceGuard>,
+    pub oraiswap_contract: OraiswapContract,
+    pub price_fallbacks: Vec<PriceSourceConfig>,
+    pub max_price_divergence_bps: Option<u16>,
+    pub tier_policy: TierPolicy,
+    pub tier_locks: TierLocks,
+    pub protocol_fee: Option<ProtocolFee>,
+    pub unbonding: Unbonding,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IdoAmountResponse {
+    pub amount: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IdoInfoResponse {
+    pub admin: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub token_contract: String,
+    pub price: Uint128,
+    pub participants: u64,
+    pub payment: PaymentMethod,
+    pub sold_amount: Uint128,
+    pub total_tokens_amount: Uint128,
+    pub total_payment: Uint128,
+    pub soft_cap: Uint128,
+    pub withdrawn: bool,
+    pub shared_whitelist: bool,
+    pub remaining_per_tiers: Vec<Uint128>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct InWhitelistResponse {
+    pub in_whitelist: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IdoListOwnedByResponse {
+    pub ido_ids: Vec<u32>,
+    pub amount: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PurchasesResponse {
+    pub purchases: Vec<PurchaseAnswer>,
+    pub amount: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ArchivedPurchasesResponse {
+    pub purchases: Vec<PurchaseAnswer>,
+    pub amount: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UserInfoResponse {
+    pub total_payment: Uint128,
+    pub total_tokens_bought: Uint128,
+    pub total_tokens_received: Uint128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IdoParticipantsResponse {
+    pub participants: Vec<ParticipantAnswer>,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UserPortfolioResponse {
+    pub positions: Vec<PortfolioPosition>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SimulateBuyResponse {
+    pub whitelisted: bool,
+    /// Tier the purchase is allocated from, or the lowest tier when the IDO rejects it outright.
+    pub tier: u8,
+    pub amount: Uint128,
+    pub payment: Uint128,
+    pub unlock_time: Option<u64>,
+    pub rejection: Option<BuyRejection>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TierUserInfoResponse {
+    pub tier: u8,
+    pub timestamp: u64,
+    pub usd_deposit: Uint128,
+    pub orai_deposit: Uint128,
+    pub below_tier_since: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TierInfoResponse {
+    pub tier: u8,
+    pub stake_tier: u8,
+    pub nft_tier: u8,
+    pub next_tier: Option<u8>,
+    pub usd_to_next_tier: Uint128,
+    pub orai_to_next_tier: Uint128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SimulateDepositResponse {
+    pub usd_needed: Uint128,
+    pub orai_needed: Uint128,
+    pub tier: u8,
+    pub orai_refund: Uint128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WithdrawalsResponse {
+    pub amount: u32,
+    pub withdrawals: Vec<SerializedWithdrawals>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StakingStatsResponse {
+    pub total_orai_deposit: Uint128,
+    pub total_usd_deposit: Uint128,
+    pub users_per_tier: Vec<u64>,
+    pub total_pending_withdrawals: Uint128,
+    /// Shared staking rewards and penalties that are still delegated and not claimed yet.
+    pub outstanding_rewards: Uint128,
+    /// Share of the delegated uORAI left after every slash so far.
+    pub slashing_index: Decimal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PriceCacheResponse {
+    pub price: Decimal,
+    pub twap: Decimal,
+    pub updated_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CircuitBreakerResponse {
+    pub tripped: bool,
+    pub tripped_at: Option<u64>,
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TierLockResponse {
+    pub tier: u8,
+    /// When the deposit can leave without the early-exit policy kicking in.
+    pub unlock_time: u64,
+    pub locked: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PendingRewardsResponse {
+    pub amount: Uint128,
+    pub auto_compound: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ValidatorDelegation {
+    pub validator: String,
+    /// Weight in basis points, zero once the validator left the set.
+    pub weight: u128,
+    /// uORAI the contract's ledger says it delegated.
+    pub delegated: Uint128,
+    /// uORAI the chain reports as delegated.
+    pub live: Uint128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DelegationsResponse {
+    pub delegations: Vec<ValidatorDelegation>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UnbondingEpochResponse {
+    pub id: u64,
+    pub amount: Uint128,
+    pub opened_at: u64,
+    pub processable_at: u64,
+    pub unbonded_at: Option<u64>,
+    /// The one applied once the epoch is undelegated, the current one until then.
+    pub unbonding_time: u64,
+    /// Estimated until the epoch is undelegated.
+    pub claim_time: u64,
 }
@@ -0,0 +1,272 @@
+//! Staking rewards and early-exit penalties shared by the tier stakers.
+//!
+//! Both stay delegated: harvested rewards are delegated again once the protocol fee is paid,
+//! and shared penalties are never unbonded. Each one raises a global index of uORAI earned per
+//! staked uORAI, and a staker settles the index into their pending rewards whenever their
+//! deposit changes. `ClaimStakingRewards` unbonds the pending rewards like a tier withdrawal,
+//! while `Compound` moves them into the tier deposit of stakers that opted in.
+use cosmwasm_std::{
+    coins, Addr, BankMsg, Decimal, DepsMut, DistributionMsg, Env, Event, QuerierWrapper, StdResult,
+    Storage, SubMsg, Uint128,
+};
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+
+use crate::contract::ORAI;
+use crate::error::ContractError;
+use crate::events;
+use crate::msg::ValidatorWithWeight;
+use crate::slashing;
+use crate::state::{Config, StakingStats};
+
+pub const REWARD_POOL: Item<RewardPool> = Item::new("reward_pool");
+pub const STAKER_REWARDS: Map<String, StakerRewards> = Map::new("staker_rewards");
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RewardPool {
+    /// uORAI earned per staked uORAI so far.
+    pub index: Decimal,
+    /// Shared uORAI that is still delegated and not claimed yet.
+    pub outstanding: u128,
+    /// Shared uORAI held back because nobody was staking at the time.
+    pub undistributed: u128,
+}
+
+impl RewardPool {
+    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
+        Ok(REWARD_POOL.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        REWARD_POOL.save(storage, self)
+    }
+
+    /// Shares `amount` uORAI between the `total_staked` uORAI currently deposited.
+    pub fn distribute(&mut self, amount: u128, total_staked: u128) -> Result<(), ContractError> {
+        self.outstanding = self
+            .outstanding
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow {})?;
+        let amount = self
+            .undistributed
+            .checked_add(amount)
+            .ok_or(ContractError::Overflow {})?;
+        if total_staked == 0 {
+            self.undistributed = amount;
+            return Ok(());
+        }
+
+        let growth = Decimal::checked_from_ratio(amount, total_staked)
+            .map_err(|_| ContractError::Overflow {})?;
+        self.index = self
+            .index
+            .checked_add(growth)
+            .map_err(|_| ContractError::Overflow {})?;
+        self.undistributed = 0;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StakerRewards {
+    /// Pool index the staker was last settled at.
+    pub index: Decimal,
+    pub pending: u128,
+    /// Whether `Compound` may add the pending rewards to the staker's tier deposit.
+    #[serde(default)]
+    pub auto_compound: bool,
+    /// Slashing index `pending` is up to date with.
+    #[serde(default)]
+    pub slashing_index: Option<Decimal>,
+}
+
+impl StakerRewards {
+    /// Pending rewards stay delegated, so they take their share of slashes.
+    pub fn load(storage: &dyn Storage, address: &str) -> StdResult<Self> {
+        let index = slashing::index(storage)?;
+        let mut rewards = STAKER_REWARDS
+            .may_load(storage, address.to_string())?
+            .unwrap_or_default();
+        rewards.pending = slashing::scale(rewards.pending, rewards.slashing_index, index);
+        rewards.slashing_index = Some(index);
+
+        Ok(rewards)
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage, address: &str) -> StdResult<()> {
+        STAKER_REWARDS.save(storage, address.to_string(), self)
+    }
+
+    /// Adds what `staked` uORAI earned since the last settlement.
+    pub fn settle(&mut self, pool: &RewardPool, staked: u128) -> Result<(), ContractError> {
+        let growth = pool
+            .index
+            .checked_sub(self.index)
+            .map_err(|_| ContractError::Overflow {})?;
+        let earned = Uint128::new(staked)
+            .checked_multiply_ratio(growth.atomics(), Decimal::one().atomics())
+            .map_err(|_| ContractError::Overflow {})?;
+
+        self.pending = self
+            .pending
+            .checked_add(earned.u128())
+            .ok_or(ContractError::Overflow {})?;
+        self.index = pool.index;
+
+        Ok(())
+    }
+}
+
+/// Settles `address` before its `staked` uORAI change.
+pub fn settle(
+    storage: &mut dyn Storage,
+    address: &str,
+    staked: u128,
+) -> Result<StakerRewards, ContractError> {
+    let pool = RewardPool::load(storage)?;
+    let mut rewards = StakerRewards::load(storage, address)?;
+    rewards.settle(&pool, staked)?;
+    rewards.save(storage, address)?;
+
+    Ok(rewards)
+}
+
+/// Staking rewards pulled from the validators by [`harvest`].
+#[derive(Default)]
+pub struct Harvest {
+    /// Reward withdrawals and the protocol fee payment.
+    pub messages: Vec<SubMsg>,
+    pub events: Vec<Event>,
+    /// uORAI withdrawn from all validators.
+    pub rewards: u128,
+    /// uORAI left after the protocol fee, which the caller has to delegate again.
+    pub restake: u128,
+}
+
+/// Staking rewards accrued on each validator, skipping those without any.
+pub fn accrued_rewards(
+    querier: &QuerierWrapper,
+    delegator: &Addr,
+    validators: &[ValidatorWithWeight],
+) -> StdResult<Vec<(String, u128)>> {
+    let mut accrued = vec![];
+    for validator in validators {
+        let rewards: u128 = querier
+            .query_delegation(delegator, &validator.address)?
+            .map(|delegation| {
+                delegation
+                    .accumulated_rewards
+                    .iter()
+                    .filter(|reward| reward.denom == ORAI)
+                    .map(|reward| reward.amount.u128())
+                    .sum()
+            })
+            .unwrap_or_default();
+        if rewards != 0 {
+            accrued.push((validator.address.clone(), rewards));
+        }
+    }
+
+    Ok(accrued)
+}
+
+/// Part of `rewards` the protocol keeps under `config`.
+pub fn protocol_fee(config: &Config, rewards: u128) -> u128 {
+    config
+        .protocol_fee
+        .as_ref()
+        .map(|fee| {
+            Uint128::new(rewards)
+                .multiply_ratio(fee.bps, 10_000u128)
+                .u128()
+        })
+        .unwrap_or_default()
+}
+
+/// Withdraws the staking rewards of every validator and shares them, minus the protocol fee,
+/// between the current stakers. Runs before every delegation change, since the chain pays
+/// out pending rewards on those anyway.
+pub fn harvest(deps: DepsMut, env: &Env, config: &Config) -> Result<Harvest, ContractError> {
+    let accrued = accrued_rewards(&deps.querier, &env.contract.address, &config.validators)?;
+
+    let mut harvest = Harvest::default();
+    for (validator, rewards) in accrued {
+        harvest.rewards = harvest
+            .rewards
+            .checked_add(rewards)
+            .ok_or(ContractError::Overflow {})?;
+        harvest
+            .messages
+            .push(SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                validator: validator.clone(),
+            }));
+        harvest
+            .events
+            .push(events::rewards_withdrawn(&validator, rewards));
+    }
+    if harvest.rewards == 0 {
+        return Ok(harvest);
+    }
+
+    let fee = protocol_fee(config, harvest.rewards);
+    if let (Some(protocol_fee), true) = (&config.protocol_fee, fee != 0) {
+        harvest.messages.push(SubMsg::new(BankMsg::Send {
+            to_address: protocol_fee.recipient.clone(),
+            amount: coins(fee, ORAI),
+        }));
+    }
+    harvest.restake = harvest.rewards - fee;
+
+    let stats = StakingStats::load(deps.storage)?;
+    let mut pool = RewardPool::load(deps.storage)?;
+    pool.distribute(harvest.restake, stats.total_orai_deposit)?;
+    pool.save(deps.storage)?;
+    harvest.events.push(events::rewards_harvested(
+        harvest.rewards,
+        fee,
+        harvest.restake,
+    ));
+
+    Ok(harvest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn shared_penalties() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+
+        // nobody stakes yet, so the first penalty waits for the next one
+        let mut pool = RewardPool::load(storage).unwrap();
+        pool.distribute(300, 0).unwrap();
+        assert_eq!(pool.index, Decimal::zero());
+        assert_eq!(pool.undistributed, 300);
+        pool.save(storage).unwrap();
+
+        // alice stakes 1_000 and bob 3_000
+        settle(storage, "alice", 0).unwrap();
+        settle(storage, "bob", 0).unwrap();
+
+        let mut pool = RewardPool::load(storage).unwrap();
+        pool.distribute(100, 4_000).unwrap();
+        assert_eq!(pool.index, Decimal::percent(10));
+        assert_eq!(pool.outstanding, 400);
+        assert_eq!(pool.undistributed, 0);
+        pool.save(storage).unwrap();
+
+        // bob doubles his stake, which only counts from now on
+        assert_eq!(settle(storage, "bob", 3_000).unwrap().pending, 300);
+        let mut pool = RewardPool::load(storage).unwrap();
+        pool.distribute(70, 7_000).unwrap();
+        pool.save(storage).unwrap();
+
+        assert_eq!(settle(storage, "alice", 1_000).unwrap().pending, 110);
+        assert_eq!(settle(storage, "bob", 6_000).unwrap().pending, 360);
+        assert_eq!(settle(storage, "bob", 6_000).unwrap().pending, 360);
+    }
+}
@@ -0,0 +1,143 @@
+//! Validator slashes shared by everything the delegated uORAI backs.
+//!
+//! `ReconcileDelegations` writes slashes off the delegation ledger. The stake left over the
+//! stake before gives a ratio that scales the totals right away and a global index that the
+//! single records catch up with once loaded: tier deposits, pending staking rewards and the
+//! withdrawals waiting in the open unbonding epoch. The chain slashes unbonding entries too but
+//! does not report them to contracts, so the epochs still unbonding take the same ratio.
+//! Withdrawals queued before epochs existed keep their amount.
+use cosmwasm_std::{Decimal, StdResult, Storage, Uint128};
+use cw_storage_plus::Item;
+
+use crate::error::ContractError;
+use crate::rewards::RewardPool;
+use crate::state::StakingStats;
+use crate::unbonding::UnbondingEpoch;
+
+pub const SLASHING_INDEX: Item<Decimal> = Item::new("slashing_index");
+
+/// Share of the delegated uORAI left after every slash so far.
+pub fn index(storage: &dyn Storage) -> StdResult<Decimal> {
+    Ok(SLASHING_INDEX
+        .may_load(storage)?
+        .unwrap_or_else(Decimal::one))
+}
+
+/// `amount` recorded at index `since`, one when unset, as of index `until`.
+pub fn scale(amount: u128, since: Option<Decimal>, until: Decimal) -> u128 {
+    let since = since.unwrap_or_else(Decimal::one);
+    if since == until || since.is_zero() {
+        return amount;
+    }
+    Uint128::new(amount)
+        .multiply_ratio(until.atomics(), since.atomics())
+        .u128()
+}
+
+fn apply(amount: u128, ratio: Decimal) -> u128 {
+    Uint128::new(amount)
+        .multiply_ratio(ratio.atomics(), Decimal::one().atomics())
+        .u128()
+}
+
+/// Records that the delegated uORAI kept `ratio` of itself at `now` and returns the new index.
+pub fn record(
+    storage: &mut dyn Storage,
+    ratio: Decimal,
+    now: u64,
+) -> Result<Decimal, ContractError> {
+    let index = index(storage)?
+        .checked_mul(ratio)
+        .map_err(|_| ContractError::Overflow {})?;
+    SLASHING_INDEX.save(storage, &index)?;
+
+    let mut epoch = UnbondingEpoch::open(storage)?;
+    let mut withdrawals_slashed = epoch.amount - apply(epoch.amount, ratio);
+    epoch.amount -= withdrawals_slashed;
+    epoch.save(storage)?;
+    // epochs are undelegated in order, so the first one found back ends the walk
+    for id in (0..epoch.id).rev() {
+        let mut epoch = UnbondingEpoch::load(storage, id)?;
+        if !epoch.is_unbonding(now) {
+            break;
+        }
+        let slashed = epoch.amount - apply(epoch.amount, ratio);
+        withdrawals_slashed += slashed;
+        epoch.amount -= slashed;
+        epoch.slashing_index = Some(
+            epoch
+                .slashing_index
+                .unwrap_or_else(Decimal::one)
+                .checked_mul(ratio)
+                .map_err(|_| ContractError::Overflow {})?,
+        );
+        epoch.save(storage)?;
+    }
+
+    let mut stats = StakingStats::load(storage)?;
+    stats.total_orai_deposit = apply(stats.total_orai_deposit, ratio);
+    stats.total_pending_withdrawals = stats
+        .total_pending_withdrawals
+        .saturating_sub(withdrawals_slashed);
+    stats.save(storage)?;
+
+    let mut pool = RewardPool::load(storage)?;
+    pool.outstanding = apply(pool.outstanding, ratio);
+    pool.undistributed = apply(pool.undistributed, ratio);
+    pool.save(storage)?;
+
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::Unbonding;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn records_catch_up() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        assert_eq!(index(storage).unwrap(), Decimal::one());
+
+        let unbonding = Unbonding {
+            epoch_duration: 100,
+            unbonding_time: 1_000,
+            from_chain: false,
+        };
+        let mut stats = StakingStats::load(storage).unwrap();
+        stats.total_orai_deposit = 1_000;
+        stats.total_pending_withdrawals = 100 + 200 + 300;
+        stats.save(storage).unwrap();
+        // back in the contract, still unbonding and open
+        crate::unbonding::queue(storage, 100)
+            .unwrap()
+            .process(storage, &unbonding, 100)
+            .unwrap();
+        crate::unbonding::queue(storage, 200)
+            .unwrap()
+            .process(storage, &unbonding, 600)
+            .unwrap();
+        crate::unbonding::queue(storage, 300).unwrap();
+
+        // 10% slashed, then 50% of the rest
+        record(storage, Decimal::percent(90), 1_100).unwrap();
+        let index = record(storage, Decimal::percent(50), 1_200).unwrap();
+        assert_eq!(index, Decimal::percent(45));
+
+        let stats = StakingStats::load(storage).unwrap();
+        assert_eq!(stats.total_orai_deposit, 450);
+        assert_eq!(stats.total_pending_withdrawals, 100 + 90 + 135);
+        assert_eq!(UnbondingEpoch::load(storage, 0).unwrap().amount, 100);
+        let unbonding_epoch = UnbondingEpoch::load(storage, 1).unwrap();
+        assert_eq!(unbonding_epoch.amount, 90);
+        assert_eq!(unbonding_epoch.slashing_index, Some(index));
+        assert_eq!(UnbondingEpoch::open(storage).unwrap().amount, 135);
+
+        // deposits recorded before, between and after the slashes
+        assert_eq!(scale(1_000, None, index), 450);
+        assert_eq!(scale(1_000, Some(Decimal::percent(90)), index), 500);
+        assert_eq!(scale(1_000, Some(index), index), 1_000);
+    }
+}
@@ -1,8 +1,12 @@
+use crate::error::ContractError;
 use crate::msg::{
-    ContractStatus, OraiswapContract, PaymentMethod, PurchaseAnswer, QueryResponse,
-    SerializedWithdrawals, ValidatorWithWeight,
+    CircuitBreakerResponse, ConfigResponse, ContractStatus, IdoInfoResponse, OraiswapContract,
+    PaymentMethod, PriceCacheResponse, PriceGuard, PriceSourceConfig, ProtocolFee, PurchaseAnswer,
+    QueryResponse, SerializedWithdrawals, TierLocks, TierPolicy, TierUserInfoResponse, Unbonding,
+    UserInfoResponse, ValidatorWithWeight,
 };
-use cosmwasm_std::{Order, StdError, StdResult, Storage, Uint128};
+use crate::slashing;
+use cosmwasm_std::{Decimal, Order, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 use serde::{Deserialize, Serialize};
 use std::cmp::min;
@@ -12,12 +16,17 @@ pub const PURCHASES: Map<(String, u32), Vec<Purchase>> = Map::new("purchase"); /
 pub const ARCHIVED_PURCHASES: Map<(String, u32), Vec<Purchase>> = Map::new("archive");
 pub const ACTIVE_IDOS: Map<(String, u32), bool> = Map::new("active_idos");
 pub const IDO_TO_INFO: Map<(String, u32), UserInfo> = Map::new("ido2info");
+// (ido, user) -> tier used for the latest purchase
+pub const IDO_PARTICIPANTS: Map<(u32, String), u8> = Map::new("ido2participants");
 pub const OWNER_TO_IDOS: Map<String, Vec<u32>> = Map::new("owner2idos");
 pub const WHITELIST: Map<(u32, String), bool> = Map::new("whitelist");
 pub const USERINFO: Map<String, UserInfo> = Map::new("usr2info");
 pub const TIER_USER_INFOS: Map<String, TierUserInfo> = Map::new("user_info");
 pub const IDO_ITEM: Map<u32, Ido> = Map::new("ido_list");
 pub const WITHDRAWALS_LIST: Map<String, Vec<UserWithdrawal>> = Map::new("withdraw");
+pub const STAKING_STATS: Item<StakingStats> = Item::new("staking_stats");
+pub const PRICE_CACHE: Item<PriceCache> = Item::new("price_cache");
+pub const CIRCUIT_BREAKER: Item<CircuitBreaker> = Item::new("circuit_breaker");
 // pub fn ido_whitelist(ido_id: u32, storage: &dyn Storage) -> Map<String, bool> {
 
 //     let key = format!("whitelist_{}", ido_id);
@@ -76,6 +85,22 @@ pub struct Config {
     pub validators: Vec<ValidatorWithWeight>, // Tier Contract
     pub usd_deposits: Vec<u128>,              // Tier Contract
     pub oraiswap_contract: OraiswapContract,
+    #[serde(default)]
+    pub price_source: PriceSourceConfig,
+    #[serde(default)]
+    pub price_guard: Option<PriceGuard>,
+    #[serde(default)]
+    pub price_fallbacks: Vec<PriceSourceConfig>,
+    #[serde(default)]
+    pub max_price_divergence_bps: Option<u16>,
+    #[serde(default)]
+    pub tier_policy: TierPolicy,
+    #[serde(default)]
+    pub tier_locks: TierLocks,
+    #[serde(default)]
+    pub protocol_fee: Option<ProtocolFee>,
+    #[serde(default)]
+    pub unbonding: Unbonding,
 }
 
 impl Config {
@@ -103,7 +128,7 @@ impl Config {
         }
         // let temp_validators = self.validators.clone();
 
-        Ok(QueryResponse::Config {
+        Ok(QueryResponse::Config(Box::new(ConfigResponse {
             admin,
             nft_contract,
             validators: temp_validators,
@@ -115,7 +140,16 @@ impl Config {
                 .map(|d| Uint128::from(*d))
                 .collect(),
             min_tier: self.min_tier,
-        })
+            price_source: self.price_source,
+            price_guard: self.price_guard,
+            oraiswap_contract: self.oraiswap_contract,
+            price_fallbacks: self.price_fallbacks,
+            max_price_divergence_bps: self.max_price_divergence_bps,
+            tier_policy: self.tier_policy,
+            tier_locks: self.tier_locks,
+            protocol_fee: self.protocol_fee,
+            unbonding: self.unbonding,
+        })))
     }
 
     // Tier Contract
@@ -127,11 +161,30 @@ impl Config {
         1
     }
 
+    /// Tier one step above `tier`, failing for the top tier and for tiers out of range.
+    pub fn next_tier(&self, tier: u8) -> Result<u8, ContractError> {
+        if tier <= self.max_tier() || tier > self.min_tier {
+            return Err(ContractError::InvalidTier { tier });
+        }
+
+        Ok(tier - 1)
+    }
+
     pub fn deposit_by_tier(&self, tier: u8) -> u128 {
         let tier_index = tier.checked_sub(1).unwrap();
         self.usd_deposits[tier_index as usize]
     }
 
+    /// USD deposit recorded for a user at `tier` whose ORAI is worth `usd_value`: the tier
+    /// threshold, like a deposit leaves it, or the value itself below the lowest tier.
+    pub fn tier_usd_deposit(&self, tier: u8, usd_value: u128) -> u128 {
+        if tier < self.min_tier() {
+            self.deposit_by_tier(tier)
+        } else {
+            usd_value
+        }
+    }
+
     pub fn tier_by_deposit(&self, usd_deposit: u128) -> u8 {
         self.usd_deposits
             .iter()
@@ -141,10 +194,10 @@ impl Config {
             .unwrap() as u8
     }
 
-    pub fn assert_contract_active(&self) -> StdResult<()> {
+    pub fn assert_contract_active(&self) -> Result<(), ContractError> {
         let active = ContractStatus::Active as u8;
         if self.status != active {
-            return Err(StdError::generic_err("Contract is not active"));
+            return Err(ContractError::ContractNotActive {});
         }
 
         Ok(())
@@ -179,11 +232,11 @@ pub struct UserInfo {
 
 impl UserInfo {
     pub fn to_answer(&self) -> QueryResponse {
-        QueryResponse::UserInfo {
+        QueryResponse::UserInfo(UserInfoResponse {
             total_payment: Uint128::new(self.total_payment),
             total_tokens_bought: Uint128::new(self.total_tokens_bought),
             total_tokens_received: Uint128::new(self.total_tokens_received),
-        }
+        })
     }
 }
 
@@ -193,18 +246,99 @@ pub struct TierUserInfo {
     pub timestamp: u64,
     pub usd_deposit: u128,
     pub orai_deposit: u128,
+    /// Set by `SyncTier` while the deposit is worth less than `tier` under mark-to-market.
+    #[serde(default)]
+    pub below_tier_since: Option<u64>,
+    /// Slashing index `orai_deposit` is up to date with, see [`slashing`](crate::slashing).
+    #[serde(default)]
+    pub slashing_index: Option<Decimal>,
 }
 
 impl TierUserInfo {
+    /// The tier deposit of `address`, less the slashes since it was last saved.
+    pub fn may_load(storage: &dyn Storage, address: &str) -> StdResult<Option<Self>> {
+        let index = slashing::index(storage)?;
+        Ok(TIER_USER_INFOS
+            .may_load(storage, address.to_string())?
+            .map(|mut user_info| {
+                user_info.orai_deposit =
+                    slashing::scale(user_info.orai_deposit, user_info.slashing_index, index);
+                user_info.slashing_index = Some(index);
+                user_info
+            }))
+    }
+
     pub fn get_tier(&self) -> u8 {
         self.tier as u8
     }
+
+    /// When the deposit is free to leave under `tier_locks`.
+    pub fn unlock_time(&self, tier_locks: &TierLocks) -> u64 {
+        let lock_duration = self
+            .tier
+            .checked_sub(1)
+            .and_then(|tier_index| tier_locks.min_lock_durations.get(tier_index as usize))
+            .copied()
+            .unwrap_or_default();
+
+        self.timestamp.saturating_add(lock_duration)
+    }
+
+    /// Tier and grace start under mark-to-market when the deposit is worth `live_tier` now.
+    pub fn mark_to_market(&self, live_tier: u8, now: u64, grace_period: u64) -> (u8, Option<u64>) {
+        if live_tier <= self.tier {
+            return (live_tier, None);
+        }
+
+        let since = self.below_tier_since.unwrap_or(now);
+        if now >= since.saturating_add(grace_period) {
+            (live_tier, None)
+        } else {
+            (self.tier, Some(since))
+        }
+    }
+
     pub fn to_answer(&self) -> QueryResponse {
-        QueryResponse::TierUserInfo {
+        QueryResponse::TierUserInfo(TierUserInfoResponse {
             tier: self.tier,
             timestamp: self.timestamp,
             usd_deposit: Uint128::from(self.usd_deposit),
             orai_deposit: Uint128::from(self.orai_deposit),
+            below_tier_since: self.below_tier_since,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StakingStats {
+    pub total_orai_deposit: u128,
+    pub total_usd_deposit: u128,
+    pub users_per_tier: Vec<u64>,
+    pub total_pending_withdrawals: u128,
+}
+
+impl StakingStats {
+    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
+        Ok(STAKING_STATS.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        STAKING_STATS.save(storage, self)
+    }
+
+    pub fn add_user(&mut self, tier: u8) {
+        let tier_index = tier.checked_sub(1).unwrap() as usize;
+        if self.users_per_tier.len() <= tier_index {
+            self.users_per_tier.resize(tier_index + 1, 0);
+        }
+
+        self.users_per_tier[tier_index] = self.users_per_tier[tier_index].saturating_add(1);
+    }
+
+    pub fn remove_user(&mut self, tier: u8) {
+        let tier_index = tier.checked_sub(1).unwrap() as usize;
+        if let Some(users) = self.users_per_tier.get_mut(tier_index) {
+            *users = users.saturating_sub(1);
         }
     }
 }
@@ -269,6 +403,10 @@ impl Ido {
         current_time >= self.start_time && current_time < self.end_time
     }
 
+    pub fn is_failed(&self, current_time: u64) -> bool {
+        current_time > self.end_time && self.soft_cap > self.sold_amount
+    }
+
     pub fn is_native_payment(&self) -> bool {
         self.payment_token_contract.is_none() && self.payment_token_hash.is_none()
     }
@@ -304,7 +442,7 @@ impl Ido {
         for tier in 1..=(self.remaining_tokens_per_tier.len() as u8) {
             remaining_per_tiers.push(Uint128::new(self.remaining_tokens_per_tier(tier)));
         }
-        Ok(QueryResponse::IdoInfo {
+        Ok(QueryResponse::IdoInfo(IdoInfoResponse {
             admin,
             start_time: self.start_time,
             end_time: self.end_time,
@@ -319,27 +457,121 @@ impl Ido {
             soft_cap: Uint128::new(self.soft_cap),
             withdrawn: self.withdrawn,
             shared_whitelist: self.shared_whitelist,
-        })
+        }))
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct UserWithdrawal {
     pub amount: u128,
+    /// Only used by withdrawals queued before unbonding epochs.
     pub claim_time: u64,
     pub timestamp: u64,
+    #[serde(default)]
+    pub epoch: Option<u64>,
+    /// Slashing index when the withdrawal was queued.
+    #[serde(default)]
+    pub slashing_index: Option<Decimal>,
 }
 
 impl UserWithdrawal {
-    pub fn to_serialized(&self) -> SerializedWithdrawals {
+    pub fn to_serialized(
+        &self,
+        amount: u128,
+        claim_time: u64,
+        queued: bool,
+    ) -> SerializedWithdrawals {
         SerializedWithdrawals {
-            amount: Uint128::from(self.amount),
-            claim_time: self.claim_time,
+            amount: Uint128::from(amount),
+            claim_time,
             timestamp: self.timestamp,
+            epoch: self.epoch,
+            queued,
         }
     }
 }
 
+/// Last ORAI price stored by `RefreshPrice` and its time-weighted average.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PriceCache {
+    pub price: Decimal,
+    pub twap: Decimal,
+    pub updated_at: u64,
+}
+
+impl PriceCache {
+    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
+        Ok(PRICE_CACHE.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        PRICE_CACHE.save(storage, self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.price.is_zero()
+    }
+
+    /// Stores `price` and moves the average towards the previous price by the share of
+    /// `twap_window` it was held for.
+    pub fn refresh(&mut self, price: Decimal, now: u64, twap_window: u64) {
+        if self.is_empty() || twap_window == 0 {
+            self.twap = price;
+        } else {
+            let held = now.saturating_sub(self.updated_at).min(twap_window);
+            self.twap = self.twap * Decimal::from_ratio(twap_window - held, twap_window)
+                + self.price * Decimal::from_ratio(held, twap_window);
+        }
+        self.price = price;
+        self.updated_at = now;
+    }
+
+    pub fn to_answer(&self) -> QueryResponse {
+        QueryResponse::PriceCache(PriceCacheResponse {
+            price: self.price,
+            twap: self.twap,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+/// Pauses tier deposits after the price sources failed or disagreed, until the admin resets it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub tripped_at: Option<u64>,
+    pub reason: Option<String>,
+}
+
+impl CircuitBreaker {
+    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
+        Ok(CIRCUIT_BREAKER.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        CIRCUIT_BREAKER.save(storage, self)
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped_at.is_some()
+    }
+
+    pub fn assert_closed(&self) -> Result<(), ContractError> {
+        if self.is_tripped() {
+            return Err(ContractError::DepositsPaused {});
+        }
+
+        Ok(())
+    }
+
+    pub fn to_answer(&self) -> QueryResponse {
+        QueryResponse::CircuitBreaker(CircuitBreakerResponse {
+            tripped: self.is_tripped(),
+            tripped_at: self.tripped_at,
+            reason: self.reason.clone(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -392,4 +624,78 @@ mod test {
         assert_eq!(loaded_ido.id(), 1);
         assert_eq!(Ido::len(&storage), Ok(2));
     }
+
+    #[test]
+    fn staking_stats() {
+        let deps = mock_dependencies();
+        let mut storage = deps.storage;
+
+        let mut stats = StakingStats::load(&storage).unwrap();
+        assert_eq!(stats, StakingStats::default());
+
+        stats.add_user(3);
+        stats.add_user(1);
+        stats.add_user(3);
+        assert_eq!(stats.users_per_tier, vec![1, 0, 2]);
+
+        stats.remove_user(3);
+        stats.remove_user(2);
+        stats.remove_user(4);
+        assert_eq!(stats.users_per_tier, vec![1, 0, 1]);
+
+        stats.save(&mut storage).unwrap();
+        assert_eq!(StakingStats::load(&storage), Ok(stats));
+    }
+
+    #[test]
+    fn price_cache() {
+        let deps = mock_dependencies();
+        let mut storage = deps.storage;
+
+        let mut cache = PriceCache::load(&storage).unwrap();
+        assert!(cache.is_empty());
+
+        cache.refresh(Decimal::percent(200), 1000, 100);
+        assert_eq!(cache.twap, Decimal::percent(200));
+        assert_eq!(cache.updated_at, 1000);
+
+        // a new price in the same block does not move the average
+        cache.refresh(Decimal::percent(400), 1000, 100);
+        assert_eq!(cache.price, Decimal::percent(400));
+        assert_eq!(cache.twap, Decimal::percent(200));
+
+        // 4.00 held for a quarter of the window
+        cache.refresh(Decimal::percent(300), 1025, 100);
+        assert_eq!(cache.twap, Decimal::percent(250));
+
+        // 3.00 held for longer than the window
+        cache.refresh(Decimal::percent(100), 2000, 100);
+        assert_eq!(cache.twap, Decimal::percent(300));
+
+        cache.save(&mut storage).unwrap();
+        assert_eq!(PriceCache::load(&storage), Ok(cache));
+    }
+
+    #[test]
+    fn mark_to_market() {
+        let mut user_info = TierUserInfo {
+            tier: 2,
+            orai_deposit: 1_000,
+            ..Default::default()
+        };
+
+        // upgrades and unchanged tiers apply at once
+        assert_eq!(user_info.mark_to_market(1, 100, 50), (1, None));
+        assert_eq!(user_info.mark_to_market(2, 100, 50), (2, None));
+
+        // downgrades wait out the grace period from the first time they are seen
+        assert_eq!(user_info.mark_to_market(4, 100, 50), (2, Some(100)));
+        user_info.below_tier_since = Some(100);
+        assert_eq!(user_info.mark_to_market(3, 149, 50), (2, Some(100)));
+        assert_eq!(user_info.mark_to_market(3, 150, 50), (3, None));
+        assert_eq!(user_info.mark_to_market(1, 120, 50), (1, None));
+
+        user_info.below_tier_since = None;
+        assert_eq!(user_info.mark_to_market(3, 100, 0), (3, None));
+    }
 }
@@ -1,9 +1,11 @@
 mod query {
     use crate::{
-        msg::ContractStatus,
-        state::{self, Config, TIER_USER_INFOS},
+        band::BandProtocol,
+        error::ContractError,
+        msg::{ContractStatus, TierPolicy},
+        state::{self, Config, TierUserInfo},
     };
-    use cosmwasm_std::{DepsMut, StdError, StdResult, Uint128};
+    use cosmwasm_std::{Deps, StdError, StdResult, Uint128};
     use cw721::{AllNftInfoResponse, Cw721QueryMsg, TokensResponse};
     use schemars::JsonSchema;
     // use secret_toolkit_snip721::{
@@ -83,7 +85,7 @@ mod query {
     }
 
     pub fn get_tier_from_nft_contract(
-        deps: &DepsMut,
+        deps: Deps,
         address: &String,
         config: &Config,
         _viewing_key: String,
@@ -98,9 +100,17 @@ mod query {
 
         let tokensresponse: TokensResponse = deps.querier.query_wasm_smart(nft_contract, &msg)?;
 
-        let token_list = tokensresponse.tokens.iter();
+        get_tier_from_nft_tokens(deps, address, config, &tokensresponse.tokens)
+    }
+
+    pub fn get_tier_from_nft_tokens(
+        deps: Deps,
+        address: &String,
+        config: &Config,
+        token_ids: &[String],
+    ) -> StdResult<Option<u8>> {
         let mut result_tier = 5;
-        for token_id in token_list {
+        for token_id in token_ids {
             let nft_contract = config.nft_contract.to_string();
             let msg = Cw721QueryMsg::AllNftInfo {
                 token_id: token_id.clone(),
@@ -142,13 +152,76 @@ mod query {
     //     }
     // }
 
-    pub fn get_tier(deps: &DepsMut, address: String, viewing_key: Option<String>) -> StdResult<u8> {
+    /// Tier the deposit of `user_info` is worth at the current price, after the grace period of
+    /// a mark-to-market policy, along with its value in USD.
+    pub fn marked_tier(
+        config: &Config,
+        band_protocol: &BandProtocol,
+        user_info: &TierUserInfo,
+        grace_period: u64,
+        now: u64,
+    ) -> Result<(u8, Option<u64>, u128), ContractError> {
+        let usd_value = band_protocol.usd_amount(user_info.orai_deposit)?;
+        let live_tier = config.tier_by_deposit(usd_value);
+        let (tier, below_tier_since) = user_info.mark_to_market(live_tier, now, grace_period);
+
+        Ok((tier, below_tier_since, usd_value))
+    }
+
+    /// `user_info` as `SyncTier` would store it, or unchanged when tiers are locked at deposit.
+    pub fn marked_user_info(
+        config: &Config,
+        band_protocol: &BandProtocol,
+        user_info: &TierUserInfo,
+        now: u64,
+    ) -> Result<TierUserInfo, ContractError> {
+        let grace_period = match config.tier_policy {
+            TierPolicy::LockedAtDeposit {} => return Ok(user_info.clone()),
+            TierPolicy::MarkToMarket { grace_period } => grace_period,
+        };
+
+        let (tier, below_tier_since, usd_value) =
+            marked_tier(config, band_protocol, user_info, grace_period, now)?;
+        let mut marked = user_info.clone();
+        if tier != user_info.tier {
+            marked.tier = tier;
+            marked.usd_deposit = config.tier_usd_deposit(tier, usd_value);
+        }
+        marked.below_tier_since = below_tier_since;
+
+        Ok(marked)
+    }
+
+    /// Staking tier of `user_info` under the configured [`TierPolicy`]. A mark-to-market tier
+    /// needs a guarded price quote, so it fails while none can be had rather than keeping a
+    /// tier the deposit may no longer be worth.
+    pub fn stake_tier(
+        deps: Deps,
+        config: &Config,
+        user_info: &TierUserInfo,
+        now: u64,
+    ) -> Result<u8, ContractError> {
+        let grace_period = match config.tier_policy {
+            TierPolicy::LockedAtDeposit {} => return Ok(user_info.get_tier()),
+            TierPolicy::MarkToMarket { grace_period } => grace_period,
+        };
+
+        let band_protocol = BandProtocol::guarded(deps, now)?;
+        let (tier, _, _) = marked_tier(config, &band_protocol, user_info, grace_period, now)?;
+
+        Ok(tier)
+    }
+
+    pub fn get_tier(
+        deps: Deps,
+        address: String,
+        viewing_key: Option<String>,
+        now: u64,
+    ) -> Result<u8, ContractError> {
         let config = Config::load(deps.storage)?;
 
         let from_nft_contract = viewing_key
-            .map(|viewing_key| {
-                get_tier_from_nft_contract(deps.clone(), &address, &config, viewing_key)
-            })
+            .map(|viewing_key| get_tier_from_nft_contract(deps, &address, &config, viewing_key))
             .unwrap_or(Ok(None))?;
 
         // let mut tier = get_tier_from_tier_contract(deps, address, &config)?;
@@ -160,13 +233,11 @@ mod query {
         // };
         let min_tier = config.min_tier();
         let tier_user_info =
-            TIER_USER_INFOS
-                .may_load(deps.storage, address)?
-                .unwrap_or(state::TierUserInfo {
-                    tier: min_tier,
-                    ..Default::default()
-                });
-        let mut tier = tier_user_info.get_tier();
+            TierUserInfo::may_load(deps.storage, &address)?.unwrap_or(state::TierUserInfo {
+                tier: min_tier,
+                ..Default::default()
+            });
+        let mut tier = stake_tier(deps, &config, &tier_user_info, now)?;
         if let Some(nft_tier) = from_nft_contract {
             if nft_tier < tier {
                 tier = nft_tier
@@ -176,7 +247,7 @@ mod query {
         Ok(tier)
     }
 
-    pub fn get_min_tier(deps: &DepsMut, config: &Config) -> StdResult<u8> {
+    pub fn get_min_tier(deps: Deps, config: &Config) -> StdResult<u8> {
         if let config = Config::load(deps.storage)? {
             let min_tier = config.min_tier();
             Ok(min_tier)
@@ -186,94 +257,147 @@ mod query {
     }
 }
 
-#[cfg(test)]
-pub mod manual {
-    use crate::state::Config;
-    use cosmwasm_std::{DepsMut, StdResult};
-    use std::sync::Mutex;
-
-    static TIER: Mutex<u8> = Mutex::new(0);
-    static MIN_TIER: Mutex<u8> = Mutex::new(4);
-
-    pub fn set_tier(tier: u8) {
-        let mut tier_lock = TIER.lock().unwrap();
-        *tier_lock = tier;
-    }
-
-    pub fn set_min_tier(tier: u8) {
-        let mut tier_lock = MIN_TIER.lock().unwrap();
-        *tier_lock = tier;
-    }
-
-    pub fn get_tier(
-        _deps: &DepsMut,
-        _address: String,
-        _viewing_key: Option<String>,
-    ) -> StdResult<u8> {
-        let tier_lock = TIER.lock().unwrap();
-        Ok(*tier_lock)
-    }
-
-    pub fn get_min_tier(_deps: &DepsMut, _config: &Config) -> StdResult<u8> {
-        let tier_lock = MIN_TIER.lock().unwrap();
-        Ok(*tier_lock)
-    }
-
-    pub fn get_tier_from_nft_contract(
-        _deps: &DepsMut,
-        _address: &String,
-        _config: &Config,
-        _viewing_key: String,
-    ) -> StdResult<Option<u8>> {
-        let tier_lock = TIER.lock().unwrap();
-        Ok(Some(*tier_lock))
-    }
-}
-
-#[cfg(not(test))]
-pub use query::get_tier;
-
-#[cfg(not(test))]
 pub use query::get_min_tier;
-
-#[cfg(not(test))]
+pub use query::get_tier;
 pub use query::get_tier_from_nft_contract;
-
-#[cfg(test)]
-pub use manual::get_tier;
-
-#[cfg(test)]
-pub use manual::get_min_tier;
-
-#[cfg(test)]
-pub use manual::get_tier_from_nft_contract;
+pub use query::get_tier_from_nft_tokens;
+pub use query::marked_tier;
+pub use query::marked_user_info;
+pub use query::stake_tier;
 
 #[cfg(test)]
 mod tests {
-    use std::marker::PhantomData;
-
     use cosmwasm_std::{
-        testing::{MockApi, MockQuerier, MockStorage},
-        OwnedDeps,
+        from_json,
+        testing::{mock_dependencies, MockQuerier},
+        to_json_binary, ContractResult, Decimal, SystemResult, WasmQuery,
+    };
+    use cw721::{
+        AllNftInfoResponse, Cw721QueryMsg, NftInfoResponse, OwnerOfResponse, TokensResponse,
     };
 
-    use super::manual::{get_tier, set_tier};
+    use super::query::{Metadata, Trait};
+    use super::*;
+    use crate::msg::{OraiswapContract, PriceSourceConfig, TierPolicy};
+    use crate::state::{Config, TierUserInfo, TIER_USER_INFOS};
+
+    fn mock_nft_contract(querier: &mut MockQuerier, owner: &str, tiers: &[(&str, &str)]) {
+        let owner = owner.to_string();
+        let tiers: Vec<(String, String)> = tiers
+            .iter()
+            .map(|(id, tier)| (id.to_string(), tier.to_string()))
+            .collect();
+
+        querier.update_wasm(move |request| {
+            let WasmQuery::Smart { msg, .. } = request else {
+                panic!("Unexpected query");
+            };
+            let response = match from_json(msg).unwrap() {
+                Cw721QueryMsg::Tokens { .. } => to_json_binary(&TokensResponse {
+                    tokens: tiers.iter().map(|(id, _)| id.clone()).collect(),
+                }),
+                Cw721QueryMsg::AllNftInfo { token_id, .. } => {
+                    let (_, tier) = tiers.iter().find(|(id, _)| *id == token_id).unwrap();
+                    to_json_binary(&AllNftInfoResponse {
+                        access: OwnerOfResponse {
+                            owner: owner.clone(),
+                            approvals: vec![],
+                        },
+                        info: NftInfoResponse {
+                            token_uri: None,
+                            extension: Metadata {
+                                attributes: Some(vec![Trait {
+                                    trait_type: "Tier".to_string(),
+                                    value: tier.clone(),
+                                }]),
+                            },
+                        },
+                    })
+                }
+                _ => panic!("Unexpected query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        });
+    }
 
     #[test]
-    fn manual_tier() {
-        let mut deps = OwnedDeps {
-            storage: MockStorage::default(),
-            api: MockApi::default(),
-            querier: MockQuerier::default(),
-            custom_query_type: PhantomData::default(),
+    fn tier() {
+        let mut deps = mock_dependencies();
+        let config = Config {
+            admin: "admin".to_string(),
+            status: 0,
+            nft_contract: "nft".to_string(),
+            lock_periods: vec![50, 40, 30, 20, 10],
+            min_tier: 5,
+            validators: vec![],
+            usd_deposits: vec![400, 300, 200, 100],
+            oraiswap_contract: OraiswapContract {
+                orai_contract: "router".to_string(),
+                usdt_contract: "usdt".to_string(),
+                route: vec![],
+                offer_amount: None,
+            },
+            price_source: PriceSourceConfig::default(),
+            price_guard: None,
+            price_fallbacks: vec![],
+            max_price_divergence_bps: None,
+            tier_policy: TierPolicy::default(),
+            tier_locks: Default::default(),
+            protocol_fee: None,
+            unbonding: Default::default(),
         };
-        let address = "address".to_string();
-        let tier = get_tier(&deps.as_mut(), address.clone(), None).unwrap();
+        config.save(&mut deps.storage).unwrap();
 
-        for i in 1..=4 {
-            set_tier(i);
-            assert_eq!(get_tier(&deps.as_mut(), address.clone(), None), Ok(i));
+        let address = "address".to_string();
+        assert_eq!(get_tier(deps.as_ref(), address.clone(), None, 0), Ok(5));
+
+        let user_info = TierUserInfo {
+            tier: 3,
+            timestamp: 0,
+            usd_deposit: 200,
+            orai_deposit: 2000,
+            below_tier_since: None,
+            slashing_index: None,
+        };
+        TIER_USER_INFOS
+            .save(&mut deps.storage, address.clone(), &user_info)
+            .unwrap();
+        assert_eq!(get_tier(deps.as_ref(), address.clone(), None, 0), Ok(3));
+
+        // the best of the staking and NFT tiers wins
+        mock_nft_contract(&mut deps.querier, &address, &[("1", "4"), ("2", "2")]);
+        assert_eq!(
+            get_tier(deps.as_ref(), address.clone(), Some(String::new()), 0),
+            Ok(2)
+        );
+
+        mock_nft_contract(&mut deps.querier, &address, &[("1", "4")]);
+        assert_eq!(
+            get_tier(deps.as_ref(), address.clone(), Some(String::new()), 0),
+            Ok(3)
+        );
+
+        // tokens owned by someone else are ignored
+        mock_nft_contract(&mut deps.querier, "other", &[("1", "1")]);
+        assert_eq!(
+            get_tier(deps.as_ref(), address.clone(), Some(String::new()), 0),
+            Ok(3)
+        );
+        let tokens = vec!["1".to_string()];
+        assert_eq!(
+            get_tier_from_nft_tokens(deps.as_ref(), &address, &config, &tokens),
+            Ok(Some(5))
+        );
+
+        // mark-to-market re-derives the staking tier from the ORAI deposit
+        let mut config = config;
+        config.tier_policy = TierPolicy::MarkToMarket { grace_period: 0 };
+        for (usd_per_orai, tier) in [(50_000u128, 4), (200_000, 1)] {
+            config.price_source = PriceSourceConfig::FixedPrice {
+                price: Decimal::from_ratio(usd_per_orai, 1u128),
+            };
+            config.save(&mut deps.storage).unwrap();
+            assert_eq!(get_tier(deps.as_ref(), address.clone(), None, 0), Ok(tier));
         }
-        set_tier(tier);
     }
 }
@@ -0,0 +1,305 @@
+//! Unbonding epochs that batch withdrawals into one undelegation.
+//!
+//! The chain limits how many unbonding entries a delegator may have with a validator, so
+//! withdrawals are not undelegated one by one. They add up in the open epoch until the
+//! `ProcessUnbondingEpoch` crank undelegates the total, at most once per `epoch_duration`.
+//! A withdrawal can be claimed once its epoch finished unbonding.
+//!
+//! The unbonding time comes from `Config` or, in `from_chain` mode, from the staking params of
+//! the chain. An epoch keeps the one it was undelegated with.
+use std::convert::TryFrom;
+
+use cosmwasm_std::{
+    to_json_vec, Binary, ContractResult, Decimal, QuerierWrapper, QueryRequest, StdError,
+    StdResult, Storage, SystemResult,
+};
+use cw_storage_plus::{Item, Map};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::msg::{Unbonding, UnbondingEpochResponse};
+use crate::slashing;
+use crate::state::UserWithdrawal;
+
+pub const OPEN_EPOCH: Item<u64> = Item::new("open_unbonding_epoch");
+pub const UNBONDING_EPOCHS: Map<u64, UnbondingEpoch> = Map::new("unbonding_epochs");
+
+pub const STAKING_PARAMS_PATH: &str = "/cosmos.staking.v1beta1.Query/Params";
+
+/// The part of `cosmos.staking.v1beta1.QueryParamsResponse` the launchpad reads.
+#[derive(Clone, PartialEq, Message)]
+pub struct StakingParamsResponse {
+    #[prost(message, optional, tag = "1")]
+    pub params: Option<StakingParams>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StakingParams {
+    #[prost(message, optional, tag = "1")]
+    pub unbonding_time: Option<ProtoDuration>,
+}
+
+/// `google.protobuf.Duration`
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoDuration {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+/// Unbonding time of the chain's staking module, rounded up to whole seconds.
+pub fn chain_unbonding_time(querier: &QuerierWrapper) -> StdResult<u64> {
+    let request: QueryRequest<cosmwasm_std::Empty> = QueryRequest::Stargate {
+        path: STAKING_PARAMS_PATH.to_string(),
+        data: Binary::default(),
+    };
+    let raw = match querier.raw_query(&to_json_vec(&request)?) {
+        SystemResult::Ok(ContractResult::Ok(raw)) => raw,
+        SystemResult::Ok(ContractResult::Err(err)) => {
+            return Err(StdError::generic_err(format!(
+                "Staking params query: {}",
+                err
+            )))
+        }
+        SystemResult::Err(err) => {
+            return Err(StdError::generic_err(format!(
+                "Staking params query: {}",
+                err
+            )))
+        }
+    };
+
+    let duration = StakingParamsResponse::decode(raw.as_slice())
+        .map_err(|err| StdError::parse_err("StakingParamsResponse", err))?
+        .params
+        .and_then(|params| params.unbonding_time)
+        .ok_or_else(|| StdError::not_found("unbonding_time"))?;
+    let seconds = u64::try_from(duration.seconds)
+        .map_err(|_| StdError::generic_err("Negative unbonding time"))?;
+
+    Ok(seconds + u64::from(duration.nanos > 0))
+}
+
+/// `unbonding` with the unbonding time of the chain in `from_chain` mode.
+pub fn current(querier: &QuerierWrapper, unbonding: &Unbonding) -> StdResult<Unbonding> {
+    let mut unbonding = unbonding.clone();
+    if unbonding.from_chain {
+        unbonding.unbonding_time = chain_unbonding_time(querier)?;
+    }
+
+    Ok(unbonding)
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnbondingEpoch {
+    pub id: u64,
+    /// uORAI withdrawn during the epoch.
+    pub amount: u128,
+    /// When the previous epoch was undelegated.
+    pub opened_at: u64,
+    pub unbonded_at: Option<u64>,
+    /// Unbonding time the epoch was undelegated with.
+    #[serde(default)]
+    pub unbonding_time: Option<u64>,
+    /// Slashing index when the epoch was undelegated, lowered by the slashes it takes while
+    /// unbonding.
+    #[serde(default)]
+    pub slashing_index: Option<Decimal>,
+}
+
+impl UnbondingEpoch {
+    pub fn load(storage: &dyn Storage, id: u64) -> StdResult<Self> {
+        Ok(UNBONDING_EPOCHS
+            .may_load(storage, id)?
+            .unwrap_or(UnbondingEpoch {
+                id,
+                ..Default::default()
+            }))
+    }
+
+    /// The epoch collecting withdrawals right now.
+    pub fn open(storage: &dyn Storage) -> StdResult<Self> {
+        let id = OPEN_EPOCH.may_load(storage)?.unwrap_or_default();
+        Self::load(storage, id)
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        UNBONDING_EPOCHS.save(storage, self.id, self)
+    }
+
+    /// Earliest time the crank may undelegate the epoch.
+    pub fn processable_at(&self, unbonding: &Unbonding) -> u64 {
+        self.opened_at.saturating_add(unbonding.epoch_duration)
+    }
+
+    /// Unbonding time the epoch's uORAI takes, the current one until it is undelegated.
+    pub fn unbonding_time(&self, unbonding: &Unbonding) -> u64 {
+        self.unbonding_time.unwrap_or(unbonding.unbonding_time)
+    }
+
+    /// When the epoch's uORAI can be claimed. Until it is undelegated, this assumes the crank
+    /// runs as soon as it may.
+    pub fn claim_time(&self, unbonding: &Unbonding, now: u64) -> u64 {
+        self.unbonded_at
+            .unwrap_or_else(|| self.processable_at(unbonding).max(now))
+            .saturating_add(self.unbonding_time(unbonding))
+    }
+
+    /// Whether the epoch is undelegated and its uORAI is not back yet.
+    pub fn is_unbonding(&self, now: u64) -> bool {
+        self.unbonded_at.is_some_and(|unbonded_at| {
+            unbonded_at.saturating_add(self.unbonding_time.unwrap_or_default()) > now
+        })
+    }
+
+    /// Closes the epoch at `now` and opens the next one. `unbonding` has to be [`current`].
+    pub fn process(
+        &mut self,
+        storage: &mut dyn Storage,
+        unbonding: &Unbonding,
+        now: u64,
+    ) -> Result<(), ContractError> {
+        let processable_at = self.processable_at(unbonding);
+        if now < processable_at {
+            return Err(ContractError::EpochNotOver { processable_at });
+        }
+        if self.amount == 0 {
+            return Err(ContractError::NothingToWithdraw {});
+        }
+
+        self.unbonded_at = Some(now);
+        self.unbonding_time = Some(unbonding.unbonding_time);
+        self.slashing_index = Some(slashing::index(storage)?);
+        self.save(storage)?;
+
+        let next = UnbondingEpoch {
+            id: self.id.checked_add(1).ok_or(ContractError::Overflow {})?,
+            opened_at: now,
+            ..Default::default()
+        };
+        next.save(storage)?;
+        OPEN_EPOCH.save(storage, &next.id)?;
+
+        Ok(())
+    }
+
+    pub fn to_answer(&self, unbonding: &Unbonding, now: u64) -> UnbondingEpochResponse {
+        UnbondingEpochResponse {
+            id: self.id,
+            amount: self.amount.into(),
+            opened_at: self.opened_at,
+            processable_at: self.processable_at(unbonding),
+            unbonded_at: self.unbonded_at,
+            unbonding_time: self.unbonding_time(unbonding),
+            claim_time: self.claim_time(unbonding, now),
+        }
+    }
+}
+
+/// Adds `amount` uORAI to the open epoch and returns it.
+pub fn queue(storage: &mut dyn Storage, amount: u128) -> Result<UnbondingEpoch, ContractError> {
+    let mut epoch = UnbondingEpoch::open(storage)?;
+    epoch.amount = epoch
+        .amount
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow {})?;
+    epoch.save(storage)?;
+
+    Ok(epoch)
+}
+
+/// A withdrawal as its epoch stands.
+pub struct Claimable {
+    /// uORAI left after the slashes the epoch took before it finished unbonding.
+    pub amount: u128,
+    pub claim_time: u64,
+    /// Whether the epoch is undelegated already.
+    pub unbonded: bool,
+}
+
+pub fn claimable(
+    storage: &dyn Storage,
+    unbonding: &Unbonding,
+    withdrawal: &UserWithdrawal,
+    now: u64,
+) -> StdResult<Claimable> {
+    let id = match withdrawal.epoch {
+        // queued before epochs, undelegated right away
+        None => {
+            return Ok(Claimable {
+                amount: withdrawal.amount,
+                claim_time: withdrawal.claim_time,
+                unbonded: true,
+            })
+        }
+        Some(id) => id,
+    };
+
+    let epoch = UnbondingEpoch::load(storage, id)?;
+    let index = match (epoch.slashing_index, epoch.unbonded_at) {
+        (Some(index), _) => index,
+        // undelegated before any slash was recorded
+        (None, Some(_)) => Decimal::one(),
+        (None, None) => slashing::index(storage)?,
+    };
+    Ok(Claimable {
+        amount: slashing::scale(withdrawal.amount, withdrawal.slashing_index, index),
+        claim_time: epoch.claim_time(unbonding, now),
+        unbonded: epoch.unbonded_at.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn epochs() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        let mut unbonding = Unbonding {
+            epoch_duration: 100,
+            unbonding_time: 1_000,
+            from_chain: false,
+        };
+
+        // the first epoch opened at genesis, so it may be processed right away
+        let mut epoch = UnbondingEpoch::open(storage).unwrap();
+        assert_eq!(
+            epoch.process(storage, &unbonding, 1_000).unwrap_err(),
+            ContractError::NothingToWithdraw {}
+        );
+        queue(storage, 10).unwrap();
+        let mut epoch = queue(storage, 5).unwrap();
+        assert_eq!(epoch.amount, 15);
+        assert_eq!(epoch.claim_time(&unbonding, 1_000), 1_000 + 1_000);
+        epoch.process(storage, &unbonding, 1_000).unwrap();
+        assert_eq!(
+            UnbondingEpoch::load(storage, 0).unwrap().unbonded_at,
+            Some(1_000)
+        );
+
+        // the next one waits for the epoch duration
+        let mut epoch = queue(storage, 7).unwrap();
+        assert_eq!(epoch.id, 1);
+        assert_eq!(epoch.claim_time(&unbonding, 1_010), 1_100 + 1_000);
+        assert_eq!(
+            epoch.process(storage, &unbonding, 1_099).unwrap_err(),
+            ContractError::EpochNotOver {
+                processable_at: 1_100
+            }
+        );
+        epoch.process(storage, &unbonding, 1_400).unwrap();
+        assert_eq!(epoch.claim_time(&unbonding, 1_500), 1_400 + 1_000);
+
+        // an undelegated epoch keeps its unbonding time
+        unbonding.unbonding_time = 500;
+        assert_eq!(epoch.claim_time(&unbonding, 1_500), 1_400 + 1_000);
+        let epoch = UnbondingEpoch::open(storage).unwrap();
+        assert_eq!(epoch.claim_time(&unbonding, 1_500), 1_500 + 500);
+        assert_eq!(UnbondingEpoch::open(storage).unwrap().id, 2);
+    }
+}
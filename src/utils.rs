@@ -1,39 +1,44 @@
 use crate::contract::ORAI;
 use crate::{
+    error::ContractError,
     msg::ContractStatus,
     state::{Config, Ido, CONFIG_KEY, WHITELIST},
 };
-use cosmwasm_std::{Addr, Coin, DepsMut, Env, FullDelegation, StdError, StdResult, Storage};
+use cosmwasm_std::{Addr, Coin, DepsMut, Env, FullDelegation, StdResult, Storage};
 use serde::Deserialize;
 
-pub fn assert_contract_active(storage: &dyn Storage) -> StdResult<()> {
+pub fn assert_contract_active(storage: &dyn Storage) -> Result<(), ContractError> {
     let config = Config::load(storage)?;
     let active_status = ContractStatus::Active as u8;
 
     if config.status != active_status {
-        return Err(StdError::generic_err("Contract is not active"));
+        return Err(ContractError::ContractNotActive {});
     }
 
     Ok(())
 }
 
-pub fn assert_admin(deps: &DepsMut, address: &String) -> StdResult<()> {
+pub fn assert_admin(deps: &DepsMut, address: &String) -> Result<(), ContractError> {
     let canonical_admin = address.clone();
     let config = CONFIG_KEY.load(deps.storage)?;
 
     if config.admin != canonical_admin {
-        return Err(StdError::generic_err("Unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     Ok(())
 }
 
-pub fn assert_ido_admin(deps: &DepsMut, address: &String, ido_id: u32) -> StdResult<()> {
+pub fn assert_ido_admin(
+    deps: &DepsMut,
+    address: &String,
+    ido_id: u32,
+) -> Result<(), ContractError> {
     let canonical_admin = address.clone();
     let ido = Ido::load(deps.storage, ido_id)?;
 
     if ido.admin != canonical_admin {
-        return Err(StdError::generic_err("Unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     Ok(())
@@ -53,15 +58,19 @@ pub fn in_whitelist(storage: &dyn Storage, address: &String, ido_id: u32) -> Std
     }
 }
 
-pub fn sent_funds(coins: &[Coin]) -> StdResult<u128> {
+pub fn sent_funds(coins: &[Coin]) -> Result<u128, ContractError> {
     let mut amount: u128 = 0;
 
     for coin in coins {
-        if coin.denom != "orai" {
-            return Err(StdError::generic_err("Unsopported token"));
+        if coin.denom != ORAI {
+            return Err(ContractError::UnsupportedToken {
+                denom: coin.denom.clone(),
+            });
         }
 
-        amount = amount.checked_add(coin.amount.u128()).unwrap();
+        amount = amount
+            .checked_add(coin.amount.u128())
+            .ok_or(ContractError::Overflow {})?;
     }
 
     Ok(amount)
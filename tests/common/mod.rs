@@ -0,0 +1,640 @@
+//! Shared `cw-multi-test` setup: the launchpad wired to mock cw20, cw721 and
+//! Oraiswap router contracts, to the staking module and to its params query.
+#![allow(dead_code)]
+
+use cosmwasm_std::{
+    coins, from_json,
+    testing::{mock_env, MockApi, MockStorage},
+    Addr, Api, Binary, BlockInfo, Decimal, Empty, Querier, StdResult, Storage, Uint128, Validator,
+};
+use cw_multi_test::error::{bail, AnyResult};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, ContractWrapper, DistributionKeeper, Executor,
+    FailingModule, GovFailingModule, IbcFailingModule, StakeKeeper, StakingInfo, Stargate,
+    WasmKeeper,
+};
+use cw_template::contract::UNBOUND_LATENCY;
+use cw_template::error::ContractError;
+use cw_template::msg::{
+    ExecuteMsg, ExecuteResponse, InstantiateMsg, OraiswapContract, PaymentMethod, QueryMsg,
+    QueryResponse, ValidatorWithWeight, Whitelist,
+};
+use cw_template::unbonding::{
+    ProtoDuration, StakingParams, StakingParamsResponse, STAKING_PARAMS_PATH,
+};
+
+pub const ADMIN: &str = "admin";
+pub const USER1: &str = "user1";
+pub const USER2: &str = "user2";
+pub const USER3: &str = "user3";
+pub const VALIDATOR1: &str = "validator1";
+pub const VALIDATOR2: &str = "validator2";
+pub const VALIDATOR3: &str = "validator3";
+
+pub const INITIAL_ORAI: u128 = 1_000_000 * ONE_USD_IN_ORAI;
+pub const USD_DEPOSITS: [u128; 4] = [4000, 3000, 2000, 1000];
+pub const LOCK_PERIODS: [u64; 5] = [50, 40, 30, 20, 10];
+// uusdt the router quotes for one ORAI, i.e. 10 USD per ORAI.
+pub const ROUTER_RATE: u128 = 10_000_000;
+// uorai worth one USD at `ROUTER_RATE`.
+pub const ONE_USD_IN_ORAI: u128 = 100_000;
+
+pub fn orai(usd: u128) -> u128 {
+    usd * ONE_USD_IN_ORAI
+}
+
+pub mod mock_cw20 {
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+        Uint128,
+    };
+    use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+    use cw_multi_test::{Contract, ContractWrapper};
+    use cw_storage_plus::Map;
+    use serde::{Deserialize, Serialize};
+
+    const BALANCES: Map<&str, Uint128> = Map::new("balances");
+    const ALLOWANCES: Map<(&str, &str), Uint128> = Map::new("allowances");
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct InstantiateMsg {
+        pub balances: Vec<(String, Uint128)>,
+    }
+
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        for (address, amount) in msg.balances {
+            BALANCES.save(deps.storage, &address, &amount)?;
+        }
+        Ok(Response::new())
+    }
+
+    fn transfer(deps: DepsMut, from: &str, to: &str, amount: Uint128) -> StdResult<()> {
+        let balance = BALANCES.may_load(deps.storage, from)?.unwrap_or_default();
+        BALANCES.save(deps.storage, from, &balance.checked_sub(amount)?)?;
+        let balance = BALANCES.may_load(deps.storage, to)?.unwrap_or_default();
+        BALANCES.save(deps.storage, to, &balance.checked_add(amount)?)?;
+        Ok(())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: Cw20ExecuteMsg,
+    ) -> StdResult<Response> {
+        let sender = info.sender.as_str();
+        match msg {
+            Cw20ExecuteMsg::Transfer { recipient, amount } => {
+                transfer(deps, sender, &recipient, amount)?;
+            }
+            Cw20ExecuteMsg::TransferFrom {
+                owner,
+                recipient,
+                amount,
+            } => {
+                let allowance = ALLOWANCES
+                    .may_load(deps.storage, (&owner, sender))?
+                    .unwrap_or_default();
+                ALLOWANCES.save(
+                    deps.storage,
+                    (&owner, sender),
+                    &allowance.checked_sub(amount)?,
+                )?;
+                transfer(deps, &owner, &recipient, amount)?;
+            }
+            Cw20ExecuteMsg::IncreaseAllowance {
+                spender, amount, ..
+            } => {
+                let allowance = ALLOWANCES
+                    .may_load(deps.storage, (sender, &spender))?
+                    .unwrap_or_default();
+                ALLOWANCES.save(
+                    deps.storage,
+                    (sender, &spender),
+                    &allowance.checked_add(amount)?,
+                )?;
+            }
+            _ => return Err(StdError::generic_err("Unsupported message")),
+        }
+        Ok(Response::new())
+    }
+
+    fn query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw20QueryMsg::Balance { address } => to_json_binary(&BalanceResponse {
+                balance: BALANCES
+                    .may_load(deps.storage, &address)?
+                    .unwrap_or_default(),
+            }),
+            _ => Err(StdError::generic_err("Unsupported query")),
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<cosmwasm_std::Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+pub mod mock_cw721 {
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError,
+        StdResult,
+    };
+    use cw721::{
+        AllNftInfoResponse, Cw721QueryMsg, NftInfoResponse, OwnerOfResponse, TokensResponse,
+    };
+    use cw_multi_test::{Contract, ContractWrapper};
+    use cw_storage_plus::Map;
+    use serde::{Deserialize, Serialize};
+
+    // token id -> (owner, tier)
+    const TOKENS: Map<&str, (String, String)> = Map::new("tokens");
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct InstantiateMsg {
+        pub tokens: Vec<Token>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Token {
+        pub token_id: String,
+        pub owner: String,
+        pub tier: String,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Trait {
+        pub trait_type: String,
+        pub value: String,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Metadata {
+        pub attributes: Option<Vec<Trait>>,
+    }
+
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        for token in msg.tokens {
+            TOKENS.save(deps.storage, &token.token_id, &(token.owner, token.tier))?;
+        }
+        Ok(Response::new())
+    }
+
+    fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+        Err(StdError::generic_err("Unsupported message"))
+    }
+
+    fn query(deps: Deps, _env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw721QueryMsg::Tokens { owner, .. } => {
+                let tokens = TOKENS
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .filter_map(|item| match item {
+                        Ok((token_id, (token_owner, _))) if token_owner == owner => {
+                            Some(Ok(token_id))
+                        }
+                        Ok(_) => None,
+                        Err(err) => Some(Err(err)),
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                to_json_binary(&TokensResponse { tokens })
+            }
+            Cw721QueryMsg::AllNftInfo { token_id, .. } => {
+                let (owner, tier) = TOKENS.load(deps.storage, &token_id)?;
+                to_json_binary(&AllNftInfoResponse {
+                    access: OwnerOfResponse {
+                        owner,
+                        approvals: vec![],
+                    },
+                    info: NftInfoResponse {
+                        token_uri: None,
+                        extension: Metadata {
+                            attributes: Some(vec![Trait {
+                                trait_type: "tier".to_string(),
+                                value: tier,
+                            }]),
+                        },
+                    },
+                })
+            }
+            _ => Err(StdError::generic_err("Unsupported query")),
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+pub mod mock_router {
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    };
+    use cw_multi_test::{Contract, ContractWrapper};
+    use cw_storage_plus::Item;
+    use cw_template::band::SwapContractMessage;
+    use serde::{Deserialize, Serialize};
+
+    // uusdt returned for one ORAI along any route.
+    const RATE: Item<u128> = Item::new("rate");
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct InstantiateMsg {
+        pub rate: u128,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ExecuteMsg {
+        SetRate { rate: u128 },
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Amount {
+        pub amount: u128,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct SimulateResponse {
+        pub data: Amount,
+    }
+
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        RATE.save(deps.storage, &msg.rate)?;
+        Ok(Response::new())
+    }
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            ExecuteMsg::SetRate { rate } => RATE.save(deps.storage, &rate)?,
+        }
+        Ok(Response::new())
+    }
+
+    fn query(deps: Deps, _env: Env, msg: SwapContractMessage) -> StdResult<Binary> {
+        let offer_amount = msg.simulate_swap_operations.offer_amount;
+        let amount = offer_amount * RATE.load(deps.storage)? / 1_000_000;
+        to_json_binary(&SimulateResponse {
+            data: Amount { amount },
+        })
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+pub fn launchpad_contract() -> Box<dyn cw_multi_test::Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw_template::contract::execute,
+        cw_template::contract::instantiate,
+        cw_template::contract::query,
+    ))
+}
+
+// Unbonding time of the mocked staking module.
+pub const CHAIN_UNBONDING_TIME: u64 = UNBOUND_LATENCY;
+
+/// Answers the staking params query, which `cw-multi-test` leaves to the app.
+pub struct StakingParamsQuery;
+
+impl Stargate for StakingParamsQuery {
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        path: String,
+        _data: Binary,
+    ) -> AnyResult<Binary> {
+        if path != STAKING_PARAMS_PATH {
+            bail!("Unexpected stargate query: path = {:?}", path);
+        }
+        let response = StakingParamsResponse {
+            params: Some(StakingParams {
+                unbonding_time: Some(ProtoDuration {
+                    seconds: CHAIN_UNBONDING_TIME as i64,
+                    nanos: 0,
+                }),
+            }),
+        };
+        Ok(Binary::from(prost::Message::encode_to_vec(&response)))
+    }
+}
+
+pub type LaunchpadApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcFailingModule,
+    GovFailingModule,
+    StakingParamsQuery,
+>;
+
+pub struct Suite {
+    pub app: LaunchpadApp,
+    pub launchpad: Addr,
+    pub token: Addr,
+    pub payment_token: Addr,
+    pub nft: Addr,
+    pub router: Addr,
+}
+
+impl Suite {
+    pub fn new() -> Self {
+        let mut app =
+            AppBuilder::new()
+                .with_stargate(StakingParamsQuery)
+                .build(|router, api, storage| {
+                    let block = mock_env().block;
+                    router
+                        .staking
+                        .setup(
+                            storage,
+                            StakingInfo {
+                                bonded_denom: "orai".to_string(),
+                                unbonding_time: CHAIN_UNBONDING_TIME,
+                                apr: Decimal::percent(10),
+                            },
+                        )
+                        .unwrap();
+                    for validator in [VALIDATOR1, VALIDATOR2, VALIDATOR3] {
+                        let validator = Validator {
+                            address: validator.to_string(),
+                            commission: Decimal::zero(),
+                            max_commission: Decimal::percent(10),
+                            max_change_rate: Decimal::percent(1),
+                        };
+                        router
+                            .staking
+                            .add_validator(api, storage, &block, validator)
+                            .unwrap();
+                    }
+                    for user in [ADMIN, USER1, USER2, USER3] {
+                        router
+                            .bank
+                            .init_balance(
+                                storage,
+                                &Addr::unchecked(user),
+                                coins(INITIAL_ORAI, "orai"),
+                            )
+                            .unwrap();
+                    }
+                });
+
+        let admin = Addr::unchecked(ADMIN);
+        let cw20_id = app.store_code(mock_cw20::contract());
+        let token = app
+            .instantiate_contract(
+                cw20_id,
+                admin.clone(),
+                &mock_cw20::InstantiateMsg {
+                    balances: vec![(ADMIN.to_string(), Uint128::new(1_000_000))],
+                },
+                &[],
+                "token",
+                None,
+            )
+            .unwrap();
+        let payment_token = app
+            .instantiate_contract(
+                cw20_id,
+                admin.clone(),
+                &mock_cw20::InstantiateMsg {
+                    balances: [USER1, USER2, USER3]
+                        .iter()
+                        .map(|user| (user.to_string(), Uint128::new(1_000_000)))
+                        .collect(),
+                },
+                &[],
+                "payment token",
+                None,
+            )
+            .unwrap();
+
+        let nft_id = app.store_code(mock_cw721::contract());
+        let nft = app
+            .instantiate_contract(
+                nft_id,
+                admin.clone(),
+                &mock_cw721::InstantiateMsg {
+                    tokens: vec![mock_cw721::Token {
+                        token_id: "1".to_string(),
+                        owner: USER2.to_string(),
+                        tier: "2".to_string(),
+                    }],
+                },
+                &[],
+                "nft",
+                None,
+            )
+            .unwrap();
+
+        let router_id = app.store_code(mock_router::contract());
+        let router = app
+            .instantiate_contract(
+                router_id,
+                admin.clone(),
+                &mock_router::InstantiateMsg { rate: ROUTER_RATE },
+                &[],
+                "router",
+                None,
+            )
+            .unwrap();
+
+        let launchpad_id = app.store_code(launchpad_contract());
+        let launchpad = app
+            .instantiate_contract(
+                launchpad_id,
+                admin,
+                &InstantiateMsg {
+                    admin: None,
+                    lock_periods: LOCK_PERIODS.to_vec(),
+                    nft_contract: nft.to_string(),
+                    validators: vec![
+                        ValidatorWithWeight {
+                            address: VALIDATOR1.to_string(),
+                            weight: 6_000,
+                        },
+                        ValidatorWithWeight {
+                            address: VALIDATOR2.to_string(),
+                            weight: 4_000,
+                        },
+                    ],
+                    deposits: USD_DEPOSITS.iter().map(|d| Uint128::new(*d)).collect(),
+                    oraiswap_contract: OraiswapContract {
+                        orai_contract: router.to_string(),
+                        usdt_contract: "usdt".to_string(),
+                        route: vec![],
+                        offer_amount: None,
+                    },
+                    price_source: None,
+                    price_guard: None,
+                    tier_policy: None,
+                    tier_locks: None,
+                    protocol_fee: None,
+                    unbonding: None,
+                },
+                &[],
+                "launchpad",
+                None,
+            )
+            .unwrap();
+
+        Suite {
+            app,
+            launchpad,
+            token,
+            payment_token,
+            nft,
+            router,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.app.block_info().time.seconds()
+    }
+
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.time = block.time.plus_seconds(seconds);
+            block.height += seconds / 5;
+        });
+        // the staking module pays out matured unbondings when the next block starts
+        self.app.update_block(|block| block.height += 1);
+    }
+
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        msg: ExecuteMsg,
+        funds: &[cosmwasm_std::Coin],
+    ) -> Result<ExecuteResponse, ContractError> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.launchpad.clone(), &msg, funds)
+            .map(|response: AppResponse| from_json(response.data.unwrap()).unwrap())
+            .map_err(|err| match err.downcast() {
+                Ok(err) => err,
+                Err(err) => panic!("{:?}", err),
+            })
+    }
+
+    pub fn query(&self, msg: QueryMsg) -> QueryResponse {
+        self.try_query(msg).unwrap()
+    }
+
+    pub fn try_query(&self, msg: QueryMsg) -> StdResult<QueryResponse> {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.launchpad.clone(), &msg)
+    }
+
+    pub fn approve(&mut self, owner: &str, token: &Addr, amount: u128) {
+        let msg = cw20::Cw20ExecuteMsg::IncreaseAllowance {
+            spender: self.launchpad.to_string(),
+            amount: Uint128::new(amount),
+            expires: None,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(owner), token.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    /// Sets the uusdt the mock router quotes for one ORAI.
+    pub fn set_router_rate(&mut self, rate: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                self.router.clone(),
+                &mock_router::ExecuteMsg::SetRate { rate },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn token_balance(&self, token: &Addr, address: &str) -> u128 {
+        let response: cw20::BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                token.clone(),
+                &cw20::Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.balance.u128()
+    }
+
+    pub fn orai_balance(&self, address: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(address, "orai")
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    pub fn delegation(&self, validator: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_delegation(self.launchpad.clone(), validator)
+            .unwrap()
+            .map(|delegation| delegation.amount.amount.u128())
+            .unwrap_or_default()
+    }
+
+    /// Starts an IDO of 10_000 tokens at 10 tokens per payment unit that opens in 100 seconds
+    /// and runs for 1_000 seconds.
+    pub fn start_ido(&mut self, payment: PaymentMethod, whitelist: Whitelist) -> u32 {
+        let token = self.token.clone();
+        self.approve(ADMIN, &token, 10_000);
+
+        let start_time = self.now() + 100;
+        let msg = ExecuteMsg::StartIdo {
+            start_time,
+            end_time: start_time + 1_000,
+            token_contract: self.token.to_string(),
+            price: Uint128::new(10),
+            soft_cap: Uint128::new(5_000),
+            payment,
+            total_amount: Uint128::new(10_000),
+            tokens_per_tier: [4_000, 3_000, 2_000, 1_000, 1_000]
+                .iter()
+                .map(|amount| Uint128::new(*amount))
+                .collect(),
+            padding: None,
+            whitelist,
+        };
+
+        match self.execute(ADMIN, msg, &[]).unwrap() {
+            ExecuteResponse::StartIdo { ido_id, .. } => ido_id,
+            _ => unreachable!(),
+        }
+    }
+}
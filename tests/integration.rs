@@ -0,0 +1,2400 @@
+mod common;
+
+use common::*;
+use cosmwasm_std::{coins, Addr, Decimal, Uint128};
+use cw_multi_test::{StakingSudo, SudoMsg};
+use cw_template::band::{AssetInfo, Operation, OraiSwap};
+use cw_template::contract::UNBOUND_LATENCY;
+use cw_template::error::ContractError;
+use cw_template::msg::{
+    BuyRejection, EarlyExitPolicy, ExecuteMsg, ExecuteResponse, PaymentMethod, PenaltyRecipient,
+    PriceGuard, PriceSourceConfig, ProtocolFee, QueryMsg, QueryResponse, ResponseStatus,
+    SimulateBuyResponse, SimulateDepositResponse, TierInfoResponse, TierLocks, TierPolicy,
+    Unbonding, UnbondingEpochResponse, ValidatorWithWeight, Whitelist,
+};
+
+fn buy(ido_id: u32, amount: u128) -> ExecuteMsg {
+    ExecuteMsg::BuyTokens {
+        ido_id,
+        amount: Uint128::new(amount),
+        viewing_key: None,
+        padding: None,
+    }
+}
+
+fn recv(ido_id: u32) -> ExecuteMsg {
+    ExecuteMsg::RecvTokens {
+        ido_id,
+        start: None,
+        limit: None,
+        purchase_indices: None,
+        padding: None,
+    }
+}
+
+fn withdraw(ido_id: u32) -> ExecuteMsg {
+    ExecuteMsg::Withdraw {
+        ido_id,
+        padding: None,
+    }
+}
+
+fn deposit() -> ExecuteMsg {
+    ExecuteMsg::Deposit { padding: None }
+}
+
+fn process_unbonding_epoch() -> ExecuteMsg {
+    ExecuteMsg::ProcessUnbondingEpoch { padding: None }
+}
+
+fn withdraw_from_tier(amount: Option<u128>) -> ExecuteMsg {
+    ExecuteMsg::WithdrawFromTier {
+        amount: amount.map(Uint128::new),
+        padding: None,
+    }
+}
+
+fn withdrawal_amounts(suite: &Suite, address: &str) -> Vec<u128> {
+    match suite.query(QueryMsg::Withdrawals {
+        address: address.to_string(),
+        start: None,
+        limit: None,
+    }) {
+        QueryResponse::Withdrawals(withdrawals) => withdrawals
+            .withdrawals
+            .iter()
+            .map(|withdrawal| withdrawal.amount.u128())
+            .collect(),
+        _ => unreachable!(),
+    }
+}
+
+fn open_epoch(suite: &Suite) -> UnbondingEpochResponse {
+    match suite.query(QueryMsg::UnbondingEpoch { id: None }) {
+        QueryResponse::UnbondingEpoch(epoch) => epoch,
+        _ => unreachable!(),
+    }
+}
+
+fn pending_rewards(suite: &Suite, address: &str) -> u128 {
+    match suite.query(QueryMsg::PendingRewards {
+        address: address.to_string(),
+    }) {
+        QueryResponse::PendingRewards(rewards) => rewards.amount.u128(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn ido_lifecycle_with_native_payment() {
+    let mut suite = Suite::new();
+    let ido_id = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Empty {
+            with: Some(vec![USER1.to_string(), USER2.to_string()]),
+        },
+    );
+    let launchpad = suite.launchpad.to_string();
+    let token = suite.token.clone();
+    assert_eq!(suite.token_balance(&token, &launchpad), 10_000);
+    assert_eq!(suite.token_balance(&token, ADMIN), 990_000);
+
+    let err = suite
+        .execute(USER1, buy(ido_id, 0), &coins(100, "orai"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::IdoNotActive {});
+
+    // user1 stakes into tier 3, user2 holds a tier 2 NFT, user3 is not whitelisted
+    suite
+        .execute(USER1, deposit(), &coins(orai(2000), "orai"))
+        .unwrap();
+    suite.advance_time(100);
+
+    let err = suite
+        .execute(USER1, buy(ido_id, 0), &coins(300, "orai"))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TierSoldOut {
+            tier: 3,
+            remaining: Uint128::new(2_000)
+        }
+    );
+
+    match suite
+        .execute(USER1, buy(ido_id, 0), &coins(150, "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::BuyTokens {
+            amount,
+            unlock_time,
+            ..
+        } => {
+            assert_eq!(amount, Uint128::new(1_500));
+            assert_eq!(unlock_time, suite.now() + 1_000 + LOCK_PERIODS[2]);
+        }
+        _ => unreachable!(),
+    }
+
+    let msg = ExecuteMsg::BuyTokens {
+        ido_id,
+        amount: Uint128::zero(),
+        viewing_key: Some(String::new()),
+        padding: None,
+    };
+    suite.execute(USER2, msg, &coins(300, "orai")).unwrap();
+    suite
+        .execute(USER3, buy(ido_id, 0), &coins(50, "orai"))
+        .unwrap();
+
+    match suite.query(QueryMsg::IdoInfo { ido_id }) {
+        QueryResponse::IdoInfo(info) => {
+            assert_eq!(info.sold_amount, Uint128::new(5_000));
+            assert_eq!(info.total_payment, Uint128::new(500));
+            assert_eq!(info.participants, 3);
+            assert_eq!(
+                info.remaining_per_tiers,
+                [4_000u128, 0, 500, 1_000, 500]
+                    .iter()
+                    .map(|amount| Uint128::new(*amount))
+                    .collect::<Vec<_>>()
+            );
+        }
+        _ => unreachable!(),
+    }
+
+    let err = suite.execute(ADMIN, withdraw(ido_id), &[]).unwrap_err();
+    assert_eq!(err, ContractError::IdoNotFinished {});
+
+    suite.advance_time(1_000);
+    let err = suite.execute(USER1, recv(ido_id), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NothingToReceive {});
+
+    suite.advance_time(LOCK_PERIODS[0]);
+    for (user, amount) in [(USER1, 1_500), (USER2, 3_000), (USER3, 500)] {
+        match suite.execute(user, recv(ido_id), &[]).unwrap() {
+            ExecuteResponse::RecvTokens {
+                amount: received,
+                ido_success,
+                ..
+            } => {
+                assert_eq!(received, Uint128::new(amount));
+                assert!(ido_success);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(suite.token_balance(&token, user), amount);
+    }
+
+    match suite.query(QueryMsg::UserPortfolio {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::UserPortfolio(portfolio) => assert!(portfolio.positions.is_empty()),
+        _ => unreachable!(),
+    }
+
+    let admin_orai = suite.orai_balance(ADMIN);
+    match suite.execute(ADMIN, withdraw(ido_id), &[]).unwrap() {
+        ExecuteResponse::Withdraw {
+            ido_amount,
+            payment_amount,
+            ..
+        } => {
+            assert_eq!(ido_amount, Uint128::new(5_000));
+            assert_eq!(payment_amount, Uint128::new(500));
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(ADMIN), admin_orai + 500);
+    assert_eq!(suite.token_balance(&token, ADMIN), 995_000);
+    assert_eq!(suite.token_balance(&token, &launchpad), 0);
+
+    let err = suite.execute(ADMIN, withdraw(ido_id), &[]).unwrap_err();
+    assert_eq!(err, ContractError::AlreadyWithdrawn {});
+}
+
+#[test]
+fn failed_ido_refunds_payment() {
+    let mut suite = Suite::new();
+    let payment_token = suite.payment_token.clone();
+    let token = suite.token.clone();
+    let launchpad = suite.launchpad.to_string();
+
+    let native_ido = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Shared { with_blocked: None },
+    );
+    let token_ido = suite.start_ido(
+        PaymentMethod::Token {
+            contract: payment_token.to_string(),
+        },
+        Whitelist::Shared { with_blocked: None },
+    );
+    suite.advance_time(100);
+
+    suite
+        .execute(USER1, buy(native_ido, 0), &coins(100, "orai"))
+        .unwrap();
+    suite.approve(USER1, &payment_token, 100);
+    suite.execute(USER1, buy(token_ido, 1_000), &[]).unwrap();
+    assert_eq!(suite.token_balance(&payment_token, USER1), 999_900);
+    assert_eq!(suite.token_balance(&payment_token, &launchpad), 100);
+
+    match suite.query(QueryMsg::UserPortfolio {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::UserPortfolio(portfolio) => {
+            assert_eq!(portfolio.positions.len(), 2);
+            assert!(portfolio.positions.iter().all(|p| !p.refund_eligible));
+        }
+        _ => unreachable!(),
+    }
+
+    suite.advance_time(1_001);
+    let orai_balance = suite.orai_balance(USER1);
+    for ido_id in [native_ido, token_ido] {
+        match suite.execute(USER1, recv(ido_id), &[]).unwrap() {
+            ExecuteResponse::RecvTokens {
+                amount,
+                ido_success,
+                ..
+            } => {
+                assert_eq!(amount, Uint128::new(100));
+                assert!(!ido_success);
+            }
+            _ => unreachable!(),
+        }
+
+        let err = suite.execute(USER1, recv(ido_id), &[]).unwrap_err();
+        assert_eq!(err, ContractError::NothingToReceive {});
+        let err = suite.execute(USER2, recv(ido_id), &[]).unwrap_err();
+        assert_eq!(err, ContractError::NothingToReceive {});
+
+        match suite.execute(ADMIN, withdraw(ido_id), &[]).unwrap() {
+            ExecuteResponse::Withdraw { ido_amount, .. } => {
+                assert_eq!(ido_amount, Uint128::new(10_000))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    assert_eq!(suite.orai_balance(USER1), orai_balance + 100);
+    assert_eq!(suite.token_balance(&payment_token, USER1), 1_000_000);
+    assert_eq!(suite.token_balance(&payment_token, &launchpad), 0);
+    assert_eq!(suite.token_balance(&token, ADMIN), 1_000_000);
+    assert_eq!(suite.orai_balance(&launchpad), 0);
+}
+
+#[test]
+fn tier_deposit_withdraw_and_claim() {
+    let mut suite = Suite::new();
+    let launchpad = suite.launchpad.to_string();
+
+    // anything above the tier 4 threshold is refunded
+    match suite
+        .execute(USER1, deposit(), &coins(orai(1500), "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::Deposit {
+            usd_deposit,
+            orai_deposit,
+            tier,
+            ..
+        } => {
+            assert_eq!(tier, 4);
+            assert_eq!(usd_deposit, Uint128::new(1000));
+            assert_eq!(orai_deposit, Uint128::new(orai(1000)));
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(USER1), INITIAL_ORAI - orai(1000));
+    assert_eq!(suite.delegation(VALIDATOR1), orai(600));
+    assert_eq!(suite.delegation(VALIDATOR2), orai(400));
+
+    let err = suite
+        .execute(USER1, deposit(), &coins(orai(100), "orai"))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BelowNextTier {
+            usd_needed: Uint128::new(1000),
+            orai_needed: Uint128::new(orai(1000)),
+        }
+    );
+
+    suite
+        .execute(USER1, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+    let err = suite
+        .execute(USER1, deposit(), &coins(orai(100), "orai"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::MaxTierReached {});
+
+    match suite.query(QueryMsg::TierInfo {
+        address: USER1.to_string(),
+        nft_token_ids: None,
+    }) {
+        QueryResponse::TierInfo(info) => {
+            assert_eq!(info.tier, 1);
+            assert_eq!(info.next_tier, None);
+        }
+        _ => unreachable!(),
+    }
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::new(orai(4000)));
+            assert_eq!(stats.total_usd_deposit, Uint128::new(4000));
+            assert_eq!(stats.users_per_tier, vec![1, 0, 0, 0]);
+        }
+        _ => unreachable!(),
+    }
+
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    let claim_time = suite.now() + UNBOUND_LATENCY;
+    match suite.query(QueryMsg::TierUserInfo {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::TierUserInfo(info) => {
+            assert_eq!(info.tier, 5);
+            assert_eq!(info.orai_deposit, Uint128::zero());
+        }
+        _ => unreachable!(),
+    }
+    match suite.query(QueryMsg::Withdrawals {
+        address: USER1.to_string(),
+        start: None,
+        limit: None,
+    }) {
+        QueryResponse::Withdrawals(withdrawals) => {
+            assert_eq!(withdrawals.amount, 1);
+            assert_eq!(withdrawals.withdrawals[0].amount, Uint128::new(orai(4000)));
+            assert_eq!(withdrawals.withdrawals[0].claim_time, claim_time);
+            assert!(withdrawals.withdrawals[0].queued);
+        }
+        _ => unreachable!(),
+    }
+
+    let claim = ExecuteMsg::Claim {
+        recipient: None,
+        start: None,
+        limit: None,
+        padding: None,
+    };
+    let err = suite.execute(USER1, claim.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+
+    // nothing unbonds before the epoch is processed
+    suite.advance_time(UNBOUND_LATENCY);
+    let err = suite.execute(USER1, claim.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+
+    let claim_time = suite.now() + UNBOUND_LATENCY;
+    suite
+        .execute(USER2, process_unbonding_epoch(), &[])
+        .unwrap();
+    match suite.query(QueryMsg::Withdrawals {
+        address: USER1.to_string(),
+        start: None,
+        limit: None,
+    }) {
+        QueryResponse::Withdrawals(withdrawals) => {
+            assert_eq!(withdrawals.withdrawals[0].claim_time, claim_time);
+            assert!(!withdrawals.withdrawals[0].queued);
+        }
+        _ => unreachable!(),
+    }
+    suite.advance_time(UNBOUND_LATENCY);
+
+    match suite.execute(USER1, claim.clone(), &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(4000))),
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(USER1), INITIAL_ORAI);
+    assert_eq!(suite.orai_balance(&launchpad), 0);
+
+    let err = suite.execute(USER1, claim, &[]).unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::zero());
+            assert_eq!(stats.total_pending_withdrawals, Uint128::zero());
+            assert_eq!(stats.users_per_tier, vec![0, 0, 0, 0]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn admin_switches_price_source() {
+    let mut suite = Suite::new();
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => {
+            assert_eq!(config.price_source, PriceSourceConfig::OraiswapRouter {})
+        }
+        _ => unreachable!(),
+    }
+
+    let update = |price| ExecuteMsg::UpdatePriceSource {
+        price_source: PriceSourceConfig::FixedPrice { price },
+        padding: None,
+    };
+    let err = suite
+        .execute(USER1, update(Decimal::percent(2000)), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = suite
+        .execute(ADMIN, update(Decimal::zero()), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPrice {});
+
+    // 20 USD per ORAI, twice the router quote
+    suite
+        .execute(ADMIN, update(Decimal::percent(2000)), &[])
+        .unwrap();
+    match suite
+        .execute(USER1, deposit(), &coins(orai(1000) / 2, "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::Deposit {
+            usd_deposit, tier, ..
+        } => {
+            assert_eq!(usd_deposit, Uint128::new(1000));
+            assert_eq!(tier, 4);
+        }
+        _ => unreachable!(),
+    }
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => assert_eq!(
+            config.price_source,
+            PriceSourceConfig::FixedPrice {
+                price: Decimal::percent(2000)
+            }
+        ),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn price_guard_rejects_stale_and_skewed_quotes() {
+    let mut suite = Suite::new();
+    let guard = PriceGuard {
+        max_age: 600,
+        max_deviation_bps: 500,
+        twap_window: 3_600,
+    };
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(PriceGuard {
+            twap_window: 0,
+            ..guard.clone()
+        }),
+        padding: None,
+    };
+    let err = suite.execute(ADMIN, msg, &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPriceGuard {});
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(guard),
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    let stale = ContractError::StalePrice { max_age: 600 };
+    let err = suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap_err();
+    assert_eq!(err, stale);
+
+    let refresh = ExecuteMsg::RefreshPrice { padding: None };
+    suite.execute(USER3, refresh.clone(), &[]).unwrap();
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // a quote pushed 10% up is rejected, and refreshing caches it clamped to the TWAP
+    let price = Decimal::from_ratio(ROUTER_RATE, 1_000_000u128);
+    let skewed = Decimal::from_ratio(ROUTER_RATE * 11 / 10, 1_000_000u128);
+    suite.set_router_rate(ROUTER_RATE * 11 / 10);
+    let err = suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PriceDeviation {
+            price: skewed,
+            reference: price,
+        }
+    );
+    let clamped = Decimal::from_ratio(ROUTER_RATE * 105 / 100, 1_000_000u128);
+    match suite.execute(USER3, refresh.clone(), &[]).unwrap() {
+        ExecuteResponse::RefreshPrice {
+            price: cached,
+            twap,
+            ..
+        } => {
+            assert_eq!(cached, clamped);
+            assert_eq!(twap, price);
+        }
+        _ => unreachable!(),
+    }
+    let err = suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PriceDeviation {
+            price: skewed,
+            reference: price,
+        }
+    );
+
+    suite.set_router_rate(ROUTER_RATE);
+    suite.advance_time(601);
+    let err = suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap_err();
+    assert_eq!(err, stale);
+
+    match suite.query(QueryMsg::PriceCache {}) {
+        QueryResponse::PriceCache(cache) => assert_eq!(cache.price, clamped),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn admin_sets_multi_hop_price_route() {
+    let mut suite = Suite::new();
+    let native = |denom: &str| AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    };
+    let hop = |offer_asset_info, ask_asset_info| Operation {
+        orai_swap: OraiSwap {
+            offer_asset_info,
+            ask_asset_info,
+        },
+    };
+    let route = vec![
+        hop(native("orai"), native("ibc/atom")),
+        hop(
+            native("ibc/atom"),
+            AssetInfo::Token {
+                contract_addr: "usdt".to_string(),
+            },
+        ),
+    ];
+    let update = |route: Vec<Operation>, offer_amount: u128| ExecuteMsg::UpdatePriceRoute {
+        route,
+        offer_amount: Some(Uint128::new(offer_amount)),
+        padding: None,
+    };
+
+    let err = suite
+        .execute(USER1, update(route.clone(), 10_000_000), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = suite
+        .execute(ADMIN, update(route[1..].to_vec(), 10_000_000), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPriceRoute {});
+    let err = suite
+        .execute(ADMIN, update(route.clone(), 0), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPriceRoute {});
+
+    suite
+        .execute(ADMIN, update(route.clone(), 10_000_000), &[])
+        .unwrap();
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => {
+            assert_eq!(config.oraiswap_contract.route, route);
+            assert_eq!(
+                config.oraiswap_contract.offer_amount,
+                Some(Uint128::new(10_000_000))
+            );
+        }
+        _ => unreachable!(),
+    }
+
+    match suite
+        .execute(USER1, deposit(), &coins(orai(2000), "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::Deposit {
+            usd_deposit, tier, ..
+        } => {
+            assert_eq!(usd_deposit, Uint128::new(2000));
+            assert_eq!(tier, 3);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn circuit_breaker_pauses_deposits_until_reset() {
+    let mut suite = Suite::new();
+    let router_price = Decimal::from_ratio(ROUTER_RATE, 1_000_000u128);
+    let fixed = |price: Decimal| PriceSourceConfig::FixedPrice { price };
+
+    let msg = ExecuteMsg::UpdatePriceFallbacks {
+        fallbacks: vec![fixed(Decimal::zero())],
+        max_divergence_bps: None,
+        padding: None,
+    };
+    let err = suite.execute(ADMIN, msg, &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPrice {});
+    let msg = ExecuteMsg::UpdatePriceFallbacks {
+        fallbacks: vec![fixed(router_price)],
+        max_divergence_bps: Some(500),
+        padding: None,
+    };
+    let err = suite.execute(USER1, msg.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    // the router stops quoting and the fallback keeps deposits going
+    suite.set_router_rate(0);
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // the router comes back 10% off the fallback and trips the breaker
+    suite.set_router_rate(ROUTER_RATE * 11 / 10);
+    let balance = suite.orai_balance(USER2);
+    match suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::Deposit {
+            usd_deposit,
+            status,
+            ..
+        } => {
+            assert_eq!(usd_deposit, Uint128::zero());
+            assert_eq!(status, ResponseStatus::Failure);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(USER2), balance);
+    match suite.query(QueryMsg::CircuitBreaker {}) {
+        QueryResponse::CircuitBreaker(breaker) => {
+            assert!(breaker.tripped);
+            assert_eq!(breaker.tripped_at, Some(suite.now()));
+        }
+        _ => unreachable!(),
+    }
+
+    // deposits stay paused even once the sources agree again
+    suite.set_router_rate(ROUTER_RATE);
+    let err = suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::DepositsPaused {});
+
+    let reset = ExecuteMsg::ResetCircuitBreaker { padding: None };
+    let err = suite.execute(USER1, reset.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    suite.execute(ADMIN, reset, &[]).unwrap();
+    match suite.query(QueryMsg::CircuitBreaker {}) {
+        QueryResponse::CircuitBreaker(breaker) => {
+            assert!(!breaker.tripped);
+            assert_eq!(breaker.reason, None);
+        }
+        _ => unreachable!(),
+    }
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+}
+
+#[test]
+fn mark_to_market_tiers_follow_the_price() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+
+    let sync = ExecuteMsg::SyncTier {
+        address: USER1.to_string(),
+        padding: None,
+    };
+    let err = suite.execute(USER3, sync.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::TierPolicyLocked {});
+
+    let msg = ExecuteMsg::UpdateTierPolicy {
+        tier_policy: TierPolicy::MarkToMarket {
+            grace_period: 3_600,
+        },
+        padding: None,
+    };
+    let err = suite.execute(USER1, msg.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    let missing = ExecuteMsg::SyncTier {
+        address: USER2.to_string(),
+        padding: None,
+    };
+    let err = suite.execute(USER3, missing, &[]).unwrap_err();
+    assert_eq!(err, ContractError::NoTierDeposit {});
+
+    let stake_tier = |suite: &Suite| match suite.query(QueryMsg::TierInfo {
+        address: USER1.to_string(),
+        nft_token_ids: Some(vec![]),
+    }) {
+        QueryResponse::TierInfo(info) => info.stake_tier,
+        _ => unreachable!(),
+    };
+    let tier_user_info = |suite: &Suite| match suite.query(QueryMsg::TierUserInfo {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::TierUserInfo(info) => info,
+        _ => unreachable!(),
+    };
+
+    // ORAI halves: the deposit is worth 1500 USD, but the tier holds through the grace period
+    suite.set_router_rate(ROUTER_RATE / 2);
+    let started_at = suite.now();
+    match suite.execute(USER3, sync.clone(), &[]).unwrap() {
+        ExecuteResponse::SyncTier { tier, .. } => assert_eq!(tier, 2),
+        _ => unreachable!(),
+    }
+    assert_eq!(tier_user_info(&suite).below_tier_since, Some(started_at));
+    assert_eq!(stake_tier(&suite), 2);
+
+    suite.advance_time(3_600);
+    assert_eq!(stake_tier(&suite), 4);
+    match suite.execute(USER3, sync.clone(), &[]).unwrap() {
+        ExecuteResponse::SyncTier { tier, .. } => assert_eq!(tier, 4),
+        _ => unreachable!(),
+    }
+    let info = tier_user_info(&suite);
+    assert_eq!(info.tier, 4);
+    assert_eq!(info.usd_deposit, Uint128::new(1000));
+    assert_eq!(info.below_tier_since, None);
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_usd_deposit, Uint128::new(1000));
+            assert_eq!(stats.users_per_tier, vec![0, 0, 0, 1]);
+        }
+        _ => unreachable!(),
+    }
+
+    // the price recovers and the tier comes back at once
+    suite.set_router_rate(ROUTER_RATE);
+    assert_eq!(stake_tier(&suite), 2);
+    suite.execute(USER3, sync, &[]).unwrap();
+    let info = tier_user_info(&suite);
+    assert_eq!(info.tier, 2);
+    assert_eq!(info.usd_deposit, Uint128::new(3000));
+}
+
+#[test]
+fn partial_tier_withdrawals_downgrade() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(4000), "orai"))
+        .unwrap();
+
+    let err = suite
+        .execute(USER1, withdraw_from_tier(Some(0)), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::ZeroAmount {});
+    let err = suite
+        .execute(USER1, withdraw_from_tier(Some(orai(4000) + 1)), &[])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientDeposit {
+            available: Uint128::new(orai(4000)),
+        }
+    );
+
+    let tier_user_info = |suite: &Suite| match suite.query(QueryMsg::TierUserInfo {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::TierUserInfo(info) => info,
+        _ => unreachable!(),
+    };
+    let stats = |suite: &Suite| match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => stats,
+        _ => unreachable!(),
+    };
+
+    // 2500 USD stay behind, which is worth tier 3
+    suite
+        .execute(USER1, withdraw_from_tier(Some(orai(1500))), &[])
+        .unwrap();
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+    let info = tier_user_info(&suite);
+    assert_eq!(info.tier, 3);
+    assert_eq!(info.usd_deposit, Uint128::new(2000));
+    assert_eq!(info.orai_deposit, Uint128::new(orai(2500)));
+    assert_eq!(suite.delegation(VALIDATOR1), orai(1500));
+    assert_eq!(suite.delegation(VALIDATOR2), orai(1000));
+    let stats_after = stats(&suite);
+    assert_eq!(stats_after.total_orai_deposit, Uint128::new(orai(2500)));
+    assert_eq!(stats_after.total_usd_deposit, Uint128::new(2000));
+    assert_eq!(stats_after.users_per_tier, vec![0, 0, 1]);
+    assert_eq!(
+        stats_after.total_pending_withdrawals,
+        Uint128::new(orai(1500))
+    );
+
+    // below the lowest tier the deposit stays, at its own value
+    suite
+        .execute(USER1, withdraw_from_tier(Some(orai(2000))), &[])
+        .unwrap();
+    let info = tier_user_info(&suite);
+    assert_eq!(info.tier, 5);
+    assert_eq!(info.usd_deposit, Uint128::new(500));
+    assert_eq!(info.orai_deposit, Uint128::new(orai(500)));
+
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    let info = tier_user_info(&suite);
+    assert_eq!(info.tier, 5);
+    assert_eq!(info.orai_deposit, Uint128::zero());
+    let stats_after = stats(&suite);
+    assert_eq!(stats_after.total_orai_deposit, Uint128::zero());
+    assert_eq!(stats_after.total_usd_deposit, Uint128::zero());
+    assert_eq!(
+        stats_after.total_pending_withdrawals,
+        Uint128::new(orai(4000))
+    );
+
+    match suite.query(QueryMsg::Withdrawals {
+        address: USER1.to_string(),
+        start: None,
+        limit: None,
+    }) {
+        QueryResponse::Withdrawals(withdrawals) => {
+            let amounts = withdrawals
+                .withdrawals
+                .iter()
+                .map(|withdrawal| withdrawal.amount.u128())
+                .collect::<Vec<_>>();
+            assert_eq!(amounts, vec![orai(1500), orai(2000), orai(500)]);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(open_epoch(&suite).amount, Uint128::new(orai(2500)));
+    assert_eq!(suite.delegation(VALIDATOR1), orai(1500));
+}
+
+#[test]
+fn tier_locks_reject_early_exits() {
+    let mut suite = Suite::new();
+    let update = |min_lock_durations: Vec<u64>, early_exit| ExecuteMsg::UpdateTierLocks {
+        tier_locks: TierLocks {
+            min_lock_durations,
+            early_exit,
+        },
+        padding: None,
+    };
+
+    let err = suite
+        .execute(ADMIN, update(vec![400], EarlyExitPolicy::Reject {}), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidTierLocks { expected: 4 });
+    let penalty = EarlyExitPolicy::Penalty {
+        bps: 10_001,
+        recipient: PenaltyRecipient::Stakers {},
+    };
+    let err = suite
+        .execute(ADMIN, update(vec![], penalty), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidPenalty {});
+
+    let msg = update(vec![400, 300, 200, 100], EarlyExitPolicy::Reject {});
+    let err = suite.execute(USER1, msg.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    suite
+        .execute(USER1, deposit(), &coins(orai(4000), "orai"))
+        .unwrap();
+    let unlock_time = suite.now() + 400;
+    let tier_lock = |suite: &Suite| match suite.query(QueryMsg::TierLock {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::TierLock(lock) => lock,
+        _ => unreachable!(),
+    };
+    let lock = tier_lock(&suite);
+    assert_eq!(lock.tier, 1);
+    assert_eq!(lock.unlock_time, unlock_time);
+    assert!(lock.locked);
+
+    let err = suite
+        .execute(USER1, withdraw_from_tier(Some(orai(1000))), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::TierLocked { unlock_time });
+
+    suite.advance_time(400);
+    assert!(!tier_lock(&suite).locked);
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    assert_eq!(withdrawal_amounts(&suite, USER1), vec![orai(4000)]);
+}
+
+#[test]
+fn early_exit_penalties_go_to_stakers_or_treasury() {
+    let mut suite = Suite::new();
+    let msg = ExecuteMsg::UpdateTierLocks {
+        tier_locks: TierLocks {
+            min_lock_durations: vec![400, 300, 200, 100],
+            early_exit: EarlyExitPolicy::Penalty {
+                bps: 1_000,
+                recipient: PenaltyRecipient::Stakers {},
+            },
+        },
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // USER1 leaves at once and leaves 10% behind for USER2
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    assert_eq!(withdrawal_amounts(&suite, USER1), vec![orai(900)]);
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+    assert_eq!(suite.delegation(VALIDATOR1), orai(660));
+    assert_eq!(suite.delegation(VALIDATOR2), orai(440));
+
+    let pending_rewards =
+        |suite: &Suite, address: &str| match suite.query(QueryMsg::PendingRewards {
+            address: address.to_string(),
+        }) {
+            QueryResponse::PendingRewards(rewards) => rewards.amount.u128(),
+            _ => unreachable!(),
+        };
+    assert_eq!(pending_rewards(&suite, USER1), 0);
+    assert_eq!(pending_rewards(&suite, USER2), orai(100));
+
+    let claim = ExecuteMsg::ClaimStakingRewards { padding: None };
+    let err = suite.execute(USER1, claim.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NoRewards {});
+    match suite.execute(USER2, claim, &[]).unwrap() {
+        ExecuteResponse::ClaimStakingRewards { amount, .. } => {
+            assert_eq!(amount, Uint128::new(orai(100)))
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(pending_rewards(&suite, USER2), 0);
+    assert_eq!(withdrawal_amounts(&suite, USER2), vec![orai(100)]);
+
+    // a treasury penalty is queued for the treasury to claim
+    let msg = ExecuteMsg::UpdateTierLocks {
+        tier_locks: TierLocks {
+            min_lock_durations: vec![400, 300, 200, 100],
+            early_exit: EarlyExitPolicy::Penalty {
+                bps: 1_000,
+                recipient: PenaltyRecipient::Treasury {
+                    address: USER3.to_string(),
+                },
+            },
+        },
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    suite
+        .execute(USER2, withdraw_from_tier(Some(orai(500))), &[])
+        .unwrap();
+    assert_eq!(
+        withdrawal_amounts(&suite, USER2),
+        vec![orai(100), orai(450)]
+    );
+    assert_eq!(withdrawal_amounts(&suite, USER3), vec![orai(50)]);
+
+    // both claims wait in the next epoch, which the epoch duration keeps closed
+    assert_eq!(open_epoch(&suite).amount, Uint128::new(orai(600)));
+    assert_eq!(suite.delegation(VALIDATOR1), orai(660));
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::new(orai(500)));
+            assert_eq!(stats.total_pending_withdrawals, Uint128::new(orai(1500)));
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn rewards_are_harvested_and_redelegated() {
+    let mut suite = Suite::new();
+    let launchpad = suite.launchpad.to_string();
+
+    let msg = ExecuteMsg::WithdrawRewards { padding: None };
+    let err = suite.execute(USER2, msg.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NoRewards {});
+
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // 10% a year, harvested from both validators by anyone and delegated again
+    suite.advance_time(365 * 24 * 60 * 60);
+    assert_eq!(pending_rewards(&suite, USER1), orai(100));
+    match suite.execute(USER2, msg, &[]).unwrap() {
+        ExecuteResponse::WithdrawRewards { amount, .. } => {
+            assert_eq!(amount, Uint128::new(orai(100)))
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(&launchpad), 0);
+    assert_eq!(suite.delegation(VALIDATOR1), orai(660));
+    assert_eq!(suite.delegation(VALIDATOR2), orai(440));
+    assert_eq!(pending_rewards(&suite, USER1), orai(100));
+
+    let msg = ExecuteMsg::Redelegate {
+        validator_address: VALIDATOR3.to_string(),
+        recipient: None,
+        padding: None,
+    };
+    let err = suite.execute(USER1, msg.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let same = ExecuteMsg::Redelegate {
+        validator_address: VALIDATOR1.to_string(),
+        recipient: None,
+        padding: None,
+    };
+    let err = suite.execute(ADMIN, same, &[]).unwrap_err();
+    assert_eq!(err, ContractError::SameValidator {});
+
+    match suite.execute(ADMIN, msg, &[]).unwrap() {
+        ExecuteResponse::Redelegate { amount, .. } => assert_eq!(amount, Uint128::new(orai(660))),
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.delegation(VALIDATOR1), 0);
+    assert_eq!(suite.delegation(VALIDATOR3), orai(660));
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => assert_eq!(config.validators[0].address, VALIDATOR3),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn staking_rewards_are_shared_pro_rata() {
+    let mut suite = Suite::new();
+
+    let update = |bps| ExecuteMsg::UpdateProtocolFee {
+        protocol_fee: Some(ProtocolFee {
+            bps,
+            recipient: ADMIN.to_string(),
+        }),
+        padding: None,
+    };
+    let err = suite.execute(USER1, update(1_000), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = suite.execute(ADMIN, update(10_001), &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidProtocolFee {});
+    suite.execute(ADMIN, update(1_000), &[]).unwrap();
+
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+
+    // 400 USD of rewards, 10% of which is the protocol's
+    suite.advance_time(365 * 24 * 60 * 60);
+    assert_eq!(pending_rewards(&suite, USER1), orai(90));
+    assert_eq!(pending_rewards(&suite, USER2), orai(270));
+
+    let admin_balance = suite.orai_balance(ADMIN);
+    let claim = ExecuteMsg::ClaimStakingRewards { padding: None };
+    match suite.execute(USER1, claim.clone(), &[]).unwrap() {
+        ExecuteResponse::ClaimStakingRewards { amount, .. } => {
+            assert_eq!(amount, Uint128::new(orai(90)))
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(ADMIN), admin_balance + orai(40));
+    assert_eq!(withdrawal_amounts(&suite, USER1), vec![orai(90)]);
+    assert_eq!(pending_rewards(&suite, USER1), 0);
+    assert_eq!(pending_rewards(&suite, USER2), orai(270));
+
+    let err = suite.execute(USER1, claim, &[]).unwrap_err();
+    assert_eq!(err, ContractError::NoRewards {});
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+
+    // the rest of the rewards stays delegated until USER2 claims it
+    assert_eq!(
+        suite.delegation(VALIDATOR1) + suite.delegation(VALIDATOR2),
+        orai(4000) + orai(270)
+    );
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.outstanding_rewards, Uint128::new(orai(270)));
+            assert_eq!(stats.total_pending_withdrawals, Uint128::new(orai(90)));
+        }
+        _ => unreachable!(),
+    }
+
+    let disable = ExecuteMsg::UpdateProtocolFee {
+        protocol_fee: None,
+        padding: None,
+    };
+    suite.execute(ADMIN, disable, &[]).unwrap();
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => assert_eq!(config.protocol_fee, None),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn opted_in_rewards_compound_into_tier() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    let opt_in = ExecuteMsg::SetAutoCompound {
+        enabled: true,
+        padding: None,
+    };
+    match suite.execute(USER1, opt_in, &[]).unwrap() {
+        ExecuteResponse::SetAutoCompound { enabled, .. } => assert!(enabled),
+        _ => unreachable!(),
+    }
+
+    // ORAI is worth ten times more by the time a year of rewards is compounded
+    suite.advance_time(365 * 24 * 60 * 60);
+    suite.set_router_rate(ROUTER_RATE * 10);
+    let msg = ExecuteMsg::Compound {
+        addresses: vec![USER1.to_string(), USER2.to_string(), USER3.to_string()],
+        padding: None,
+    };
+    match suite.execute(USER3, msg.clone(), &[]).unwrap() {
+        ExecuteResponse::Compound { amount, .. } => assert_eq!(amount, Uint128::new(orai(100))),
+        _ => unreachable!(),
+    }
+
+    match suite.query(QueryMsg::TierUserInfo {
+        address: USER1.to_string(),
+    }) {
+        QueryResponse::TierUserInfo(info) => {
+            assert_eq!(info.orai_deposit, Uint128::new(orai(1100)));
+            assert_eq!(info.usd_deposit, Uint128::new(2000));
+            assert_eq!(info.tier, 3);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(pending_rewards(&suite, USER1), 0);
+    assert_eq!(pending_rewards(&suite, USER2), orai(100));
+
+    // nothing is left to compound in the same block
+    match suite.execute(USER3, msg, &[]).unwrap() {
+        ExecuteResponse::Compound { amount, .. } => assert_eq!(amount, Uint128::zero()),
+        _ => unreachable!(),
+    }
+
+    assert_eq!(
+        suite.delegation(VALIDATOR1) + suite.delegation(VALIDATOR2),
+        orai(2200)
+    );
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::new(orai(2100)));
+            assert_eq!(stats.outstanding_rewards, Uint128::new(orai(100)));
+            assert_eq!(stats.users_per_tier, vec![0, 0, 1, 1]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn admin_manages_validators_and_rebalances() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    let add = |address: &str| ExecuteMsg::AddValidator {
+        address: address.to_string(),
+        padding: None,
+    };
+    let err = suite.execute(USER1, add(VALIDATOR3), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = suite.execute(ADMIN, add(VALIDATOR1), &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DuplicateValidator {
+            address: VALIDATOR1.to_string()
+        }
+    );
+    let err = suite.execute(ADMIN, add("validator4"), &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnknownValidator {
+            address: "validator4".to_string()
+        }
+    );
+    suite.execute(ADMIN, add(VALIDATOR3), &[]).unwrap();
+
+    let weights = |weights: &[(&str, u128)]| ExecuteMsg::SetWeights {
+        validators: weights
+            .iter()
+            .map(|(address, weight)| ValidatorWithWeight {
+                address: address.to_string(),
+                weight: *weight,
+            })
+            .collect(),
+        padding: None,
+    };
+    let msg = weights(&[(VALIDATOR1, 5_000), (VALIDATOR2, 0), (VALIDATOR3, 4_000)]);
+    let err = suite.execute(ADMIN, msg, &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidValidatorWeights {});
+    let msg = weights(&[(VALIDATOR1, 5_000), (VALIDATOR3, 5_000)]);
+    let err = suite.execute(ADMIN, msg, &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidValidatorWeights {});
+    let msg = weights(&[(VALIDATOR1, 5_000), (VALIDATOR2, 0), (VALIDATOR3, 5_000)]);
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    let remove = ExecuteMsg::RemoveValidator {
+        address: VALIDATOR2.to_string(),
+        padding: None,
+    };
+    let err = suite.execute(ADMIN, remove.clone(), &[]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ValidatorInUse {
+            address: VALIDATOR2.to_string()
+        }
+    );
+
+    let rebalance = ExecuteMsg::Rebalance { padding: None };
+    let err = suite.execute(USER1, rebalance.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    match suite.execute(ADMIN, rebalance, &[]).unwrap() {
+        ExecuteResponse::Rebalance { amount, .. } => assert_eq!(amount, Uint128::new(orai(500))),
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.delegation(VALIDATOR1), orai(500));
+    assert_eq!(suite.delegation(VALIDATOR2), 0);
+    assert_eq!(suite.delegation(VALIDATOR3), orai(500));
+
+    suite.execute(ADMIN, remove, &[]).unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    assert_eq!(suite.delegation(VALIDATOR1), orai(1000));
+    assert_eq!(suite.delegation(VALIDATOR3), orai(1000));
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => {
+            let validators: Vec<_> = config
+                .validators
+                .iter()
+                .map(|validator| validator.address.as_str())
+                .collect();
+            assert_eq!(validators, vec![VALIDATOR1, VALIDATOR3]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn delegation_ledger_tracks_odd_amounts() {
+    let mut suite = Suite::new();
+    let ledger = |suite: &Suite| match suite.query(QueryMsg::Delegations {}) {
+        QueryResponse::Delegations(ledger) => ledger
+            .delegations
+            .iter()
+            .map(|delegation| {
+                assert_eq!(delegation.delegated, delegation.live);
+                (delegation.validator.clone(), delegation.delegated.u128())
+            })
+            .collect::<Vec<_>>(),
+        _ => unreachable!(),
+    };
+
+    // 60% of the odd uORAI rounds down, the heaviest validator takes the remainder
+    let amount = orai(1000) + 7;
+    suite
+        .execute(USER1, deposit(), &coins(amount, "orai"))
+        .unwrap();
+    assert_eq!(
+        ledger(&suite),
+        vec![
+            (VALIDATOR1.to_string(), orai(600) + 5),
+            (VALIDATOR2.to_string(), orai(400) + 2),
+        ]
+    );
+
+    // withdrawals are split by the ledger, and the rest comes out exactly
+    suite
+        .execute(USER1, withdraw_from_tier(Some(3)), &[])
+        .unwrap();
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+    assert_eq!(
+        ledger(&suite),
+        vec![
+            (VALIDATOR1.to_string(), orai(600) + 3),
+            (VALIDATOR2.to_string(), orai(400) + 1),
+        ]
+    );
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    suite.advance_time(Unbonding::default().epoch_duration);
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+
+    // only the rewards harvested by the crank stay delegated
+    let held: u128 = ledger(&suite).iter().map(|(_, amount)| amount).sum();
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::zero());
+            assert_eq!(stats.outstanding_rewards, Uint128::new(held));
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn withdrawals_unbond_in_epochs() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // both withdrawals wait in the open epoch and stay delegated
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    suite.execute(USER2, withdraw_from_tier(None), &[]).unwrap();
+    let epoch = open_epoch(&suite);
+    assert_eq!(epoch.id, 0);
+    assert_eq!(epoch.amount, Uint128::new(orai(2000)));
+    assert_eq!(epoch.unbonded_at, None);
+    assert_eq!(
+        suite.delegation(VALIDATOR1) + suite.delegation(VALIDATOR2),
+        orai(2000)
+    );
+
+    // one crank undelegates the whole batch
+    match suite
+        .execute(USER3, process_unbonding_epoch(), &[])
+        .unwrap()
+    {
+        ExecuteResponse::ProcessUnbondingEpoch {
+            epoch,
+            amount,
+            claim_time,
+            ..
+        } => {
+            assert_eq!(epoch, 0);
+            assert_eq!(amount, Uint128::new(orai(2000)));
+            assert_eq!(claim_time, suite.now() + UNBOUND_LATENCY);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.delegation(VALIDATOR1), 0);
+    assert_eq!(suite.delegation(VALIDATOR2), 0);
+    let epoch = open_epoch(&suite);
+    assert_eq!(epoch.id, 1);
+    assert_eq!(epoch.amount, Uint128::zero());
+
+    // the next epoch waits for the epoch duration
+    let epoch_duration = Unbonding::default().epoch_duration;
+    let err = suite
+        .execute(USER3, process_unbonding_epoch(), &[])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::EpochNotOver {
+            processable_at: suite.now() + epoch_duration
+        }
+    );
+
+    let update = |epoch_duration| ExecuteMsg::UpdateUnbonding {
+        unbonding: Unbonding {
+            epoch_duration,
+            ..Default::default()
+        },
+        padding: None,
+    };
+    let err = suite.execute(USER1, update(60), &[]).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = suite.execute(ADMIN, update(0), &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidUnbonding {});
+    suite.execute(ADMIN, update(60), &[]).unwrap();
+    suite.advance_time(60);
+    let err = suite
+        .execute(USER3, process_unbonding_epoch(), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::NothingToWithdraw {});
+
+    // claims settle once the processed epoch finished unbonding
+    let claim = ExecuteMsg::Claim {
+        recipient: None,
+        start: None,
+        limit: None,
+        padding: None,
+    };
+    suite.advance_time(UNBOUND_LATENCY - 61);
+    let err = suite.execute(USER1, claim.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+    suite.advance_time(1);
+    match suite.execute(USER1, claim.clone(), &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(1000))),
+        _ => unreachable!(),
+    }
+    match suite.execute(USER2, claim, &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(1000))),
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(suite.launchpad.as_str()), 0);
+}
+
+#[test]
+fn unbonding_time_follows_config_or_chain() {
+    let mut suite = Suite::new();
+    let day = 24 * 60 * 60;
+    let update = |unbonding_time, from_chain| ExecuteMsg::UpdateUnbonding {
+        unbonding: Unbonding {
+            unbonding_time,
+            from_chain,
+            ..Default::default()
+        },
+        padding: None,
+    };
+    let err = suite.execute(ADMIN, update(0, false), &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidUnbonding {});
+    suite.execute(ADMIN, update(day, false), &[]).unwrap();
+    match suite.query(QueryMsg::Config {}) {
+        QueryResponse::Config(config) => {
+            assert_eq!(config.unbonding.unbonding_time, day);
+            assert!(!config.unbonding.from_chain);
+        }
+        _ => unreachable!(),
+    }
+
+    suite
+        .execute(USER1, deposit(), &coins(orai(2000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER1, withdraw_from_tier(Some(orai(1000))), &[])
+        .unwrap();
+    let claim_time = suite.now() + day;
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+
+    // the chain's staking params take over, except for the epoch already unbonding
+    suite.execute(ADMIN, update(day, true), &[]).unwrap();
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    let epoch_duration = Unbonding::default().epoch_duration;
+    let processable_at = suite.now() + epoch_duration;
+    let claim_times = |suite: &Suite| match suite.query(QueryMsg::Withdrawals {
+        address: USER1.to_string(),
+        start: None,
+        limit: None,
+    }) {
+        QueryResponse::Withdrawals(withdrawals) => withdrawals
+            .withdrawals
+            .iter()
+            .map(|withdrawal| withdrawal.claim_time)
+            .collect::<Vec<_>>(),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        claim_times(&suite),
+        vec![claim_time, processable_at + CHAIN_UNBONDING_TIME]
+    );
+    assert_eq!(open_epoch(&suite).unbonding_time, CHAIN_UNBONDING_TIME);
+
+    suite.advance_time(epoch_duration + 10);
+    suite
+        .execute(USER1, process_unbonding_epoch(), &[])
+        .unwrap();
+    assert_eq!(
+        claim_times(&suite),
+        vec![claim_time, processable_at + 10 + CHAIN_UNBONDING_TIME]
+    );
+
+    // the chain returns the uORAI right when the claim time says
+    let claim = ExecuteMsg::Claim {
+        recipient: None,
+        start: Some(1),
+        limit: None,
+        padding: None,
+    };
+    suite.advance_time(CHAIN_UNBONDING_TIME - 1);
+    let err = suite.execute(USER1, claim.clone(), &[]).unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+    suite.advance_time(1);
+    match suite.execute(USER1, claim, &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(1000))),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn slashes_are_shared_by_deposits_and_withdrawals() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER1, withdraw_from_tier(Some(orai(1000))), &[])
+        .unwrap();
+
+    let err = suite
+        .execute(
+            USER3,
+            ExecuteMsg::ReconcileDelegations { padding: None },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoSlashing {});
+
+    // a quarter of VALIDATOR1's 60% is gone, 15% of everything delegated
+    suite
+        .app
+        .sudo(SudoMsg::Staking(StakingSudo::Slash {
+            validator: VALIDATOR1.to_string(),
+            percentage: Decimal::percent(25),
+        }))
+        .unwrap();
+    match suite
+        .execute(
+            USER3,
+            ExecuteMsg::ReconcileDelegations { padding: None },
+            &[],
+        )
+        .unwrap()
+    {
+        ExecuteResponse::ReconcileDelegations { slashed, ratio, .. } => {
+            assert_eq!(slashed, Uint128::new(orai(600)));
+            assert_eq!(ratio, Decimal::percent(85));
+        }
+        _ => unreachable!(),
+    }
+    let err = suite
+        .execute(
+            USER3,
+            ExecuteMsg::ReconcileDelegations { padding: None },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoSlashing {});
+
+    let orai_deposit = |suite: &Suite, address: &str| match suite.query(QueryMsg::TierUserInfo {
+        address: address.to_string(),
+    }) {
+        QueryResponse::TierUserInfo(info) => info.orai_deposit.u128(),
+        _ => unreachable!(),
+    };
+    assert_eq!(orai_deposit(&suite, USER1), orai(1700));
+    assert_eq!(orai_deposit(&suite, USER2), orai(850));
+    assert_eq!(withdrawal_amounts(&suite, USER1), vec![orai(850)]);
+    assert_eq!(open_epoch(&suite).amount, Uint128::new(orai(850)));
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::new(orai(2550)));
+            assert_eq!(stats.total_pending_withdrawals, Uint128::new(orai(850)));
+            assert_eq!(stats.slashing_index, Decimal::percent(85));
+        }
+        _ => unreachable!(),
+    }
+    match suite.query(QueryMsg::Delegations {}) {
+        QueryResponse::Delegations(response) => {
+            for delegation in response.delegations {
+                assert_eq!(delegation.delegated, delegation.live);
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    // what is left comes out exactly
+    suite.execute(USER2, withdraw_from_tier(None), &[]).unwrap();
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    suite
+        .execute(USER3, process_unbonding_epoch(), &[])
+        .unwrap();
+    assert_eq!(suite.delegation(VALIDATOR1), 0);
+    assert_eq!(suite.delegation(VALIDATOR2), 0);
+
+    suite.advance_time(UNBOUND_LATENCY);
+    let claim = ExecuteMsg::Claim {
+        recipient: None,
+        start: None,
+        limit: None,
+        padding: None,
+    };
+    match suite.execute(USER1, claim.clone(), &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(2550))),
+        _ => unreachable!(),
+    }
+    match suite.execute(USER2, claim, &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(850))),
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(suite.launchpad.as_str()), 0);
+}
+
+#[test]
+fn ido_participants_page_through_buyers() {
+    let mut suite = Suite::new();
+    let ido_id = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Shared { with_blocked: None },
+    );
+    suite
+        .execute(USER1, deposit(), &coins(orai(2000), "orai"))
+        .unwrap();
+    suite.advance_time(100);
+
+    // 100 buyers fill the 1_000 tier 5 tokens, user1 buys twice in tier 3
+    let buyers: Vec<String> = (0..100).map(|i| format!("buyer{:03}", i)).collect();
+    suite.app.init_modules(|router, _, storage| {
+        for buyer in buyers.iter() {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(buyer), coins(1, "orai"))
+                .unwrap();
+        }
+    });
+    for buyer in buyers.iter() {
+        suite
+            .execute(buyer, buy(ido_id, 0), &coins(1, "orai"))
+            .unwrap();
+    }
+    for payment in [20, 30] {
+        suite
+            .execute(USER1, buy(ido_id, 0), &coins(payment, "orai"))
+            .unwrap();
+    }
+
+    let participants = |start_after: Option<String>, limit: Option<u32>| match suite.query(
+        QueryMsg::IdoParticipants {
+            ido_id,
+            start_after,
+            limit,
+        },
+    ) {
+        QueryResponse::IdoParticipants(response) => response,
+        _ => unreachable!(),
+    };
+
+    let first = participants(None, Some(1_000));
+    assert_eq!(first.amount, 101);
+    assert_eq!(first.participants.len(), 100);
+
+    let mut pages = vec![];
+    let mut start_after = None;
+    loop {
+        let page = participants(start_after, Some(40));
+        assert_eq!(page.amount, 101);
+        match page.participants.last() {
+            Some(last) => start_after = Some(last.address.clone()),
+            None => break,
+        }
+        pages.push(page.participants);
+    }
+    assert_eq!(
+        pages.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![40, 40, 21]
+    );
+
+    let participants: Vec<_> = pages.into_iter().flatten().collect();
+    let mut expected: Vec<String> = buyers.clone();
+    expected.push(USER1.to_string());
+    assert_eq!(
+        participants
+            .iter()
+            .map(|participant| participant.address.clone())
+            .collect::<Vec<_>>(),
+        expected
+    );
+    for participant in participants.iter().take(100) {
+        assert_eq!(participant.tier, 5);
+        assert_eq!(participant.total_payment, Uint128::new(1));
+        assert_eq!(participant.total_tokens_bought, Uint128::new(10));
+    }
+    let user1 = participants.last().unwrap();
+    assert_eq!(user1.tier, 3);
+    assert_eq!(user1.total_payment, Uint128::new(50));
+    assert_eq!(user1.total_tokens_bought, Uint128::new(500));
+    assert_eq!(user1.total_tokens_received, Uint128::zero());
+
+    match suite.query(QueryMsg::IdoInfo { ido_id }) {
+        QueryResponse::IdoInfo(info) => assert_eq!(info.participants, 101),
+        _ => unreachable!(),
+    }
+}
+
+fn simulate_buy(
+    suite: &Suite,
+    ido_id: u32,
+    address: &str,
+    amount: u128,
+    viewing_key: Option<String>,
+) -> SimulateBuyResponse {
+    match suite.query(QueryMsg::SimulateBuy {
+        ido_id,
+        address: address.to_string(),
+        amount: Uint128::new(amount),
+        viewing_key,
+    }) {
+        QueryResponse::SimulateBuy(simulation) => simulation,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn simulate_buy_matches_buy_tokens() {
+    let mut suite = Suite::new();
+    let payment_token = suite.payment_token.clone();
+    let native_ido = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Shared {
+            with_blocked: Some(vec![USER3.to_string()]),
+        },
+    );
+    let token_ido = suite.start_ido(
+        PaymentMethod::Token {
+            contract: payment_token.to_string(),
+        },
+        Whitelist::Shared { with_blocked: None },
+    );
+    suite
+        .execute(USER1, deposit(), &coins(orai(2000), "orai"))
+        .unwrap();
+
+    let simulation = simulate_buy(&suite, native_ido, USER1, 1_000, None);
+    assert_eq!(simulation.rejection, Some(BuyRejection::IdoNotActive));
+    assert_eq!(simulation.amount, Uint128::zero());
+    assert_eq!(simulation.payment, Uint128::zero());
+    assert_eq!(simulation.unlock_time, None);
+    suite.advance_time(100);
+
+    // native amounts round down to whole payment units
+    let simulation = simulate_buy(&suite, native_ido, USER1, 1_509, None);
+    assert!(simulation.whitelisted);
+    assert_eq!(simulation.tier, 3);
+    assert_eq!(simulation.rejection, None);
+    assert_eq!(simulation.amount, Uint128::new(1_500));
+    assert_eq!(simulation.payment, Uint128::new(150));
+    match suite
+        .execute(
+            USER1,
+            buy(native_ido, 0),
+            &coins(simulation.payment.u128(), "orai"),
+        )
+        .unwrap()
+    {
+        ExecuteResponse::BuyTokens {
+            amount,
+            unlock_time,
+            ..
+        } => {
+            assert_eq!(amount, simulation.amount);
+            assert_eq!(Some(unlock_time), simulation.unlock_time);
+        }
+        _ => unreachable!(),
+    }
+
+    let simulation = simulate_buy(&suite, native_ido, USER1, 9, None);
+    assert_eq!(simulation.rejection, Some(BuyRejection::ZeroAmount));
+    let simulation = simulate_buy(&suite, native_ido, USER1, 510, None);
+    assert_eq!(
+        simulation.rejection,
+        Some(BuyRejection::ExceedsTierRemaining {
+            tier: 3,
+            remaining: Uint128::new(500),
+        })
+    );
+    let err = suite
+        .execute(USER1, buy(native_ido, 0), &coins(51, "orai"))
+        .unwrap_err();
+    assert_eq!(err, simulation.rejection.unwrap().into());
+
+    // user3 is blocked and buys in the lowest tier
+    let simulation = simulate_buy(&suite, native_ido, USER3, 1_000, None);
+    assert!(!simulation.whitelisted);
+    assert_eq!(simulation.tier, 5);
+    assert_eq!(simulation.payment, Uint128::new(100));
+    suite
+        .execute(USER3, buy(native_ido, 0), &coins(100, "orai"))
+        .unwrap();
+    let simulation = simulate_buy(&suite, native_ido, USER3, 10, None);
+    assert_eq!(
+        simulation.rejection,
+        Some(BuyRejection::TierSoldOut { tier: 5 })
+    );
+    let err = suite
+        .execute(USER3, buy(native_ido, 0), &coins(1, "orai"))
+        .unwrap_err();
+    assert_eq!(err, simulation.rejection.unwrap().into());
+
+    // token payments keep the requested amount
+    let simulation = simulate_buy(&suite, token_ido, USER2, 2_005, Some(String::new()));
+    assert_eq!(simulation.tier, 2);
+    assert_eq!(simulation.amount, Uint128::new(2_005));
+    assert_eq!(simulation.payment, Uint128::new(200));
+    suite.approve(USER2, &payment_token, 200);
+    let msg = ExecuteMsg::BuyTokens {
+        ido_id: token_ido,
+        amount: simulation.amount,
+        viewing_key: Some(String::new()),
+        padding: None,
+    };
+    match suite.execute(USER2, msg, &[]).unwrap() {
+        ExecuteResponse::BuyTokens {
+            amount,
+            unlock_time,
+            ..
+        } => {
+            assert_eq!(amount, simulation.amount);
+            assert_eq!(Some(unlock_time), simulation.unlock_time);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.token_balance(&payment_token, USER2), 999_800);
+
+    // tiers 1 to 3 and 5 hold exactly the 10_000 tokens on sale
+    suite
+        .execute(ADMIN, deposit(), &coins(orai(4000), "orai"))
+        .unwrap();
+    suite
+        .execute(ADMIN, buy(native_ido, 0), &coins(400, "orai"))
+        .unwrap();
+    let msg = ExecuteMsg::BuyTokens {
+        ido_id: native_ido,
+        amount: Uint128::zero(),
+        viewing_key: Some(String::new()),
+        padding: None,
+    };
+    suite.execute(USER2, msg, &coins(300, "orai")).unwrap();
+    let simulation = simulate_buy(&suite, native_ido, USER1, 500, None);
+    assert_eq!(simulation.rejection, None);
+    suite
+        .execute(USER1, buy(native_ido, 0), &coins(50, "orai"))
+        .unwrap();
+
+    let simulation = simulate_buy(&suite, native_ido, USER1, 10, None);
+    assert_eq!(simulation.rejection, Some(BuyRejection::AllTokensSold));
+    let err = suite
+        .execute(USER1, buy(native_ido, 0), &coins(1, "orai"))
+        .unwrap_err();
+    assert_eq!(err, simulation.rejection.unwrap().into());
+}
+
+fn tier_info(suite: &Suite, address: &str, nft_token_ids: Option<Vec<String>>) -> TierInfoResponse {
+    match suite.query(QueryMsg::TierInfo {
+        address: address.to_string(),
+        nft_token_ids,
+    }) {
+        QueryResponse::TierInfo(info) => info,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn tier_info_quotes_what_a_deposit_needs() {
+    let mut suite = Suite::new();
+
+    // without token ids, or with unknown ones, the NFT tier is the lowest
+    let info = tier_info(&suite, USER2, None);
+    assert_eq!((info.tier, info.stake_tier, info.nft_tier), (5, 5, 5));
+    let info = tier_info(&suite, USER2, Some(vec!["missing".to_string()]));
+    assert_eq!(info.nft_tier, 5);
+    let info = tier_info(&suite, USER2, Some(vec!["1".to_string()]));
+    assert_eq!((info.tier, info.stake_tier, info.nft_tier), (2, 5, 2));
+    assert_eq!(info.next_tier, Some(4));
+    assert_eq!(info.usd_to_next_tier, Uint128::new(1000));
+
+    // at 3 USD per ORAI the floor amount is worth 1 uUSD short of the next tier
+    suite.set_router_rate(3_000_000);
+    let info = tier_info(&suite, USER1, None);
+    assert_eq!(info.orai_to_next_tier, Uint128::new(333_333_334));
+    match suite
+        .execute(USER1, deposit(), &coins(333_333_334, "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::Deposit { tier, .. } => assert_eq!(tier, 4),
+        _ => unreachable!(),
+    }
+
+    let info = tier_info(&suite, USER1, None);
+    assert_eq!(info.next_tier, Some(3));
+    assert_eq!(info.usd_to_next_tier, Uint128::new(1000));
+    let orai_needed = info.orai_to_next_tier.u128();
+    match suite
+        .execute(USER1, deposit(), &coins(orai_needed, "orai"))
+        .unwrap()
+    {
+        ExecuteResponse::Deposit { tier, .. } => assert_eq!(tier, 3),
+        _ => unreachable!(),
+    }
+
+    // the price guard that rejects a deposit also rejects the quote
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(PriceGuard {
+            max_age: 600,
+            max_deviation_bps: 500,
+            twap_window: 3_600,
+        }),
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    let stale = ContractError::StalePrice { max_age: 600 };
+    let err = suite
+        .try_query(QueryMsg::TierInfo {
+            address: USER1.to_string(),
+            nft_token_ids: None,
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains(&stale.to_string()));
+}
+
+fn simulate_deposit(suite: &Suite, address: &str, target_tier: u8) -> SimulateDepositResponse {
+    match suite.query(QueryMsg::SimulateDeposit {
+        address: address.to_string(),
+        target_tier,
+    }) {
+        QueryResponse::SimulateDeposit(simulation) => simulation,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn simulated_deposits_reach_the_target_tier() {
+    let mut suite = Suite::new();
+    let invalid_target = |suite: &Suite, target_tier: u8| {
+        let err = suite
+            .try_query(QueryMsg::SimulateDeposit {
+                address: USER1.to_string(),
+                target_tier,
+            })
+            .unwrap_err();
+        let expected = ContractError::InvalidTargetTier { tier: target_tier };
+        assert!(err.to_string().contains(&expected.to_string()));
+    };
+    invalid_target(&suite, 0);
+    invalid_target(&suite, 5);
+
+    // at 3 USD per ORAI the amount needed is rounded up
+    suite.set_router_rate(3_000_000);
+    let simulation = simulate_deposit(&suite, USER1, 3);
+    assert_eq!(simulation.usd_needed, Uint128::new(2000));
+    assert_eq!(simulation.orai_needed, Uint128::new(666_666_667));
+    assert_eq!(simulation.tier, 3);
+    assert_eq!(simulation.orai_refund, Uint128::zero());
+
+    let balance = suite.orai_balance(USER1);
+    match suite
+        .execute(
+            USER1,
+            deposit(),
+            &coins(simulation.orai_needed.u128(), "orai"),
+        )
+        .unwrap()
+    {
+        ExecuteResponse::Deposit {
+            tier, orai_deposit, ..
+        } => {
+            assert_eq!(tier, simulation.tier);
+            assert_eq!(
+                orai_deposit,
+                simulation.orai_needed - simulation.orai_refund
+            );
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(
+        suite.orai_balance(USER1),
+        balance - simulation.orai_needed.u128() + simulation.orai_refund.u128()
+    );
+    invalid_target(&suite, 3);
+    invalid_target(&suite, 4);
+
+    let simulation = simulate_deposit(&suite, USER1, 1);
+    assert_eq!(simulation.usd_needed, Uint128::new(2000));
+    assert_eq!(simulation.tier, 1);
+
+    // once ORAI halves past the grace period the deposit is quoted from tier 4
+    let msg = ExecuteMsg::UpdateTierPolicy {
+        tier_policy: TierPolicy::MarkToMarket { grace_period: 0 },
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    suite.set_router_rate(1_500_000);
+    let simulation = simulate_deposit(&suite, USER1, 3);
+    assert_eq!(simulation.usd_needed, Uint128::new(1000));
+    assert_eq!(simulation.orai_needed, Uint128::new(666_666_667));
+    match suite
+        .execute(
+            USER1,
+            deposit(),
+            &coins(simulation.orai_needed.u128(), "orai"),
+        )
+        .unwrap()
+    {
+        ExecuteResponse::Deposit { tier, .. } => assert_eq!(tier, simulation.tier),
+        _ => unreachable!(),
+    }
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_usd_deposit, Uint128::new(2000));
+            assert_eq!(stats.users_per_tier, vec![0, 0, 1]);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn recv_tokens_rejects_unknown_purchase_indices() {
+    let mut suite = Suite::new();
+    let ido_id = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Empty {
+            with: Some(vec![USER1.to_string()]),
+        },
+    );
+    suite
+        .execute(USER1, deposit(), &coins(orai(4000), "orai"))
+        .unwrap();
+    suite.advance_time(100);
+    suite
+        .execute(USER1, buy(ido_id, 0), &coins(400, "orai"))
+        .unwrap();
+    suite
+        .execute(USER3, buy(ido_id, 0), &coins(100, "orai"))
+        .unwrap();
+    suite.advance_time(1_000 + LOCK_PERIODS[0]);
+
+    let msg = ExecuteMsg::RecvTokens {
+        ido_id,
+        start: Some(0),
+        limit: Some(1),
+        purchase_indices: Some(vec![5]),
+        padding: None,
+    };
+    let err = suite.execute(USER1, msg, &[]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPurchaseIndex { index: 5 });
+
+    match suite.execute(USER1, recv(ido_id), &[]).unwrap() {
+        ExecuteResponse::RecvTokens {
+            amount,
+            ido_success,
+            ..
+        } => {
+            assert_eq!(amount, Uint128::new(4_000));
+            assert!(ido_success);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn refresh_and_deposit_in_the_same_block() {
+    let mut suite = Suite::new();
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(PriceGuard {
+            max_age: 600,
+            max_deviation_bps: 500,
+            twap_window: 3_600,
+        }),
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    let refresh = ExecuteMsg::RefreshPrice { padding: None };
+    suite.execute(USER3, refresh.clone(), &[]).unwrap();
+
+    // a pushed quote is cached clamped, and a deposit in the same block still sees the TWAP
+    let price = Decimal::from_ratio(ROUTER_RATE, 1_000_000u128);
+    let skewed = Decimal::from_ratio(ROUTER_RATE * 11 / 10, 1_000_000u128);
+    let clamped = Decimal::from_ratio(ROUTER_RATE * 105 / 100, 1_000_000u128);
+    suite.set_router_rate(ROUTER_RATE * 11 / 10);
+    match suite.execute(USER3, refresh.clone(), &[]).unwrap() {
+        ExecuteResponse::RefreshPrice { price: cached, .. } => assert_eq!(cached, clamped),
+        _ => unreachable!(),
+    }
+    let err = suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PriceDeviation {
+            price: skewed,
+            reference: price,
+        }
+    );
+
+    // a quote within the bound is cached as is and backs a deposit in the same block
+    let moved = Decimal::from_ratio(ROUTER_RATE * 103 / 100, 1_000_000u128);
+    suite.set_router_rate(ROUTER_RATE * 103 / 100);
+    match suite.execute(USER3, refresh.clone(), &[]).unwrap() {
+        ExecuteResponse::RefreshPrice { price: cached, .. } => assert_eq!(cached, moved),
+        _ => unreachable!(),
+    }
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // a real 10% move stays refreshable and is let through once the TWAP has followed it
+    suite.set_router_rate(ROUTER_RATE * 11 / 10);
+    let mut refreshes = 0;
+    while suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .is_err()
+    {
+        assert!(refreshes < 10, "the TWAP never caught up");
+        suite.advance_time(600);
+        suite.execute(USER3, refresh.clone(), &[]).unwrap();
+        refreshes += 1;
+    }
+    assert!(refreshes > 1);
+}
+
+#[test]
+fn mark_to_market_tiers_need_a_price() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+    let msg = ExecuteMsg::UpdateTierPolicy {
+        tier_policy: TierPolicy::MarkToMarket {
+            grace_period: 3_600,
+        },
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(PriceGuard {
+            max_age: 600,
+            max_deviation_bps: 500,
+            twap_window: 3_600,
+        }),
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    let ido_id = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Empty {
+            with: Some(vec![USER1.to_string()]),
+        },
+    );
+    suite.advance_time(100);
+
+    // without a fresh price the stored tier 2 is not used as a stand-in
+    let stale = ContractError::StalePrice { max_age: 600 };
+    let err = suite
+        .execute(USER1, buy(ido_id, 0), &coins(100, "orai"))
+        .unwrap_err();
+    assert_eq!(err, stale);
+    let err = suite.try_query(QueryMsg::TierInfo {
+        address: USER1.to_string(),
+        nft_token_ids: None,
+    });
+    assert!(err.unwrap_err().to_string().contains(&stale.to_string()));
+
+    suite
+        .execute(USER3, ExecuteMsg::RefreshPrice { padding: None }, &[])
+        .unwrap();
+    assert_eq!(tier_info(&suite, USER1, None).stake_tier, 2);
+    suite
+        .execute(USER1, buy(ido_id, 0), &coins(100, "orai"))
+        .unwrap();
+}
+
+#[test]
+fn ido_rejections_come_before_the_tier_lookup() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+    let msg = ExecuteMsg::UpdateTierPolicy {
+        tier_policy: TierPolicy::MarkToMarket {
+            grace_period: 3_600,
+        },
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(PriceGuard {
+            max_age: 600,
+            max_deviation_bps: 500,
+            twap_window: 3_600,
+        }),
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    let ido_id = suite.start_ido(
+        PaymentMethod::Native,
+        Whitelist::Empty {
+            with: Some(vec![USER1.to_string()]),
+        },
+    );
+
+    // the tier of USER1 can't be priced, but the IDO turns the purchase down first
+    let err = suite
+        .execute(USER1, buy(ido_id, 0), &coins(100, "orai"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::IdoNotActive {});
+    let simulation = simulate_buy(&suite, ido_id, USER1, 1_000, None);
+    assert_eq!(simulation.rejection, Some(BuyRejection::IdoNotActive));
+    assert_eq!(simulation.tier, 5);
+
+    suite.advance_time(100);
+    let simulation = simulate_buy(&suite, ido_id, USER1, 0, None);
+    assert_eq!(simulation.rejection, Some(BuyRejection::ZeroAmount));
+    let err = suite
+        .try_query(QueryMsg::SimulateBuy {
+            ido_id,
+            address: USER1.to_string(),
+            amount: Uint128::new(1_000),
+            viewing_key: None,
+        })
+        .unwrap_err();
+    let stale = ContractError::StalePrice { max_age: 600 };
+    assert!(err.to_string().contains(&stale.to_string()));
+}
+
+#[test]
+fn partial_tier_withdrawals_need_a_price() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(4000), "orai"))
+        .unwrap();
+    let msg = ExecuteMsg::UpdatePriceGuard {
+        price_guard: Some(PriceGuard {
+            max_age: 600,
+            max_deviation_bps: 500,
+            twap_window: 3_600,
+        }),
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+
+    // the rest of a partial withdrawal can't be re-tiered without a cached price
+    let err = suite
+        .execute(USER1, withdraw_from_tier(Some(orai(1000))), &[])
+        .unwrap_err();
+    assert_eq!(err, ContractError::StalePrice { max_age: 600 });
+    suite.execute(USER1, withdraw_from_tier(None), &[]).unwrap();
+    assert_eq!(withdrawal_amounts(&suite, USER1), vec![orai(4000)]);
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::zero());
+            assert_eq!(stats.total_usd_deposit, Uint128::zero());
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn partial_early_exits_leave_the_penalty_to_others() {
+    let mut suite = Suite::new();
+    let msg = ExecuteMsg::UpdateTierLocks {
+        tier_locks: TierLocks {
+            min_lock_durations: vec![400, 300, 200, 100],
+            early_exit: EarlyExitPolicy::Penalty {
+                bps: 1_000,
+                recipient: PenaltyRecipient::Stakers {},
+            },
+        },
+        padding: None,
+    };
+    suite.execute(ADMIN, msg, &[]).unwrap();
+    suite
+        .execute(USER1, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+
+    // USER1 keeps half staked, but the whole penalty goes to USER2
+    suite
+        .execute(USER1, withdraw_from_tier(Some(orai(500))), &[])
+        .unwrap();
+    assert_eq!(pending_rewards(&suite, USER1), 0);
+    assert_eq!(pending_rewards(&suite, USER2), orai(50));
+
+    // the rest of USER1's deposit still earns from the next penalty
+    suite
+        .execute(USER2, withdraw_from_tier(Some(orai(500))), &[])
+        .unwrap();
+    assert_eq!(pending_rewards(&suite, USER1), orai(50));
+    assert_eq!(pending_rewards(&suite, USER2), orai(50));
+}
+
+#[test]
+fn slashes_reach_unbonding_epochs() {
+    let mut suite = Suite::new();
+    suite
+        .execute(USER1, deposit(), &coins(orai(3000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER2, deposit(), &coins(orai(1000), "orai"))
+        .unwrap();
+    suite
+        .execute(USER1, withdraw_from_tier(Some(orai(1000))), &[])
+        .unwrap();
+    suite
+        .execute(USER3, process_unbonding_epoch(), &[])
+        .unwrap();
+
+    // the chain takes a quarter of VALIDATOR1's 60% of the unbonding entries as well
+    suite
+        .app
+        .sudo(SudoMsg::Staking(StakingSudo::Slash {
+            validator: VALIDATOR1.to_string(),
+            percentage: Decimal::percent(25),
+        }))
+        .unwrap();
+    match suite
+        .execute(
+            USER3,
+            ExecuteMsg::ReconcileDelegations { padding: None },
+            &[],
+        )
+        .unwrap()
+    {
+        ExecuteResponse::ReconcileDelegations { slashed, ratio, .. } => {
+            assert_eq!(slashed, Uint128::new(orai(450)));
+            assert_eq!(ratio, Decimal::percent(85));
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(withdrawal_amounts(&suite, USER1), vec![orai(850)]);
+    match suite.query(QueryMsg::StakingStats {}) {
+        QueryResponse::StakingStats(stats) => {
+            assert_eq!(stats.total_orai_deposit, Uint128::new(orai(2550)));
+            assert_eq!(stats.total_pending_withdrawals, Uint128::new(orai(850)));
+        }
+        _ => unreachable!(),
+    }
+
+    suite.advance_time(UNBOUND_LATENCY);
+    let claim = ExecuteMsg::Claim {
+        recipient: None,
+        start: None,
+        limit: None,
+        padding: None,
+    };
+    match suite.execute(USER1, claim, &[]).unwrap() {
+        ExecuteResponse::Claim { amount, .. } => assert_eq!(amount, Uint128::new(orai(850))),
+        _ => unreachable!(),
+    }
+    assert_eq!(suite.orai_balance(suite.launchpad.as_str()), 0);
+}
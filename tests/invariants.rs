@@ -0,0 +1,438 @@
+//! Drives seeded random sequences of launchpad messages and checks the accounting
+//! invariants after every step.
+mod common;
+
+use common::*;
+use cosmwasm_std::{coins, Uint128};
+use cw_template::contract::UNBOUND_LATENCY;
+use cw_template::msg::{
+    ExecuteMsg, IdoInfoResponse, PaymentMethod, QueryMsg, QueryResponse, Unbonding,
+    UserInfoResponse, Whitelist,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const SEEDS: u64 = 8;
+const STEPS: usize = 120;
+const USERS: [&str; 3] = [USER1, USER2, USER3];
+const TOKENS_PER_TIER: [u128; 5] = [4_000, 3_000, 2_000, 1_000, 1_000];
+const MAX_IDOS: usize = 4;
+
+struct Harness {
+    suite: Suite,
+    rng: StdRng,
+    idos: Vec<u32>,
+}
+
+impl Harness {
+    fn new(seed: u64) -> Self {
+        Harness {
+            suite: Suite::new(),
+            rng: StdRng::seed_from_u64(seed),
+            idos: vec![],
+        }
+    }
+
+    fn user(&mut self) -> &'static str {
+        USERS[self.rng.gen_range(0..USERS.len())]
+    }
+
+    fn ido(&mut self) -> Option<u32> {
+        if self.idos.is_empty() {
+            return None;
+        }
+        Some(self.idos[self.rng.gen_range(0..self.idos.len())])
+    }
+
+    /// Runs one random action. Rejections with a `ContractError` are part of the
+    /// exploration; anything else panics inside `Suite::execute`.
+    fn step(&mut self) -> String {
+        match self.rng.gen_range(0..12) {
+            0 if self.idos.len() < MAX_IDOS => {
+                let payment = if self.rng.gen_bool(0.5) {
+                    PaymentMethod::Native
+                } else {
+                    PaymentMethod::Token {
+                        contract: self.suite.payment_token.to_string(),
+                    }
+                };
+                let whitelist = if self.rng.gen_bool(0.7) {
+                    Whitelist::Shared { with_blocked: None }
+                } else {
+                    Whitelist::Empty {
+                        with: Some(vec![USER1.to_string()]),
+                    }
+                };
+                let ido_id = self.suite.start_ido(payment.clone(), whitelist);
+                self.idos.push(ido_id);
+                format!("start ido {} paid with {:?}", ido_id, payment)
+            }
+            1 | 2 => {
+                let Some(ido_id) = self.ido() else {
+                    return "skip buy".to_string();
+                };
+                let user = self.user();
+                let ido = ido_info(&self.suite, ido_id);
+                let result = if matches!(ido.payment, PaymentMethod::Native) {
+                    let orai = self.rng.gen_range(1..=150);
+                    let msg = buy_msg(ido_id, 0);
+                    self.suite.execute(user, msg, &coins(orai, "orai"))
+                } else {
+                    let amount = self.rng.gen_range(ido.price.u128()..=1_500);
+                    let token = self.suite.payment_token.clone();
+                    self.suite.approve(user, &token, amount / ido.price.u128());
+                    self.suite.execute(user, buy_msg(ido_id, amount), &[])
+                };
+                format!("{} buys in ido {}: {:?}", user, ido_id, result)
+            }
+            3 => {
+                let Some(ido_id) = self.ido() else {
+                    return "skip recv".to_string();
+                };
+                let user = self.user();
+                let msg = ExecuteMsg::RecvTokens {
+                    ido_id,
+                    start: None,
+                    limit: None,
+                    purchase_indices: None,
+                    padding: None,
+                };
+                let result = self.suite.execute(user, msg, &[]);
+                format!("{} receives from ido {}: {:?}", user, ido_id, result)
+            }
+            4 => {
+                let Some(ido_id) = self.ido() else {
+                    return "skip withdraw".to_string();
+                };
+                let msg = ExecuteMsg::Withdraw {
+                    ido_id,
+                    padding: None,
+                };
+                let result = self.suite.execute(ADMIN, msg, &[]);
+                format!("admin withdraws ido {}: {:?}", ido_id, result)
+            }
+            5 => {
+                let user = self.user();
+                let usd = USD_DEPOSITS[self.rng.gen_range(0..USD_DEPOSITS.len())];
+                let msg = ExecuteMsg::Deposit { padding: None };
+                let result = self.suite.execute(user, msg, &coins(orai(usd), "orai"));
+                format!("{} deposits {} usd: {:?}", user, usd, result)
+            }
+            6 => {
+                let user = self.user();
+                // whole deposits, or a tier's worth of it
+                let amount = match self.rng.gen_range(0..2) {
+                    0 => None,
+                    _ => Some(orai(
+                        USD_DEPOSITS[self.rng.gen_range(0..USD_DEPOSITS.len())],
+                    )),
+                };
+                let msg = ExecuteMsg::WithdrawFromTier {
+                    amount: amount.map(Uint128::new),
+                    padding: None,
+                };
+                let result = self.suite.execute(user, msg, &[]);
+                format!("{} withdraws {:?} from tier: {:?}", user, amount, result)
+            }
+            7 => {
+                let user = self.user();
+                let msg = ExecuteMsg::Claim {
+                    recipient: None,
+                    start: None,
+                    limit: None,
+                    padding: None,
+                };
+                let result = self.suite.execute(user, msg, &[]);
+                format!("{} claims: {:?}", user, result)
+            }
+            9 => {
+                let user = self.user();
+                let (action, msg) = match self.rng.gen_range(0..4) {
+                    0 => ("harvests", ExecuteMsg::WithdrawRewards { padding: None }),
+                    1 => (
+                        "claims staking rewards",
+                        ExecuteMsg::ClaimStakingRewards { padding: None },
+                    ),
+                    2 => (
+                        "toggles auto compound",
+                        ExecuteMsg::SetAutoCompound {
+                            enabled: self.rng.gen_bool(0.7),
+                            padding: None,
+                        },
+                    ),
+                    _ => (
+                        "compounds",
+                        ExecuteMsg::Compound {
+                            addresses: USERS.iter().map(|user| user.to_string()).collect(),
+                            padding: None,
+                        },
+                    ),
+                };
+                let result = self.suite.execute(user, msg, &[]);
+                format!("{} {}: {:?}", user, action, result)
+            }
+            10 => {
+                if self.rng.gen_bool(0.3) {
+                    self.suite.advance_time(Unbonding::default().epoch_duration);
+                }
+                let user = self.user();
+                let msg = ExecuteMsg::ProcessUnbondingEpoch { padding: None };
+                let result = self.suite.execute(user, msg, &[]);
+                format!("{} processes the unbonding epoch: {:?}", user, result)
+            }
+            8 if self.rng.gen_bool(0.1) => {
+                self.suite.advance_time(UNBOUND_LATENCY);
+                "advance past unbonding".to_string()
+            }
+            _ => {
+                let seconds = self.rng.gen_range(1..=300);
+                self.suite.advance_time(seconds);
+                format!("advance {} seconds", seconds)
+            }
+        }
+    }
+
+    fn check(&self) {
+        self.check_idos();
+        self.check_staking();
+    }
+
+    fn check_idos(&self) {
+        let suite = &self.suite;
+        let now = suite.now();
+        let mut ido_token_owed = 0;
+        let mut payment_token_owed = 0;
+        let mut orai_owed = 0;
+
+        for &ido_id in &self.idos {
+            let ido = ido_info(suite, ido_id);
+            let users: Vec<UserInfoResponse> = USERS
+                .iter()
+                .map(|user| user_info(suite, user, Some(ido_id)))
+                .collect();
+
+            let mut purchased = 0;
+            for (user, info) in USERS.iter().zip(&users) {
+                let pending = purchases_total(suite, ido_id, user, false);
+                let archived = purchases_total(suite, ido_id, user, true);
+                purchased += pending + archived;
+                // refunds reset the user's info but keep the purchase history
+                if info.total_payment.is_zero() {
+                    assert!(info.total_tokens_received.is_zero());
+                    continue;
+                }
+                assert_eq!(info.total_tokens_bought.u128(), pending + archived);
+                assert_eq!(info.total_tokens_received.u128(), archived);
+            }
+            assert_eq!(ido.sold_amount.u128(), purchased, "ido {}", ido_id);
+
+            let mut sold_from_tiers = 0;
+            for (initial, remaining) in TOKENS_PER_TIER.iter().zip(&ido.remaining_per_tiers) {
+                assert!(remaining.u128() <= *initial, "ido {}", ido_id);
+                sold_from_tiers += initial - remaining.u128();
+            }
+            assert_eq!(ido.sold_amount.u128(), sold_from_tiers, "ido {}", ido_id);
+
+            let bought: u128 = users.iter().map(|i| i.total_tokens_bought.u128()).sum();
+            let received: u128 = users.iter().map(|i| i.total_tokens_received.u128()).sum();
+            let paid: u128 = users.iter().map(|i| i.total_payment.u128()).sum();
+            let failed = now >= ido.end_time && ido.soft_cap > ido.sold_amount;
+
+            ido_token_owed += match (ido.withdrawn, failed) {
+                (false, _) => ido.total_tokens_amount.u128() - received,
+                (true, true) => 0,
+                (true, false) => bought - received,
+            };
+            let payment_owed = if failed {
+                paid
+            } else if ido.withdrawn {
+                0
+            } else {
+                ido.total_payment.u128()
+            };
+            if matches!(ido.payment, PaymentMethod::Native) {
+                orai_owed += payment_owed;
+            } else {
+                payment_token_owed += payment_owed;
+            }
+        }
+
+        let launchpad = suite.launchpad.as_str();
+        assert!(suite.token_balance(&suite.token, launchpad) >= ido_token_owed);
+        assert!(suite.token_balance(&suite.payment_token, launchpad) >= payment_token_owed);
+
+        let mut matured_withdrawals = 0;
+        for user in USERS {
+            for withdrawal in withdrawals(suite, user) {
+                if !withdrawal.2 && withdrawal.1 <= now {
+                    matured_withdrawals += withdrawal.0;
+                }
+            }
+        }
+        assert!(suite.orai_balance(launchpad) >= orai_owed + matured_withdrawals);
+
+        for user in USERS {
+            let total = user_info(suite, user, None);
+            let per_ido: Vec<UserInfoResponse> = self
+                .idos
+                .iter()
+                .map(|ido_id| user_info(suite, user, Some(*ido_id)))
+                .collect();
+            let sum = |field: fn(&UserInfoResponse) -> Uint128| -> Uint128 {
+                per_ido.iter().map(field).sum()
+            };
+            assert_eq!(total.total_payment, sum(|i| i.total_payment), "{}", user);
+            assert_eq!(
+                total.total_tokens_bought,
+                sum(|i| i.total_tokens_bought),
+                "{}",
+                user
+            );
+            assert_eq!(
+                total.total_tokens_received,
+                sum(|i| i.total_tokens_received),
+                "{}",
+                user
+            );
+        }
+    }
+
+    fn check_staking(&self) {
+        let suite = &self.suite;
+        let stats = match suite.query(QueryMsg::StakingStats {}) {
+            QueryResponse::StakingStats(stats) => stats,
+            _ => unreachable!(),
+        };
+
+        let delegated: u128 = [VALIDATOR1, VALIDATOR2, VALIDATOR3]
+            .iter()
+            .map(|validator| suite.delegation(validator))
+            .sum();
+        let open_epoch = match suite.query(QueryMsg::UnbondingEpoch { id: None }) {
+            QueryResponse::UnbondingEpoch(epoch) => epoch,
+            _ => unreachable!(),
+        };
+        // harvested rewards stay delegated until they are claimed, and withdrawals until
+        // their epoch is processed
+        assert_eq!(
+            delegated,
+            stats.total_orai_deposit.u128()
+                + stats.outstanding_rewards.u128()
+                + open_epoch.amount.u128()
+        );
+        let ledger = match suite.query(QueryMsg::Delegations {}) {
+            QueryResponse::Delegations(ledger) => ledger.delegations,
+            _ => unreachable!(),
+        };
+        for delegation in &ledger {
+            assert_eq!(
+                delegation.delegated, delegation.live,
+                "{}",
+                delegation.validator
+            );
+        }
+
+        let mut deposited = 0;
+        let mut pending = 0;
+        let mut users = 0;
+        for user in USERS {
+            if let QueryResponse::TierUserInfo(info) = suite.query(QueryMsg::TierUserInfo {
+                address: user.to_string(),
+            }) {
+                if !info.orai_deposit.is_zero() {
+                    users += 1;
+                }
+                deposited += info.orai_deposit.u128();
+            }
+            pending += withdrawals(suite, user).iter().map(|w| w.0).sum::<u128>();
+        }
+        assert_eq!(deposited, stats.total_orai_deposit.u128());
+        assert_eq!(pending, stats.total_pending_withdrawals.u128());
+        assert_eq!(users, stats.users_per_tier.iter().sum::<u64>());
+    }
+}
+
+fn buy_msg(ido_id: u32, amount: u128) -> ExecuteMsg {
+    ExecuteMsg::BuyTokens {
+        ido_id,
+        amount: Uint128::new(amount),
+        viewing_key: None,
+        padding: None,
+    }
+}
+
+fn ido_info(suite: &Suite, ido_id: u32) -> IdoInfoResponse {
+    match suite.query(QueryMsg::IdoInfo { ido_id }) {
+        QueryResponse::IdoInfo(info) => info,
+        _ => unreachable!(),
+    }
+}
+
+fn user_info(suite: &Suite, user: &str, ido_id: Option<u32>) -> UserInfoResponse {
+    match suite.query(QueryMsg::UserInfo {
+        address: user.to_string(),
+        ido_id,
+    }) {
+        QueryResponse::UserInfo(info) => info,
+        _ => unreachable!(),
+    }
+}
+
+fn purchases_total(suite: &Suite, ido_id: u32, user: &str, archived: bool) -> u128 {
+    let address = user.to_string();
+    let purchases = if archived {
+        match suite.query(QueryMsg::ArchivedPurchases {
+            ido_id,
+            address,
+            start: 0,
+            limit: 1_000,
+        }) {
+            QueryResponse::ArchivedPurchases(response) => response.purchases,
+            _ => unreachable!(),
+        }
+    } else {
+        match suite.query(QueryMsg::Purchases {
+            ido_id,
+            address,
+            start: None,
+            limit: Some(1_000),
+        }) {
+            QueryResponse::Purchases(response) => response.purchases,
+            _ => unreachable!(),
+        }
+    };
+    purchases.iter().map(|p| p.tokens_amount.u128()).sum()
+}
+
+/// `(amount, claim_time, queued)` of every pending withdrawal of `user`.
+fn withdrawals(suite: &Suite, user: &str) -> Vec<(u128, u64, bool)> {
+    match suite.query(QueryMsg::Withdrawals {
+        address: user.to_string(),
+        start: None,
+        limit: Some(1_000),
+    }) {
+        QueryResponse::Withdrawals(response) => response
+            .withdrawals
+            .iter()
+            .map(|w| (w.amount.u128(), w.claim_time, w.queued))
+            .collect(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn random_sequences_keep_accounting_invariants() {
+    for seed in 0..SEEDS {
+        let mut harness = Harness::new(seed);
+        let mut log = vec![];
+        for _ in 0..STEPS {
+            log.push(harness.step());
+            let checked =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| harness.check()));
+            if let Err(err) = checked {
+                eprintln!("seed {} failed after:\n{}", seed, log.join("\n"));
+                std::panic::resume_unwind(err);
+            }
+        }
+    }
+}